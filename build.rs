@@ -102,6 +102,7 @@ fn write_database_tests(path: &Path) {
         test_file,
         r#"// THIS FILE IS AUTOGENERATED - DO NOT EDIT!
 use triseratops::library::database;
+use std::io::Cursor;
 "#
     )
     .unwrap();
@@ -118,7 +119,7 @@ use triseratops::library::database;
 
         write_database_test(
             &mut test_file,
-            directory.join("database V2").as_path(),
+            directory.join("_Serato_").join("database V2").as_path(),
             db_name,
         );
     }
@@ -0,0 +1,437 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Helpers that are shared by the converters.
+
+#[cfg(any(feature = "rekordbox", feature = "traktor", feature = "mixxx"))]
+use crate::error::Error;
+#[cfg(any(feature = "rekordbox", feature = "traktor"))]
+use crate::library::SubcrateNode;
+#[cfg(any(feature = "rekordbox", feature = "traktor", feature = "mixxx"))]
+use crate::library::{Library, SUBCRATE_SEPARATOR, Subcrate, subcrate_full_name};
+#[cfg(any(feature = "rekordbox", feature = "traktor", feature = "mixxx"))]
+use crate::tag::Beatgrid;
+use crate::tag::beatgrid::{NonTerminalMarker, TerminalMarker};
+#[cfg(any(feature = "rekordbox", feature = "traktor", feature = "mixxx"))]
+use crate::tag::color::Color;
+#[cfg(any(feature = "rekordbox", feature = "traktor", feature = "mixxx"))]
+use crate::tag::generic::{Cue, Loop, Position};
+#[cfg(any(feature = "rekordbox", feature = "traktor", feature = "mixxx"))]
+use std::collections::HashSet;
+#[cfg(any(feature = "rekordbox", feature = "traktor"))]
+use std::path::Path;
+#[cfg(any(feature = "rekordbox", feature = "traktor", feature = "mixxx"))]
+use std::path::PathBuf;
+#[cfg(any(feature = "rekordbox", feature = "traktor", feature = "mixxx"))]
+use std::time::SystemTime;
+
+/// Number of hotcue slots in Serato DJ.
+#[cfg(any(feature = "rekordbox", feature = "traktor", feature = "mixxx"))]
+const NUM_CUES: u8 = 8;
+
+/// Number of saved loop slots in Serato DJ.
+#[cfg(any(feature = "rekordbox", feature = "traktor", feature = "mixxx"))]
+const NUM_LOOPS: u8 = 8;
+
+/// Color of imported saved loops (the color that Serato DJ uses for new loops).
+#[cfg(any(feature = "rekordbox", feature = "traktor", feature = "mixxx"))]
+const LOOP_COLOR: Color = Color {
+    red: 0x27,
+    green: 0xAA,
+    blue: 0xE1,
+};
+
+/// A beatgrid marker with a constant tempo until the next marker.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TempoMarker {
+    /// The position in seconds.
+    pub position: f64,
+    /// The tempo in beats per minute.
+    pub bpm: f64,
+    /// The number of beats before this marker (counted from the first marker).
+    pub beat_index: u64,
+}
+
+/// Converts the Serato beatgrid markers to markers with a tempo.
+///
+/// Serato only stores the tempo for the terminal marker. For non-terminal markers, it is
+/// calculated from the number of beats and the distance to the next marker.
+pub(crate) fn tempo_markers(
+    non_terminal_markers: &[NonTerminalMarker],
+    terminal_marker: &TerminalMarker,
+) -> Vec<TempoMarker> {
+    let terminal_bpm = f64::from(terminal_marker.bpm);
+    let mut markers = vec![];
+    let mut beat_index = 0;
+    for (i, marker) in non_terminal_markers.iter().enumerate() {
+        let position = f64::from(marker.position);
+        let next_position = f64::from(
+            non_terminal_markers
+                .get(i + 1)
+                .map_or(terminal_marker.position, |next| next.position),
+        );
+        let bpm = if next_position > position && marker.beats_till_next_marker > 0 {
+            60.0 * f64::from(marker.beats_till_next_marker) / (next_position - position)
+        } else {
+            terminal_bpm
+        };
+        markers.push(TempoMarker {
+            position,
+            bpm,
+            beat_index,
+        });
+        beat_index += u64::from(marker.beats_till_next_marker);
+    }
+    markers.push(TempoMarker {
+        position: f64::from(terminal_marker.position),
+        bpm: terminal_bpm,
+        beat_index,
+    });
+    markers
+}
+
+/// Returns the average tempo between the first and the last marker.
+///
+/// If there is only a single marker, its tempo is returned.
+#[cfg(any(feature = "traktor", feature = "mixxx", feature = "enginedj"))]
+pub(crate) fn average_bpm(markers: &[TempoMarker]) -> Option<f64> {
+    let (first, last) = (markers.first()?, markers.last()?);
+    if last.beat_index > 0 && last.position > first.position {
+        return Some(60.0 * last.beat_index as f64 / (last.position - first.position));
+    }
+    Some(last.bpm)
+}
+
+/// Returns the (UTC) calendar date of the given time as `(year, month, day)`.
+#[cfg(any(feature = "rekordbox", feature = "traktor", feature = "mixxx"))]
+pub(crate) fn date(time: SystemTime) -> (i64, u32, u32) {
+    let seconds = match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(duration) => i64::try_from(duration.as_secs()).unwrap_or(i64::MAX),
+        Err(err) => -i64::try_from(err.duration().as_secs()).unwrap_or(i64::MAX),
+    };
+    // Converts days since 1970-01-01 to a date in the proleptic Gregorian calendar, see
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = seconds.div_euclid(86400) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (
+        year,
+        u32::try_from(month).unwrap_or_default(),
+        u32::try_from(day).unwrap_or_default(),
+    )
+}
+
+/// Converts a position in seconds to a [`Position`].
+#[cfg(any(feature = "rekordbox", feature = "traktor", feature = "mixxx"))]
+pub(crate) fn position(seconds: f64) -> Position {
+    // Positions are always positive and Serato can't store positions after 1193 hours anyway.
+    let millis = (seconds * 1000.0).round().clamp(0.0, f64::from(u32::MAX)) as u32;
+    Position { millis }
+}
+
+/// A cue or loop of another DJ software.
+#[cfg(any(feature = "rekordbox", feature = "traktor", feature = "mixxx"))]
+#[derive(Debug, Clone)]
+pub(crate) struct ForeignMarker<'a> {
+    pub name: &'a str,
+    /// The start position in seconds.
+    pub start: f64,
+    /// The end position in seconds (for loops).
+    pub end: Option<f64>,
+    /// The hotcue slot (starting at 0), or `None` for memory cues and loops.
+    pub hotcue: Option<u8>,
+    pub color: Option<Color>,
+}
+
+/// Converts cues to Serato cues.
+///
+/// Hot cues keep their slot. Memory cues and hot cues in slots that Serato DJ doesn't have are
+/// assigned to the free slots (in the order of their position), unless there is already a cue at
+/// the same position. Colors are mapped to the closest Serato hotcue colors, cues without a color
+/// get the default color of their slot.
+#[cfg(any(feature = "rekordbox", feature = "traktor", feature = "mixxx"))]
+pub(crate) fn import_cues(markers: &[ForeignMarker<'_>]) -> Vec<Cue> {
+    let cue = |marker: &ForeignMarker<'_>, index, position| Cue {
+        index,
+        position,
+        color: marker.color.map_or_else(
+            || Color::default_hotcue_color(index),
+            Color::into_nearest_hotcue_color,
+        ),
+        label: marker.name.to_string(),
+    };
+
+    let mut cues: Vec<Cue> = vec![];
+    for marker in markers {
+        let Some(index) = marker.hotcue.filter(|index| *index < NUM_CUES) else {
+            continue;
+        };
+        if cues.iter().all(|cue| cue.index != index) {
+            cues.push(cue(marker, index, position(marker.start)));
+        }
+    }
+
+    let mut memory_cues: Vec<_> = markers
+        .iter()
+        .filter(|marker| marker.hotcue.is_none_or(|index| index >= NUM_CUES))
+        .map(|marker| (position(marker.start), marker))
+        .collect();
+    memory_cues.sort_by_key(|(position, _)| *position);
+    for (position, marker) in memory_cues {
+        if cues
+            .iter()
+            .any(|cue| cue.position.millis.abs_diff(position.millis) <= 1)
+        {
+            continue;
+        }
+        let Some(index) = (0..NUM_CUES).find(|index| cues.iter().all(|cue| cue.index != *index))
+        else {
+            break;
+        };
+        cues.push(cue(marker, index, position));
+    }
+    cues.sort_by_key(|cue| cue.index);
+    cues
+}
+
+/// Converts loops to Serato saved loops.
+///
+/// Hot loops (in the order of their slot) come first, followed by memory loops (in the order of
+/// their position). Duplicate loops and loops without an end position are skipped.
+#[cfg(any(feature = "rekordbox", feature = "traktor", feature = "mixxx"))]
+pub(crate) fn import_loops(markers: &[ForeignMarker<'_>]) -> Vec<Loop> {
+    let mut markers: Vec<_> = markers
+        .iter()
+        .filter_map(|marker| {
+            let end = marker.end?;
+            Some((marker, position(marker.start), position(end)))
+        })
+        .filter(|(_, start, end)| end > start)
+        .collect();
+    markers.sort_by_key(|(marker, start, _)| (marker.hotcue.is_none(), marker.hotcue, *start));
+
+    let mut loops: Vec<Loop> = vec![];
+    for (marker, start_position, end_position) in markers {
+        let is_duplicate = loops.iter().any(|saved_loop| {
+            saved_loop.start_position == start_position && saved_loop.end_position == end_position
+        });
+        if is_duplicate {
+            continue;
+        }
+        let Ok(index @ 0..NUM_LOOPS) = u8::try_from(loops.len()) else {
+            break;
+        };
+        loops.push(Loop {
+            index,
+            start_position,
+            end_position,
+            color: LOOP_COLOR,
+            is_locked: false,
+            label: marker.name.to_string(),
+        });
+    }
+    loops
+}
+
+/// Converts markers with a tempo (as `(position in seconds, bpm)` pairs) to a Serato beatgrid.
+///
+/// The number of beats between two markers is rounded to the nearest integer.
+#[cfg(any(feature = "rekordbox", feature = "traktor", feature = "mixxx"))]
+pub(crate) fn import_beatgrid(mut tempos: Vec<(f64, f64)>) -> Option<Beatgrid> {
+    tempos.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    let (last_position, last_bpm) = *tempos.last()?;
+    let non_terminal_markers = tempos
+        .windows(2)
+        .map(|tempos| {
+            let [(position, bpm), (next_position, _)] = tempos else {
+                unreachable!();
+            };
+            let beats = ((next_position - position) * bpm / 60.0).round();
+            NonTerminalMarker {
+                position: *position as f32,
+                beats_till_next_marker: beats.clamp(1.0, f64::from(u32::MAX)) as u32,
+            }
+        })
+        .collect();
+    let terminal_marker = TerminalMarker {
+        position: last_position as f32,
+        bpm: last_bpm as f32,
+    };
+    Some(Beatgrid::new(non_terminal_markers, terminal_marker))
+}
+
+/// A node in the playlist tree that is exported from the subcrates.
+#[cfg(any(feature = "rekordbox", feature = "traktor"))]
+pub(crate) enum PlaylistNode {
+    Folder {
+        name: String,
+        children: Vec<PlaylistNode>,
+    },
+    Playlist {
+        name: String,
+        /// The paths of the tracks (as stored in the database).
+        track_paths: Vec<PathBuf>,
+    },
+}
+
+#[cfg(any(feature = "rekordbox", feature = "traktor"))]
+impl PlaylistNode {
+    /// Returns the playlist tree for the subcrates of the library.
+    ///
+    /// Most DJ software doesn't allow playlists that contain both tracks and other playlists, so
+    /// a crate with subcrates becomes a folder that contains a playlist with the crate's own
+    /// tracks, followed by the subcrates.
+    pub fn from_library(library: &Library) -> Result<Vec<Self>, Error> {
+        library
+            .subcrate_tree()
+            .iter()
+            .map(|node| Self::from_subcrate(library, node))
+            .collect()
+    }
+
+    fn from_subcrate(library: &Library, node: &SubcrateNode) -> Result<Self, Error> {
+        let playlist = if node.exists {
+            let subcrate = library.read_subcrate(&node.full_name)?;
+            Some(Self::Playlist {
+                name: node.name.clone(),
+                track_paths: subcrate.track_paths().map(Path::to_path_buf).collect(),
+            })
+        } else {
+            None
+        };
+        if node.children.is_empty() {
+            return Ok(playlist.unwrap_or(Self::Playlist {
+                name: node.name.clone(),
+                track_paths: vec![],
+            }));
+        }
+
+        let mut children: Vec<_> = playlist.into_iter().collect();
+        for child in &node.children {
+            children.push(Self::from_subcrate(library, child)?);
+        }
+        Ok(Self::Folder {
+            name: node.name.clone(),
+            children,
+        })
+    }
+}
+
+/// Returns a crate name for a playlist or folder name.
+///
+/// Characters that are not allowed in crate names are replaced by `-`.
+#[cfg(any(feature = "rekordbox", feature = "traktor", feature = "mixxx"))]
+fn crate_name(name: &str) -> String {
+    let name = name
+        .replace(['/', '\\'], "-")
+        .replace(SUBCRATE_SEPARATOR, "-");
+    if name.is_empty() {
+        return "-".to_string();
+    }
+    name
+}
+
+/// Writes playlists as subcrates of the given library.
+///
+/// Each playlist is given as the names of its parent folders and itself, and the paths of its
+/// tracks (as stored in the database). Folders become parent crates. Existing crates are never
+/// replaced: if a crate with the same name already exists (or two playlists in the same folder
+/// have the same name), a number is appended to the name (e.g. `Warmup (2)`). Returns the names of
+/// the written crates.
+#[cfg(any(feature = "rekordbox", feature = "traktor", feature = "mixxx"))]
+pub(crate) fn write_subcrates<'a>(
+    library: &Library,
+    playlists: impl IntoIterator<Item = (&'a [String], Vec<PathBuf>)>,
+) -> Result<Vec<String>, Error> {
+    let mut existing_crates: HashSet<_> = library.subcrates().collect();
+    let mut names = vec![];
+    for (path, track_paths) in playlists {
+        let path: Vec<_> = path.iter().map(|name| crate_name(name)).collect();
+        let path: Vec<_> = path.iter().map(String::as_str).collect();
+        for i in 1..path.len() {
+            let parent_name = subcrate_full_name(&path[..i])?;
+            if !existing_crates.contains(&parent_name) {
+                library.write_subcrate(&parent_name, &Subcrate::new())?;
+                existing_crates.insert(parent_name);
+            }
+        }
+
+        let mut subcrate = Subcrate::new();
+        for track_path in track_paths {
+            subcrate.add_track(track_path);
+        }
+        let base_name = subcrate_full_name(&path)?;
+        let mut full_name = base_name.clone();
+        let mut number = 1;
+        while existing_crates.contains(&full_name) {
+            number += 1;
+            full_name = format!("{base_name} ({number})");
+        }
+        library.write_subcrate(&full_name, &subcrate)?;
+        existing_crates.insert(full_name.clone());
+        names.push(full_name);
+    }
+    Ok(names)
+}
+
+#[test]
+fn test_tempo_markers() {
+    let non_terminal_markers = [
+        NonTerminalMarker {
+            position: 0.5,
+            beats_till_next_marker: 6,
+        },
+        NonTerminalMarker {
+            position: 3.5,
+            beats_till_next_marker: 4,
+        },
+    ];
+    let terminal_marker = TerminalMarker {
+        position: 5.5,
+        bpm: 128.0,
+    };
+    let markers = tempo_markers(&non_terminal_markers, &terminal_marker);
+    let expected = [(0.5, 120.0, 0), (3.5, 120.0, 6), (5.5, 128.0, 10)];
+    assert_eq!(markers.len(), expected.len());
+    for (marker, (position, bpm, beat_index)) in markers.iter().zip(expected) {
+        assert!((marker.position - position).abs() < 1e-6);
+        assert!((marker.bpm - bpm).abs() < 1e-6);
+        assert_eq!(marker.beat_index, beat_index);
+    }
+}
+
+#[cfg(any(feature = "rekordbox", feature = "traktor", feature = "mixxx"))]
+#[test]
+fn test_date() {
+    use std::time::Duration;
+
+    assert_eq!(date(SystemTime::UNIX_EPOCH), (1970, 1, 1));
+    let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_582_934_400);
+    assert_eq!(date(time), (2020, 2, 29));
+    let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_609_459_199);
+    assert_eq!(date(time), (2020, 12, 31));
+}
+
+#[cfg(any(feature = "rekordbox", feature = "traktor", feature = "mixxx"))]
+#[test]
+fn test_crate_name() {
+    assert_eq!(crate_name("Club / Late"), "Club - Late");
+    assert_eq!(crate_name("100%%"), "100-");
+    assert_eq!(crate_name(""), "-");
+}
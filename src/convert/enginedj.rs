@@ -0,0 +1,660 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Engine DJ databases (`Engine Library/Database2/m.db`).
+//!
+//! Denon Engine DJ (and Engine OS on the standalone players) stores its library in an SQLite
+//! database in the `Engine Library` directory, either in the music folder or at the root of a
+//! USB drive. Writing is done using the [`rusqlite`](https://lib.rs/crates/rusqlite) crate.
+//!
+//! The schema of the database is created and migrated by Engine DJ itself, so the database must
+//! already exist (i.e. Engine DJ 2.x or later has been started at least once, or the drive has
+//! been added to Engine DJ). Engine DJ must not be running while the database is modified.
+//!
+//! # Export
+//!
+//! The Serato data is mapped to the columns of the `Track` table as follows:
+//!
+//! | Serato                       | Engine DJ
+//! | ---------------------------- | ------------------------------------------------------------
+//! | Library tracks               | Rows in the `Track` table
+//! | Sample rate and length       | `trackData` blob (only if missing)
+//! | Beatgrid markers             | `beatData` blob (default and adjusted beatgrid)
+//! | Cues                         | `quickCues` blob (hot cues 1-8)
+//! | Saved loops                  | `loops` blob (saved loops 1-8)
+//! | Overview waveform            | `overviewWaveFormData` blob
+//!
+//! Except for `loops`, the blobs are compressed with zlib and prefixed with the length of the
+//! uncompressed data (as 32-bit big-endian integer). Positions are sample positions (i.e. frames),
+//! so tracks with an unknown sample rate or length are exported without cues, loops, beats and
+//! waveform. The colors of cues and loops are converted to the colors that Serato DJ Pro displays.
+//!
+//! Track paths are stored relative to the `Engine Library` directory (e.g.
+//! `../Music/Track.mp3`). Tracks that are already in the database keep their metadata, but their
+//! cues and loops are replaced. The beats and the waveform are only replaced if the Serato tags
+//! contain a beatgrid or an overview, respectively, and the track is only marked as analyzed if
+//! both have been written. Engine DJ doesn't compute the waveform
+//! overview from the file if the track has already been analyzed, so the Serato overview is
+//! converted: its 240 columns are stretched to the 1024 points of the Engine DJ overview, and the
+//! brightness of each column is used as amplitude of all three frequency bands.
+//!
+//! Subcrates are not exported.
+//!
+//! ```no_run
+//! use std::collections::HashMap;
+//! use triseratops::convert::enginedj::EngineDatabase;
+//! use triseratops::library::Library;
+//!
+//! let library = Library::read_from_path("/Volumes/USB Drive").expect("Failed to read library!");
+//! // The Serato tags of each track, read from the files (e.g. using `TagContainer::read_from_path`).
+//! let tags = HashMap::new();
+//! let mut database =
+//!     EngineDatabase::open("/Volumes/USB Drive/Engine Library").expect("Failed to open database!");
+//! database
+//!     .write_library(&library, &tags)
+//!     .expect("Failed to export library!");
+//! ```
+
+use super::common;
+use crate::error::Error;
+use crate::library::{Library, Track};
+use crate::tag::TagContainer;
+use crate::tag::beatgrid::{NonTerminalMarker, TerminalMarker};
+use crate::tag::color::Color;
+use crate::tag::generic::{Cue, Loop, Position};
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+use rusqlite::{Connection, OpenFlags, OptionalExtension, Transaction, params};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Path of the database, relative to the `Engine Library` directory.
+const DATABASE_PATH: &str = "Database2/m.db";
+
+/// Number of hot cue slots in Engine DJ.
+const NUM_QUICK_CUES: u8 = 8;
+
+/// Number of saved loop slots in Engine DJ.
+const NUM_LOOPS: u8 = 8;
+
+/// Number of points in the overview waveform.
+const NUM_OVERVIEW_POINTS: usize = 1024;
+
+/// Maximum length of cue and loop labels in bytes.
+const MAX_LABEL_LENGTH: usize = 255;
+
+/// A beatgrid marker in the `beatData` blob.
+#[derive(Debug, Clone, PartialEq)]
+struct BeatMarker {
+    /// The sample position.
+    sample_offset: f64,
+    /// The number of the beat at this marker.
+    beat_number: i64,
+}
+
+/// Compresses a blob and prefixes it with the uncompressed length.
+fn compress(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let length = (data.len() as u32).to_be_bytes();
+    let mut encoder = ZlibEncoder::new(length.to_vec(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+/// Converts a Serato position to a sample position.
+fn sample_offset(position: &Position, sample_rate: f64) -> f64 {
+    (f64::from(position.millis) / 1000.0 * sample_rate).round()
+}
+
+/// Appends a label (prefixed with its length) to the blob.
+///
+/// Labels that are longer than 255 bytes are truncated.
+fn write_label(output: &mut Vec<u8>, label: &str) {
+    let mut length = label.len().min(MAX_LABEL_LENGTH);
+    while !label.is_char_boundary(length) {
+        length -= 1;
+    }
+    output.push(length as u8);
+    output.extend_from_slice(&label.as_bytes()[..length]);
+}
+
+/// Appends a color as ARGB value to the blob.
+fn write_color(output: &mut Vec<u8>, color: Color) {
+    output.extend_from_slice(&[0xFF, color.red, color.green, color.blue]);
+}
+
+/// Returns the (uncompressed) `trackData` blob.
+///
+/// The average loudness and the key are not known, so they are left empty.
+fn track_data_blob(sample_rate: f64, samples: f64) -> Vec<u8> {
+    let mut output = vec![];
+    output.extend_from_slice(&sample_rate.to_be_bytes());
+    output.extend_from_slice(&(samples as i64).to_be_bytes());
+    output.extend_from_slice(&0f64.to_be_bytes());
+    output.extend_from_slice(&0i32.to_be_bytes());
+    output
+}
+
+/// Returns the beatgrid markers for the Serato beatgrid.
+///
+/// Engine DJ expects the first marker to be at or before the start of the track and the last
+/// marker to be after the end of the track, so the first and last tempo sections are extended
+/// accordingly. Returns an empty list if the tempo is invalid.
+fn beat_markers(
+    non_terminal_markers: &[NonTerminalMarker],
+    terminal_marker: &TerminalMarker,
+    sample_rate: f64,
+    samples: f64,
+) -> Vec<BeatMarker> {
+    let tempo_markers = common::tempo_markers(non_terminal_markers, terminal_marker);
+    if tempo_markers
+        .iter()
+        .any(|marker| !marker.bpm.is_finite() || marker.bpm <= 0.0)
+    {
+        return vec![];
+    }
+    let first_interval = 60.0 / tempo_markers[0].bpm * sample_rate;
+    let last_interval = 60.0 / f64::from(terminal_marker.bpm) * sample_rate;
+
+    let mut markers: Vec<_> = tempo_markers
+        .iter()
+        .map(|marker| BeatMarker {
+            sample_offset: marker.position * sample_rate,
+            beat_number: marker.beat_index as i64,
+        })
+        .collect();
+    if markers[0].sample_offset > 0.0 {
+        let beats = (markers[0].sample_offset / first_interval).ceil();
+        markers[0].sample_offset -= beats * first_interval;
+        markers[0].beat_number -= beats as i64;
+    }
+
+    let last = markers[markers.len() - 1].clone();
+    let beats = ((samples - last.sample_offset) / last_interval)
+        .ceil()
+        .max(1.0);
+    markers.push(BeatMarker {
+        sample_offset: last.sample_offset + beats * last_interval,
+        beat_number: last.beat_number + beats as i64,
+    });
+    markers
+}
+
+/// Returns the (uncompressed) `beatData` blob.
+///
+/// The same markers are used for the default beatgrid and the adjusted beatgrid.
+fn beat_data_blob(sample_rate: f64, samples: f64, markers: &[BeatMarker]) -> Vec<u8> {
+    let mut output = vec![];
+    output.extend_from_slice(&sample_rate.to_be_bytes());
+    output.extend_from_slice(&samples.to_be_bytes());
+    output.push(1);
+    for _ in 0..2 {
+        output.extend_from_slice(&(markers.len() as i64).to_be_bytes());
+        for (i, marker) in markers.iter().enumerate() {
+            let number_of_beats = markers
+                .get(i + 1)
+                .map_or(0, |next| next.beat_number - marker.beat_number);
+            output.extend_from_slice(&marker.sample_offset.to_le_bytes());
+            output.extend_from_slice(&marker.beat_number.to_le_bytes());
+            output.extend_from_slice(&(number_of_beats as i32).to_le_bytes());
+            output.extend_from_slice(&0i32.to_le_bytes());
+        }
+    }
+    output
+}
+
+/// Returns the (uncompressed) `quickCues` blob.
+///
+/// The main cue is set to the start of the track.
+fn quick_cues_blob(cues: &[Cue], sample_rate: f64) -> Vec<u8> {
+    let mut output = vec![];
+    output.extend_from_slice(&i64::from(NUM_QUICK_CUES).to_be_bytes());
+    for index in 0..NUM_QUICK_CUES {
+        if let Some(cue) = cues.iter().find(|cue| cue.index == index) {
+            write_label(&mut output, &cue.label);
+            output.extend_from_slice(&sample_offset(&cue.position, sample_rate).to_be_bytes());
+            write_color(&mut output, cue.color.into_pro_hotcue_color());
+        } else {
+            write_label(&mut output, "");
+            output.extend_from_slice(&(-1f64).to_be_bytes());
+            output.extend_from_slice(&[0; 4]);
+        }
+    }
+    output.extend_from_slice(&0f64.to_be_bytes());
+    output.push(0);
+    output.extend_from_slice(&0f64.to_be_bytes());
+    output
+}
+
+/// Returns the `loops` blob (which is not compressed).
+fn loops_blob(loops: &[Loop], sample_rate: f64) -> Vec<u8> {
+    let mut output = vec![];
+    output.extend_from_slice(&i64::from(NUM_LOOPS).to_le_bytes());
+    for index in 0..NUM_LOOPS {
+        if let Some(saved_loop) = loops.iter().find(|saved_loop| saved_loop.index == index) {
+            write_label(&mut output, &saved_loop.label);
+            let start = sample_offset(&saved_loop.start_position, sample_rate);
+            let end = sample_offset(&saved_loop.end_position, sample_rate);
+            output.extend_from_slice(&start.to_le_bytes());
+            output.extend_from_slice(&end.to_le_bytes());
+            output.extend_from_slice(&[1, 1]);
+            write_color(&mut output, saved_loop.color.into_pro_hotcue_color());
+        } else {
+            write_label(&mut output, "");
+            output.extend_from_slice(&(-1f64).to_le_bytes());
+            output.extend_from_slice(&(-1f64).to_le_bytes());
+            output.extend_from_slice(&[0; 6]);
+        }
+    }
+    output
+}
+
+/// Returns the (uncompressed) `overviewWaveFormData` blob for the Serato overview data.
+///
+/// Returns `None` if the overview is empty.
+fn overview_blob(data: &[Vec<u8>], samples: f64) -> Option<Vec<u8>> {
+    if data.is_empty() {
+        return None;
+    }
+    let points: Vec<u8> = (0..NUM_OVERVIEW_POINTS)
+        .map(|i| {
+            let column = &data[i * data.len() / NUM_OVERVIEW_POINTS];
+            let sum: usize = column.iter().copied().map(usize::from).sum();
+            sum.checked_div(column.len()).unwrap_or_default() as u8
+        })
+        .collect();
+    let maximum = points.iter().copied().max().unwrap_or_default();
+
+    let mut output = vec![];
+    output.extend_from_slice(&(NUM_OVERVIEW_POINTS as i64).to_be_bytes());
+    output.extend_from_slice(&(NUM_OVERVIEW_POINTS as i64).to_be_bytes());
+    output.extend_from_slice(&(samples / NUM_OVERVIEW_POINTS as f64).to_be_bytes());
+    for point in points {
+        output.extend_from_slice(&[point; 3]);
+    }
+    output.extend_from_slice(&[maximum; 3]);
+    Some(output)
+}
+
+/// Returns the path relative to the given directory (e.g. `../Music/Track.mp3`).
+///
+/// Paths that don't have a common prefix with the directory (e.g. on another drive) are returned
+/// unchanged.
+fn relative_path(directory: &Path, path: &Path) -> PathBuf {
+    let mut directory_components = directory.components().peekable();
+    let mut path_components = path.components().peekable();
+    let mut has_common_prefix = false;
+    while let (Some(a), Some(b)) = (directory_components.peek(), path_components.peek()) {
+        if a != b {
+            break;
+        }
+        has_common_prefix = true;
+        directory_components.next();
+        path_components.next();
+    }
+    if !has_common_prefix {
+        return path.to_path_buf();
+    }
+    directory_components
+        .map(|_| Component::ParentDir)
+        .chain(path_components)
+        .collect()
+}
+
+/// Returns the seconds since the Unix epoch.
+fn timestamp(time: SystemTime) -> i64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() as i64)
+}
+
+/// Returns the ID of the track with the given path, adding it if it doesn't exist yet.
+fn track_id(
+    transaction: &Transaction<'_>,
+    path: &str,
+    track: &Track,
+    database_uuid: &str,
+) -> Result<i64, Error> {
+    let track_id = transaction
+        .query_row("SELECT id FROM Track WHERE path = ?1", [path], |row| {
+            row.get(0)
+        })
+        .optional()?;
+    if let Some(track_id) = track_id {
+        return Ok(track_id);
+    }
+
+    let now = timestamp(SystemTime::now());
+    let filename = Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned());
+    transaction.execute(
+        "INSERT INTO Track \
+         (length, bpm, year, path, filename, bitrate, fileBytes, title, artist, album, genre, \
+         comment, label, composer, rating, isPlayed, fileType, isAnalyzed, dateCreated, \
+         dateAdded, isAvailable, isMetadataOfPackedTrackChanged, \
+         isPerfomanceDataOfPackedTrackChanged, isMetadataImported, pdbImportKey, \
+         isBeatGridLocked, originDatabaseUuid, streamingFlags, explicitLyrics, lastEditTime) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, 0, ?15, ?16, 0, \
+         ?17, ?17, 1, 0, 0, 1, 0, ?18, ?19, 0, 0, ?20)",
+        params![
+            track.length.map(|length| length.as_secs() as i64),
+            track.bpm.map(|bpm| bpm.round() as i64),
+            track
+                .year
+                .as_deref()
+                .and_then(|year| year.parse::<i64>().ok()),
+            path,
+            filename,
+            track.bitrate.map(|bitrate| bitrate.round() as i64),
+            track.file_size_bytes,
+            track.title,
+            track.artist,
+            track.album,
+            track.genre,
+            track.comment,
+            track.label,
+            track.composer,
+            track.played,
+            track.file_type,
+            track.date_added.map_or(now, timestamp),
+            track.beatgrid_locked,
+            database_uuid,
+            now,
+        ],
+    )?;
+    let track_id = transaction.last_insert_rowid();
+    transaction.execute(
+        "UPDATE Track SET originTrackId = id WHERE id = ?1",
+        [track_id],
+    )?;
+    Ok(track_id)
+}
+
+/// Replaces the performance data of the track.
+fn write_tags(
+    transaction: &Transaction<'_>,
+    track_id: i64,
+    tags: &TagContainer,
+    sample_rate: f64,
+    length: Duration,
+) -> Result<(), Error> {
+    let samples = (length.as_secs_f64() * sample_rate).round();
+    transaction.execute(
+        "UPDATE Track SET trackData = ?1 WHERE id = ?2 AND trackData IS NULL",
+        params![compress(&track_data_blob(sample_rate, samples))?, track_id],
+    )?;
+    transaction.execute(
+        "UPDATE Track SET quickCues = ?1, loops = ?2 WHERE id = ?3",
+        params![
+            compress(&quick_cues_blob(&tags.cues(), sample_rate))?,
+            loops_blob(&tags.loops(), sample_rate),
+            track_id,
+        ],
+    )?;
+
+    let mut has_beat_data = false;
+    if let Some((non_terminal_markers, terminal_marker)) = tags.beatgrid() {
+        let markers = beat_markers(non_terminal_markers, terminal_marker, sample_rate, samples);
+        if !markers.is_empty() {
+            transaction.execute(
+                "UPDATE Track SET beatData = ?1, bpmAnalyzed = ?2 WHERE id = ?3",
+                params![
+                    compress(&beat_data_blob(sample_rate, samples, &markers))?,
+                    common::average_bpm(&common::tempo_markers(
+                        non_terminal_markers,
+                        terminal_marker
+                    )),
+                    track_id,
+                ],
+            )?;
+            has_beat_data = true;
+        }
+    }
+
+    let mut has_overview = false;
+    if let Some(overview) = tags
+        .overview_data()
+        .and_then(|data| overview_blob(data, samples))
+    {
+        transaction.execute(
+            "UPDATE Track SET overviewWaveFormData = ?1 WHERE id = ?2",
+            params![compress(&overview)?, track_id],
+        )?;
+        has_overview = true;
+    }
+
+    // Engine DJ skips the analysis of tracks that are marked as analyzed, so the track is only
+    // marked if both the beats and the waveform have been written.
+    if has_beat_data && has_overview {
+        transaction.execute("UPDATE Track SET isAnalyzed = 1 WHERE id = ?1", [track_id])?;
+    }
+    Ok(())
+}
+
+/// An Engine DJ database.
+#[derive(Debug)]
+pub struct EngineDatabase {
+    connection: Connection,
+    library_dir: PathBuf,
+}
+
+impl EngineDatabase {
+    /// Open the existing database in the given `Engine Library` directory.
+    pub fn open(library_dir: impl Into<PathBuf>) -> Result<Self, Error> {
+        let library_dir = library_dir.into();
+        let connection = Connection::open_with_flags(
+            library_dir.join(DATABASE_PATH),
+            OpenFlags::SQLITE_OPEN_READ_WRITE,
+        )?;
+        Ok(Self {
+            connection,
+            library_dir,
+        })
+    }
+
+    /// Use an already opened connection to the database in the given `Engine Library` directory.
+    ///
+    /// The directory is only used to convert the track paths.
+    #[must_use]
+    pub fn from_connection(connection: Connection, library_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            connection,
+            library_dir: library_dir.into(),
+        }
+    }
+
+    /// Write the tracks of the library and their performance data to the database.
+    ///
+    /// The `tags` map contains the Serato tags for each track, using the paths from the database as
+    /// keys (see [`Library::tracks_with_paths()`]). Tracks without tags keep their performance
+    /// data. Both the volume root of the library and the `Engine Library` directory should be
+    /// absolute paths.
+    pub fn write_library(
+        &mut self,
+        library: &Library,
+        tags: &HashMap<PathBuf, TagContainer>,
+    ) -> Result<(), Error> {
+        let transaction = self.connection.transaction()?;
+        let database_uuid: String =
+            transaction.query_row("SELECT uuid FROM Information", [], |row| row.get(0))?;
+
+        let mut tracks: Vec<_> = library.tracks_with_paths().collect();
+        tracks.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (path, track) in tracks {
+            let engine_path = relative_path(&self.library_dir, &library.absolute_path(path));
+            let engine_path = engine_path
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let track_id = track_id(&transaction, &engine_path, track, &database_uuid)?;
+            if let (Some(tags), Some(sample_rate), Some(length)) =
+                (tags.get(path), track.sample_rate, track.length)
+            {
+                write_tags(&transaction, track_id, tags, f64::from(sample_rate), length)?;
+            }
+        }
+
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_beat_markers() {
+    let marker = |sample_offset, beat_number| BeatMarker {
+        sample_offset,
+        beat_number,
+    };
+
+    // A single marker is extended to the start and the end of the track.
+    let markers = beat_markers(
+        &[],
+        &TerminalMarker {
+            position: 1.25,
+            bpm: 120.0,
+        },
+        100.0,
+        1000.0,
+    );
+    assert_eq!(markers, [marker(-25.0, -3), marker(1025.0, 18)]);
+
+    // The tempo of the first section is used before the first marker.
+    let markers = beat_markers(
+        &[NonTerminalMarker {
+            position: 0.5,
+            beats_till_next_marker: 6,
+        }],
+        &TerminalMarker {
+            position: 3.5,
+            bpm: 128.0,
+        },
+        100.0,
+        400.0,
+    );
+    assert_eq!(
+        markers,
+        [
+            marker(0.0, -1),
+            marker(350.0, 6),
+            marker(350.0 + 46.875 * 2.0, 8)
+        ]
+    );
+
+    let data = beat_data_blob(100.0, 400.0, &markers);
+    assert_eq!(data.len(), 8 + 8 + 1 + 2 * (8 + 3 * 24));
+    assert_eq!(data[16], 1);
+    assert_eq!(data[17..25], 3i64.to_be_bytes());
+    // The number of beats until the next marker.
+    assert_eq!(data[25 + 16..25 + 20], 7i32.to_le_bytes());
+    assert_eq!(data[25 + 48 + 16..25 + 48 + 20], 0i32.to_le_bytes());
+
+    // Invalid tempos are not exported.
+    let markers = beat_markers(
+        &[],
+        &TerminalMarker {
+            position: 0.0,
+            bpm: 0.0,
+        },
+        100.0,
+        1000.0,
+    );
+    assert!(markers.is_empty());
+}
+
+#[test]
+fn test_quick_cues_blob() {
+    let cues = [Cue {
+        index: 1,
+        position: Position { millis: 1500 },
+        color: Color {
+            red: 0xCC,
+            green: 0x00,
+            blue: 0x00,
+        },
+        label: "Drop".to_string(),
+    }];
+    let data = quick_cues_blob(&cues, 44100.0);
+    let empty_cue = [0, 0xBF, 0xF0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    let mut expected = vec![0, 0, 0, 0, 0, 0, 0, 8];
+    expected.extend_from_slice(&empty_cue);
+    expected.extend_from_slice(b"\x04Drop");
+    expected.extend_from_slice(&66150f64.to_be_bytes());
+    // The color is converted to the Serato DJ Pro color.
+    expected.extend_from_slice(&[0xFF, 0xC0, 0x26, 0x26]);
+    for _ in 2..8 {
+        expected.extend_from_slice(&empty_cue);
+    }
+    expected.extend_from_slice(&[0; 17]);
+    assert_eq!(data, expected);
+}
+
+#[test]
+fn test_loops_blob() {
+    let loops = [Loop {
+        index: 0,
+        start_position: Position { millis: 1000 },
+        end_position: Position { millis: 3000 },
+        color: Color {
+            red: 0x27,
+            green: 0xAA,
+            blue: 0xE1,
+        },
+        is_locked: false,
+        label: "é".repeat(200),
+    }];
+    let data = loops_blob(&loops, 100.0);
+    assert_eq!(data.len(), 8 + (1 + 254 + 22) + 7 * 23);
+    assert_eq!(data[..9], [8, 0, 0, 0, 0, 0, 0, 0, 254]);
+    // The label is truncated at a character boundary.
+    let rest = &data[9 + 254..];
+    assert_eq!(rest[..8], 100f64.to_le_bytes());
+    assert_eq!(rest[8..16], 300f64.to_le_bytes());
+    assert_eq!(rest[16..22], [1, 1, 0xFF, 0x27, 0xAA, 0xE1]);
+    assert_eq!(rest[22], 0);
+    assert_eq!(rest[23..31], (-1f64).to_le_bytes());
+}
+
+#[test]
+fn test_overview_blob() {
+    assert_eq!(overview_blob(&[], 1000.0), None);
+
+    let data = vec![vec![0x10; 16], vec![0x20; 16]];
+    let blob = overview_blob(&data, 2048.0).unwrap();
+    assert_eq!(blob.len(), 24 + 3 * NUM_OVERVIEW_POINTS + 3);
+    assert_eq!(blob[..8], 1024i64.to_be_bytes());
+    assert_eq!(blob[8..16], 1024i64.to_be_bytes());
+    assert_eq!(blob[16..24], 2f64.to_be_bytes());
+    assert_eq!(blob[24..27], [0x10; 3]);
+    assert_eq!(blob[24 + 3 * 511..24 + 3 * 512], [0x10; 3]);
+    assert_eq!(blob[24 + 3 * 512..24 + 3 * 513], [0x20; 3]);
+    assert_eq!(blob[blob.len() - 3..], [0x20; 3]);
+}
+
+#[test]
+fn test_relative_path() {
+    assert_eq!(
+        relative_path(
+            Path::new("/Volumes/USB/Engine Library"),
+            Path::new("/Volumes/USB/Music/Track.mp3")
+        ),
+        Path::new("../Music/Track.mp3")
+    );
+    assert_eq!(
+        relative_path(
+            Path::new("/home/user/Music/Engine Library"),
+            Path::new("/Volumes/USB/Track.mp3")
+        ),
+        Path::new("../../../../Volumes/USB/Track.mp3")
+    );
+    assert_eq!(
+        relative_path(Path::new("Engine Library"), Path::new("Music/Track.mp3")),
+        Path::new("Music/Track.mp3")
+    );
+}
@@ -0,0 +1,1069 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Mixxx databases (`mixxxdb.sqlite`).
+//!
+//! Mixxx stores its library in an SQLite database in its settings directory (e.g.
+//! `~/.mixxx/mixxxdb.sqlite` on Linux or `%LOCALAPPDATA%\Mixxx\mixxxdb.sqlite` on Windows).
+//! Reading and writing is done using the [`rusqlite`](https://lib.rs/crates/rusqlite) crate.
+//!
+//! The schema of the database is created and migrated by Mixxx itself, so the database must
+//! already exist (i.e. Mixxx 2.3 or later has been started at least once). Mixxx must not be
+//! running while the database is modified.
+//!
+//! The positions of cues and loops in the database are sample positions of the stereo signal, i.e.
+//! there are two samples per frame. The positions of beats are frame positions. Both are converted
+//! using the sample rate of the track, so tracks with an unknown sample rate are exported and
+//! imported without cues, loops and beats.
+//!
+//! # Export
+//!
+//! The Serato data is mapped as follows:
+//!
+//! | Serato                       | Mixxx
+//! | ---------------------------- | ------------------------------------------------------------
+//! | Library tracks               | Rows in the `library` and `track_locations` tables
+//! | Beatgrid markers             | `beats` blob (`BeatGrid-2.0` or `BeatMap-1.0`, see [`Beats`])
+//! | Cues                         | Hot cues 1-8 (rows in the `cues` table with `type = 1`)
+//! | Saved loops                  | Saved loops in hot cues 9-16 (`type = 4`)
+//! | Subcrates                    | Crates (e.g. `House / Deep` for the subcrate `House%%Deep`)
+//!
+//! Tracks that are already in the database keep their metadata. Their hot cues and saved loops
+//! are replaced, other cues (e.g. the main cue or intro/outro markers) are kept. Mixxx uses the
+//! same slots for hot cues and saved loops, so saved loops are put into the slots after the cues.
+//! The colors of cues and saved loops are converted to the colors that Serato DJ Pro displays.
+//!
+//! Beatgrids with a single marker are exported as `BeatGrid-2.0`. Since the tempo of beatgrids
+//! with multiple markers may change at every marker, these are exported as a list of beats
+//! (`BeatMap-1.0`) that starts before the first marker and ends at the end of the track. If the
+//! length of the track is unknown, a `BeatGrid-2.0` with the average tempo and the first marker as
+//! first beat is exported instead. Mixxx crates are unordered, so the order of the tracks in the
+//! subcrates is lost.
+//!
+//! ```no_run
+//! use std::collections::HashMap;
+//! use triseratops::convert::mixxx::MixxxDatabase;
+//! use triseratops::library::Library;
+//!
+//! let library = Library::read_from_path("/Volumes/USB Drive").expect("Failed to read library!");
+//! // The Serato tags of each track, read from the files (e.g. using `TagContainer::read_from_path`).
+//! let tags = HashMap::new();
+//! let mut database =
+//!     MixxxDatabase::open("/home/user/.mixxx/mixxxdb.sqlite").expect("Failed to open database!");
+//! database
+//!     .write_library(&library, &tags)
+//!     .expect("Failed to export library!");
+//! ```
+//!
+//! # Import
+//!
+//! Mixxx data is converted to Serato data as follows:
+//!
+//! | Mixxx                        | Serato
+//! | ---------------------------- | ------------------------------------------------------------
+//! | `beats` blob                 | Beatgrid markers (see [`LibraryTrack::beatgrid()`])
+//! | Hot cues 1-8                 | Cues with the same index
+//! | Other hot cues               | Cues in the free slots (see [`LibraryTrack::cues()`])
+//! | Saved loops                  | Saved loops (see [`LibraryTrack::loops()`])
+//! | Colors                       | The closest Serato hotcue colors
+//! | Crates and playlists         | Subcrates (see [`MixxxDatabase::write_subcrates()`])
+//!
+//! The main cue, intro/outro markers and jump cues are ignored. The converted data can be written
+//! to tags using [`LibraryTrack::to_tag_container()`].
+//!
+//! ```no_run
+//! use triseratops::convert::mixxx::MixxxDatabase;
+//! use triseratops::library::Library;
+//! use triseratops::tag::TagFormat;
+//!
+//! let database =
+//!     MixxxDatabase::open("/home/user/.mixxx/mixxxdb.sqlite").expect("Failed to open database!");
+//! for track in database.tracks().expect("Failed to read tracks!") {
+//!     let tags = track.to_tag_container(TagFormat::ID3).expect("Failed to convert track!");
+//!     // Write the tags to the file at `track.location` ...
+//! }
+//!
+//! let library = Library::read_from_path("/Volumes/USB Drive").expect("Failed to read library!");
+//! database.write_subcrates(&library).expect("Failed to write crates!");
+//! ```
+
+use super::common::{self, ForeignMarker, date, tempo_markers};
+use crate::error::Error;
+use crate::library::{Library, SUBCRATE_SEPARATOR, Track};
+use crate::tag::beatgrid::{NonTerminalMarker, TerminalMarker};
+use crate::tag::color::Color;
+use crate::tag::generic::{Cue, Loop};
+use crate::tag::{Beatgrid, TagContainer, TagFormat};
+use rusqlite::{Connection, OpenFlags, OptionalExtension, Transaction, params};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Value of the `beats_version` column for constant tempo beatgrids.
+const BEAT_GRID_VERSION: &str = "BeatGrid-2.0";
+
+/// Value of the `beats_version` column for lists of beats.
+const BEAT_MAP_VERSION: &str = "BeatMap-1.0";
+
+/// Separator of the parent crate names in Mixxx crate names.
+const CRATE_SEPARATOR: &str = " / ";
+
+/// Hotcue slot of the first saved loop (i.e. hot cue 9).
+const FIRST_LOOP_HOTCUE: i32 = 8;
+
+/// Number of samples per frame in sample positions.
+const SAMPLES_PER_FRAME: f64 = 2.0;
+
+/// Maximum difference between two beat intervals (in seconds) in a section with constant tempo.
+const BEAT_INTERVAL_TOLERANCE: f64 = 0.001;
+
+/// Protocol buffer wire type of integers.
+const WIRE_TYPE_VARINT: u64 = 0;
+
+/// Protocol buffer wire type of doubles.
+const WIRE_TYPE_FIXED64: u64 = 1;
+
+/// Protocol buffer wire type of embedded messages.
+const WIRE_TYPE_LEN: u64 = 2;
+
+/// Protocol buffer wire type of floats.
+const WIRE_TYPE_FIXED32: u64 = 5;
+
+/// The value of a protocol buffer field.
+enum FieldValue<'a> {
+    Varint(u64),
+    Fixed64([u8; 8]),
+    Len(&'a [u8]),
+    Fixed32,
+}
+
+fn write_varint(output: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        output.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+fn write_len_field(output: &mut Vec<u8>, field_number: u64, data: &[u8]) {
+    write_varint(output, field_number << 3 | WIRE_TYPE_LEN);
+    write_varint(output, data.len() as u64);
+    output.extend_from_slice(data);
+}
+
+fn read_varint(input: &mut &[u8]) -> Option<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (byte, rest) = input.split_first()?;
+        *input = rest;
+        value |= u64::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Returns the fields of a protocol buffer message as `(field number, value)` pairs.
+fn read_fields(mut input: &[u8]) -> Option<Vec<(u64, FieldValue<'_>)>> {
+    let mut fields = vec![];
+    while !input.is_empty() {
+        let key = read_varint(&mut input)?;
+        let value = match key & 0x07 {
+            WIRE_TYPE_VARINT => FieldValue::Varint(read_varint(&mut input)?),
+            WIRE_TYPE_FIXED64 => {
+                let (value, rest) = input.split_first_chunk()?;
+                input = rest;
+                FieldValue::Fixed64(*value)
+            }
+            WIRE_TYPE_LEN => {
+                let len = usize::try_from(read_varint(&mut input)?).ok()?;
+                let (value, rest) = input.split_at_checked(len)?;
+                input = rest;
+                FieldValue::Len(value)
+            }
+            WIRE_TYPE_FIXED32 => {
+                input = input.get(4..)?;
+                FieldValue::Fixed32
+            }
+            _ => return None,
+        };
+        fields.push((key >> 3, value));
+    }
+    Some(fields)
+}
+
+/// Returns a `Beat` message (`frame_position = 1`).
+fn beat_message(position: i32) -> Vec<u8> {
+    let mut message = vec![];
+    write_varint(&mut message, 1 << 3 | WIRE_TYPE_VARINT);
+    // Negative `int32` values are sign-extended to 64 bits.
+    write_varint(&mut message, i64::from(position) as u64);
+    message
+}
+
+/// Returns the position of a `Beat` message, or `None` if the beat is disabled.
+fn parse_beat_message(message: &[u8]) -> Option<i32> {
+    let mut position = 0;
+    let mut is_enabled = true;
+    for (field_number, value) in read_fields(message)? {
+        match (field_number, value) {
+            (1, FieldValue::Varint(value)) => position = value as i32,
+            (2, FieldValue::Varint(value)) => is_enabled = value != 0,
+            _ => (),
+        }
+    }
+    is_enabled.then_some(position)
+}
+
+/// The beats of a track (the `beats` column of the `library` table).
+///
+/// The blob is a serialized protocol buffer message (`mixxx.track.io.BeatGrid` or
+/// `mixxx.track.io.BeatMap`). Positions are frame positions (unlike the positions of cues, which
+/// are sample positions).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Beats {
+    /// A constant tempo (`BeatGrid-2.0`).
+    Grid { bpm: f64, first_beat: i32 },
+    /// The positions of all beats (`BeatMap-1.0`).
+    Map(Vec<i32>),
+}
+
+impl Beats {
+    /// Parse the `beats` blob with the given version (the `beats_version` column).
+    ///
+    /// Returns `None` if the version is unknown or the data is malformed.
+    #[must_use]
+    pub fn parse(version: &str, data: &[u8]) -> Option<Self> {
+        match version {
+            BEAT_GRID_VERSION => {
+                let mut bpm = None;
+                let mut first_beat = None;
+                for (field_number, value) in read_fields(data)? {
+                    match (field_number, value) {
+                        (1, FieldValue::Len(message)) => {
+                            bpm = read_fields(message)?
+                                .into_iter()
+                                .find_map(|field| match field {
+                                    (1, FieldValue::Fixed64(value)) => {
+                                        Some(f64::from_le_bytes(value))
+                                    }
+                                    _ => None,
+                                });
+                        }
+                        (2, FieldValue::Len(message)) => {
+                            first_beat = parse_beat_message(message);
+                        }
+                        _ => (),
+                    }
+                }
+                Some(Self::Grid {
+                    bpm: bpm?,
+                    first_beat: first_beat.unwrap_or_default(),
+                })
+            }
+            BEAT_MAP_VERSION => {
+                let mut positions = vec![];
+                for (field_number, value) in read_fields(data)? {
+                    if let (1, FieldValue::Len(message)) = (field_number, value) {
+                        positions.extend(parse_beat_message(message));
+                    }
+                }
+                Some(Self::Map(positions))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the version (the `beats_version` column).
+    #[must_use]
+    pub fn version(&self) -> &'static str {
+        match self {
+            Self::Grid { .. } => BEAT_GRID_VERSION,
+            Self::Map(_) => BEAT_MAP_VERSION,
+        }
+    }
+
+    /// Returns the serialized data (the `beats` column).
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = vec![];
+        match self {
+            Self::Grid { bpm, first_beat } => {
+                let mut bpm_message = vec![];
+                write_varint(&mut bpm_message, 1 << 3 | WIRE_TYPE_FIXED64);
+                bpm_message.extend_from_slice(&bpm.to_le_bytes());
+                write_len_field(&mut data, 1, &bpm_message);
+                write_len_field(&mut data, 2, &beat_message(*first_beat));
+            }
+            Self::Map(positions) => {
+                for position in positions {
+                    write_len_field(&mut data, 1, &beat_message(*position));
+                }
+            }
+        }
+        data
+    }
+}
+
+/// Converts a position in seconds to a frame position (as used for beats).
+fn frame_position(seconds: f64, sample_rate: f64) -> i32 {
+    (seconds * sample_rate)
+        .round()
+        .clamp(f64::from(i32::MIN), f64::from(i32::MAX)) as i32
+}
+
+/// Converts a position in seconds to a sample position (as used for cues).
+fn sample_position(seconds: f64, sample_rate: f64) -> i32 {
+    let frames = (seconds * sample_rate).round();
+    (frames * SAMPLES_PER_FRAME).clamp(f64::from(i32::MIN), f64::from(i32::MAX)) as i32
+}
+
+/// Converts a frame position to a position in seconds.
+fn seconds(frame_position: f64, sample_rate: f64) -> f64 {
+    frame_position / sample_rate
+}
+
+/// Returns the beats for a Serato beatgrid.
+fn beats(
+    non_terminal_markers: &[NonTerminalMarker],
+    terminal_marker: &TerminalMarker,
+    sample_rate: f64,
+    length: Option<Duration>,
+) -> Option<Beats> {
+    let markers = tempo_markers(non_terminal_markers, terminal_marker);
+    if markers.iter().any(|marker| marker.bpm <= 0.0) {
+        return None;
+    }
+    let first = markers.first()?;
+    let length = match length {
+        Some(length) if markers.len() > 1 => length.as_secs_f64(),
+        _ => {
+            return Some(Beats::Grid {
+                bpm: common::average_bpm(&markers)?,
+                first_beat: frame_position(first.position, sample_rate),
+            });
+        }
+    };
+
+    // Mixxx doesn't extend the beats before the first beat, so these are added, too.
+    let interval = 60.0 / first.bpm;
+    let beats_before = (first.position / interval).floor() as u64;
+    let mut positions: Vec<_> = (1..=beats_before)
+        .rev()
+        .map(|beat| first.position - beat as f64 * interval)
+        .collect();
+    for (i, marker) in markers.iter().enumerate() {
+        let interval = 60.0 / marker.bpm;
+        let beats = markers
+            .get(i + 1)
+            .map_or(u64::MAX, |next| next.beat_index - marker.beat_index);
+        positions.extend(
+            (0..beats)
+                .map(|beat| marker.position + beat as f64 * interval)
+                .take_while(|position| *position < length),
+        );
+    }
+    Some(Beats::Map(
+        positions
+            .into_iter()
+            .map(|position| frame_position(position, sample_rate))
+            .collect(),
+    ))
+}
+
+/// The type of a [`CuePoint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CueType {
+    Invalid,
+    HotCue,
+    MainCue,
+    Beat,
+    Loop,
+    Jump,
+    Intro,
+    Outro,
+    N60dBSound,
+    Unknown(i64),
+}
+
+impl From<i64> for CueType {
+    fn from(value: i64) -> Self {
+        match value {
+            0 => Self::Invalid,
+            1 => Self::HotCue,
+            2 => Self::MainCue,
+            3 => Self::Beat,
+            4 => Self::Loop,
+            5 => Self::Jump,
+            6 => Self::Intro,
+            7 => Self::Outro,
+            8 => Self::N60dBSound,
+            value => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<CueType> for i64 {
+    fn from(value: CueType) -> Self {
+        match value {
+            CueType::Invalid => 0,
+            CueType::HotCue => 1,
+            CueType::MainCue => 2,
+            CueType::Beat => 3,
+            CueType::Loop => 4,
+            CueType::Jump => 5,
+            CueType::Intro => 6,
+            CueType::Outro => 7,
+            CueType::N60dBSound => 8,
+            CueType::Unknown(value) => value,
+        }
+    }
+}
+
+/// A row in the `cues` table, i.e. a cue, loop or marker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CuePoint {
+    pub cue_type: CueType,
+    /// The start position (sample position).
+    pub position: f64,
+    /// The length in samples (for loops).
+    pub length: f64,
+    /// The hotcue slot (starting at 0), or `-1` if the cue is not assigned to a slot.
+    pub hotcue: i32,
+    pub label: String,
+    pub color: Option<Color>,
+}
+
+/// A row in the `library` table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LibraryTrack {
+    pub id: i64,
+    /// The file system path of the track.
+    pub location: PathBuf,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub beats: Option<Beats>,
+    pub cues: Vec<CuePoint>,
+}
+
+impl LibraryTrack {
+    /// Returns the hot cues, converted to Serato cues.
+    ///
+    /// Hot cues 1-8 keep their slot. The other hot cues are assigned to the free slots (in the
+    /// order of their position), unless there is already a cue at the same position. Colors are
+    /// mapped to the closest Serato hotcue colors.
+    #[must_use]
+    pub fn cues(&self) -> Vec<Cue> {
+        self.foreign_markers(CueType::HotCue, common::import_cues)
+    }
+
+    /// Returns the saved loops, converted to Serato saved loops.
+    ///
+    /// Loops are sorted by their hotcue slot. Duplicate loops are skipped.
+    #[must_use]
+    pub fn loops(&self) -> Vec<Loop> {
+        self.foreign_markers(CueType::Loop, common::import_loops)
+    }
+
+    /// Returns the beatgrid, converted to a Serato beatgrid.
+    ///
+    /// A `BeatGrid-2.0` becomes a single marker. A `BeatMap-1.0` is split into sections with a
+    /// constant tempo, each of which becomes a marker.
+    #[must_use]
+    pub fn beatgrid(&self) -> Option<Beatgrid> {
+        let sample_rate = f64::from(self.sample_rate?);
+        match self.beats.as_ref()? {
+            Beats::Grid { bpm, first_beat } => {
+                common::import_beatgrid(vec![(seconds(f64::from(*first_beat), sample_rate), *bpm)])
+            }
+            Beats::Map(positions) => {
+                let mut positions: Vec<_> = positions
+                    .iter()
+                    .map(|position| seconds(f64::from(*position), sample_rate))
+                    .collect();
+                positions.sort_by(f64::total_cmp);
+                if positions.len() < 2 {
+                    return None;
+                }
+                let mut tempos = vec![];
+                let mut start = 0;
+                for end in 1..positions.len() {
+                    let is_tempo_change = end > start + 1 && {
+                        let interval = positions[start + 1] - positions[start];
+                        let next_interval = positions[end] - positions[end - 1];
+                        (next_interval - interval).abs() > BEAT_INTERVAL_TOLERANCE
+                    };
+                    let is_last = end == positions.len() - 1;
+                    if is_tempo_change || is_last {
+                        let end = if is_tempo_change { end - 1 } else { end };
+                        let bpm = 60.0 * (end - start) as f64 / (positions[end] - positions[start]);
+                        tempos.push((positions[start], bpm));
+                        start = end;
+                    }
+                }
+                // The last section may end with a tempo change.
+                if start < positions.len() - 1 {
+                    let end = positions.len() - 1;
+                    let bpm = 60.0 * (end - start) as f64 / (positions[end] - positions[start]);
+                    tempos.push((positions[start], bpm));
+                }
+                common::import_beatgrid(tempos)
+            }
+        }
+    }
+
+    /// Returns a new tag container with the cues, loops and beatgrid of this track.
+    pub fn to_tag_container(&self, tag_format: TagFormat) -> Result<TagContainer, Error> {
+        let mut tags = TagContainer::with_empty_tags(tag_format);
+        for cue in self.cues() {
+            tags.set_cue(cue)?;
+        }
+        for saved_loop in self.loops() {
+            tags.set_loop(saved_loop)?;
+        }
+        if let Some(beatgrid) = self.beatgrid() {
+            tags.set_beatgrid(beatgrid);
+        }
+        Ok(tags)
+    }
+
+    /// Converts the cues of the given type.
+    fn foreign_markers<T>(
+        &self,
+        cue_type: CueType,
+        convert: impl FnOnce(&[ForeignMarker<'_>]) -> T,
+    ) -> T {
+        let Some(sample_rate) = self.sample_rate.map(f64::from) else {
+            return convert(&[]);
+        };
+        let markers: Vec<_> = self
+            .cues
+            .iter()
+            .filter(|cue| cue.cue_type == cue_type)
+            .map(|cue| ForeignMarker {
+                name: &cue.label,
+                start: seconds(cue.position / SAMPLES_PER_FRAME, sample_rate),
+                end: (cue.length > 0.0)
+                    .then(|| seconds((cue.position + cue.length) / SAMPLES_PER_FRAME, sample_rate)),
+                hotcue: u8::try_from(cue.hotcue).ok(),
+                color: cue.color,
+            })
+            .collect();
+        convert(&markers)
+    }
+}
+
+/// A row in the `crates` table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crate {
+    pub id: i64,
+    pub name: String,
+    /// The IDs of the tracks in the crate.
+    pub track_ids: Vec<i64>,
+}
+
+/// A row in the `Playlists` table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Playlist {
+    pub id: i64,
+    pub name: String,
+    /// The IDs of the tracks in the playlist (in the order of their position).
+    pub track_ids: Vec<i64>,
+}
+
+/// Returns the date and time in the format of the `datetime_added` column.
+fn datetime(time: SystemTime) -> String {
+    let (year, month, day) = date(time);
+    let seconds = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() % 86400);
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    format!("{year:04}-{month:02}-{day:02} {hours:02}:{minutes:02}:{seconds:02}")
+}
+
+/// Returns the ID of the track with the given location, adding it if it doesn't exist yet.
+fn track_id(transaction: &Transaction<'_>, location: &Path, track: &Track) -> Result<i64, Error> {
+    let location_string = location.to_string_lossy();
+    let track_id = transaction
+        .query_row(
+            "SELECT library.id FROM library \
+             JOIN track_locations ON library.location = track_locations.id \
+             WHERE track_locations.location = ?1",
+            [&location_string],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(track_id) = track_id {
+        transaction.execute(
+            "UPDATE library SET mixxx_deleted = 0 WHERE id = ?1",
+            [track_id],
+        )?;
+        return Ok(track_id);
+    }
+
+    transaction.execute(
+        "INSERT OR IGNORE INTO track_locations \
+         (location, filename, directory, filesize, fs_deleted, needs_verification) \
+         VALUES (?1, ?2, ?3, ?4, 0, 0)",
+        params![
+            location_string,
+            location
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned()),
+            location
+                .parent()
+                .map(|directory| directory.to_string_lossy().into_owned()),
+            track.file_size_bytes,
+        ],
+    )?;
+    let location_id: i64 = transaction.query_row(
+        "SELECT id FROM track_locations WHERE location = ?1",
+        [&location_string],
+        |row| row.get(0),
+    )?;
+    // Mixxx reads the remaining metadata from the file, because `header_parsed` is not set.
+    transaction.execute(
+        "INSERT INTO library \
+         (artist, title, album, year, genre, tracknumber, location, comment, duration, bitrate, \
+         samplerate, bpm, datetime_added, mixxx_deleted, header_parsed, filetype, key, composer, \
+         grouping) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, \
+         IFNULL(?13, CURRENT_TIMESTAMP), 0, 0, ?14, ?15, ?16, ?17)",
+        params![
+            track.artist,
+            track.title,
+            track.album,
+            track.year,
+            track.genre,
+            track
+                .track_number
+                .map(|track_number| track_number.to_string()),
+            location_id,
+            track.comment,
+            track.length.map(|length| length.as_secs_f64()),
+            track.bitrate.map(|bitrate| bitrate.round() as i64),
+            track.sample_rate,
+            track.bpm,
+            track.date_added.map(datetime),
+            track.file_type,
+            track.key,
+            track.composer,
+            track.grouping,
+        ],
+    )?;
+    Ok(transaction.last_insert_rowid())
+}
+
+/// Replaces the hot cues, saved loops and beats of the track.
+fn write_tags(
+    transaction: &Transaction<'_>,
+    track_id: i64,
+    tags: &TagContainer,
+    sample_rate: f64,
+    length: Option<Duration>,
+) -> Result<(), Error> {
+    let sample_position = |millis: u32| sample_position(f64::from(millis) / 1000.0, sample_rate);
+
+    transaction.execute(
+        "DELETE FROM cues WHERE track_id = ?1 AND type IN (?2, ?3)",
+        params![
+            track_id,
+            i64::from(CueType::HotCue),
+            i64::from(CueType::Loop)
+        ],
+    )?;
+    let mut insert = transaction.prepare(
+        "INSERT INTO cues (track_id, type, position, length, hotcue, label, color) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?;
+    for cue in tags.cues() {
+        insert.execute(params![
+            track_id,
+            i64::from(CueType::HotCue),
+            sample_position(cue.position.millis),
+            0,
+            cue.index,
+            cue.label,
+            u32::from(cue.color.into_pro_hotcue_color()),
+        ])?;
+    }
+    for saved_loop in tags.loops() {
+        let start = sample_position(saved_loop.start_position.millis);
+        let end = sample_position(saved_loop.end_position.millis);
+        insert.execute(params![
+            track_id,
+            i64::from(CueType::Loop),
+            start,
+            end.saturating_sub(start),
+            FIRST_LOOP_HOTCUE + i32::from(saved_loop.index),
+            saved_loop.label,
+            u32::from(saved_loop.color.into_pro_hotcue_color()),
+        ])?;
+    }
+
+    let Some((non_terminal_markers, terminal_marker)) = tags.beatgrid() else {
+        return Ok(());
+    };
+    let Some(beats) = beats(non_terminal_markers, terminal_marker, sample_rate, length) else {
+        return Ok(());
+    };
+    let markers = tempo_markers(non_terminal_markers, terminal_marker);
+    transaction.execute(
+        "UPDATE library SET bpm = ?1, beats = ?2, beats_version = ?3, beats_sub_version = '' \
+         WHERE id = ?4",
+        params![
+            common::average_bpm(&markers),
+            beats.to_bytes(),
+            beats.version(),
+            track_id,
+        ],
+    )?;
+    Ok(())
+}
+
+/// A Mixxx database.
+#[derive(Debug)]
+pub struct MixxxDatabase {
+    connection: Connection,
+}
+
+impl MixxxDatabase {
+    /// Open the existing database at the given path.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_WRITE)?;
+        Ok(Self { connection })
+    }
+
+    /// Use an already opened database connection.
+    #[must_use]
+    pub fn from_connection(connection: Connection) -> Self {
+        Self { connection }
+    }
+
+    /// Write the tracks and subcrates of the library to the database.
+    ///
+    /// The `tags` map contains the Serato tags for each track, using the paths from the database as
+    /// keys (see [`Library::tracks_with_paths()`]). Tracks without tags keep their cues, loops
+    /// and beats. Subcrates replace existing crates with the same name.
+    pub fn write_library(
+        &mut self,
+        library: &Library,
+        tags: &HashMap<PathBuf, TagContainer>,
+    ) -> Result<(), Error> {
+        // Mixxx doesn't enforce foreign keys, and the `library.location` column references the
+        // `location` column of `track_locations` instead of its ID. The previous setting of the
+        // connection is restored afterwards.
+        let foreign_keys: bool =
+            self.connection
+                .pragma_query_value(None, "foreign_keys", |row| row.get(0))?;
+        self.connection.pragma_update(None, "foreign_keys", false)?;
+        let result = self.write_library_transaction(library, tags);
+        self.connection
+            .pragma_update(None, "foreign_keys", foreign_keys)?;
+        result
+    }
+
+    fn write_library_transaction(
+        &mut self,
+        library: &Library,
+        tags: &HashMap<PathBuf, TagContainer>,
+    ) -> Result<(), Error> {
+        let transaction = self.connection.transaction()?;
+
+        let mut tracks: Vec<_> = library.tracks_with_paths().collect();
+        tracks.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let mut track_ids = HashMap::new();
+        for (path, track) in tracks {
+            let track_id = track_id(&transaction, &library.absolute_path(path), track)?;
+            track_ids.insert(path, track_id);
+            if let (Some(tags), Some(sample_rate)) = (tags.get(path), track.sample_rate) {
+                let sample_rate = f64::from(sample_rate);
+                write_tags(&transaction, track_id, tags, sample_rate, track.length)?;
+            }
+        }
+
+        for name in library.subcrates() {
+            let subcrate = library.read_subcrate(&name)?;
+            let crate_name = name
+                .split(SUBCRATE_SEPARATOR)
+                .collect::<Vec<_>>()
+                .join(CRATE_SEPARATOR);
+            transaction.execute(
+                "INSERT OR IGNORE INTO crates (name) VALUES (?1)",
+                [&crate_name],
+            )?;
+            let crate_id: i64 = transaction.query_row(
+                "SELECT id FROM crates WHERE name = ?1",
+                [&crate_name],
+                |row| row.get(0),
+            )?;
+            transaction.execute("DELETE FROM crate_tracks WHERE crate_id = ?1", [crate_id])?;
+            for path in subcrate.track_paths() {
+                if let Some(track_id) = track_ids.get(path) {
+                    transaction.execute(
+                        "INSERT OR IGNORE INTO crate_tracks (crate_id, track_id) VALUES (?1, ?2)",
+                        [crate_id, *track_id],
+                    )?;
+                }
+            }
+        }
+
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Returns all tracks that haven't been removed from the library.
+    pub fn tracks(&self) -> Result<Vec<LibraryTrack>, Error> {
+        let mut cues: HashMap<i64, Vec<CuePoint>> = HashMap::new();
+        let mut statement = self.connection.prepare(
+            "SELECT track_id, type, position, length, hotcue, label, color FROM cues ORDER BY id",
+        )?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            let color: Option<i64> = row.get(6)?;
+            let cue = CuePoint {
+                cue_type: CueType::from(row.get::<_, i64>(1)?),
+                position: row.get(2)?,
+                length: row.get::<_, Option<f64>>(3)?.unwrap_or_default(),
+                hotcue: row.get::<_, Option<i32>>(4)?.unwrap_or(-1),
+                label: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+                color: color
+                    .and_then(|color| u32::try_from(color).ok())
+                    .filter(|color| *color <= 0xFF_FFFF)
+                    .map(Color::from),
+            };
+            cues.entry(row.get(0)?).or_default().push(cue);
+        }
+
+        let mut statement = self.connection.prepare(
+            "SELECT library.id, track_locations.location, library.title, library.artist, \
+             library.samplerate, library.beats, library.beats_version FROM library \
+             JOIN track_locations ON library.location = track_locations.id \
+             WHERE IFNULL(library.mixxx_deleted, 0) = 0 ORDER BY library.id",
+        )?;
+        let mut rows = statement.query([])?;
+        let mut tracks = vec![];
+        while let Some(row) = rows.next()? {
+            let id = row.get(0)?;
+            let beats_data: Option<Vec<u8>> = row.get(5)?;
+            let beats_version: Option<String> = row.get(6)?;
+            let beats = beats_data
+                .zip(beats_version)
+                .and_then(|(data, version)| Beats::parse(&version, &data));
+            tracks.push(LibraryTrack {
+                id,
+                location: PathBuf::from(row.get::<_, String>(1)?),
+                title: row.get(2)?,
+                artist: row.get(3)?,
+                sample_rate: row
+                    .get::<_, Option<u32>>(4)?
+                    .filter(|sample_rate| *sample_rate > 0),
+                beats,
+                cues: cues.remove(&id).unwrap_or_default(),
+            });
+        }
+        Ok(tracks)
+    }
+
+    /// Returns all crates (sorted by name).
+    pub fn crates(&self) -> Result<Vec<Crate>, Error> {
+        self.track_lists(
+            "SELECT id, name FROM crates ORDER BY name",
+            "SELECT track_id FROM crate_tracks WHERE crate_id = ?1 ORDER BY track_id",
+        )
+        .map(|crates| {
+            crates
+                .into_iter()
+                .map(|(id, name, track_ids)| Crate {
+                    id,
+                    name,
+                    track_ids,
+                })
+                .collect()
+        })
+    }
+
+    /// Returns all playlists that are shown in the sidebar (sorted by their position).
+    ///
+    /// The Auto DJ queue and the history playlists are skipped.
+    pub fn playlists(&self) -> Result<Vec<Playlist>, Error> {
+        self.track_lists(
+            "SELECT id, name FROM Playlists WHERE hidden = 0 ORDER BY position",
+            "SELECT track_id FROM PlaylistTracks WHERE playlist_id = ?1 ORDER BY position",
+        )
+        .map(|playlists| {
+            playlists
+                .into_iter()
+                .map(|(id, name, track_ids)| Playlist {
+                    id,
+                    name,
+                    track_ids,
+                })
+                .collect()
+        })
+    }
+
+    /// Returns the ID, name and track IDs of crates or playlists.
+    fn track_lists(
+        &self,
+        list_query: &str,
+        track_query: &str,
+    ) -> Result<Vec<(i64, String, Vec<i64>)>, Error> {
+        let mut statement = self.connection.prepare(list_query)?;
+        let lists = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<(i64, String)>, _>>()?;
+        let mut statement = self.connection.prepare(track_query)?;
+        let mut track_lists = vec![];
+        for (id, name) in lists {
+            let track_ids = statement
+                .query_map([id], |row| row.get(0))?
+                .collect::<Result<Vec<_>, _>>()?;
+            track_lists.push((id, name, track_ids));
+        }
+        Ok(track_lists)
+    }
+
+    /// Write the crates and playlists as subcrates of the given library.
+    ///
+    /// Crate and playlist names are split at ` / ` into the names of the parent crates and the
+    /// subcrate. Existing crates are not replaced, instead a number is appended to the name of the
+    /// new crate (e.g. `Warmup (2)`). The same applies to playlists that have the same name as a
+    /// crate. Tracks that are not located on the library's volume are skipped. Returns the names
+    /// of the written crates.
+    pub fn write_subcrates(&self, library: &Library) -> Result<Vec<String>, Error> {
+        let tracks = self.tracks()?;
+        let locations: HashMap<_, _> = tracks
+            .iter()
+            .map(|track| (track.id, track.location.as_path()))
+            .collect();
+        let crates = self.crates()?;
+        let playlists = self.playlists()?;
+        let track_lists: Vec<_> = crates
+            .iter()
+            .map(|list| (&list.name, &list.track_ids))
+            .chain(playlists.iter().map(|list| (&list.name, &list.track_ids)))
+            .map(|(name, track_ids)| {
+                let path: Vec<_> = name.split(CRATE_SEPARATOR).map(String::from).collect();
+                let track_paths: Vec<_> = track_ids
+                    .iter()
+                    .filter_map(|track_id| locations.get(track_id))
+                    .filter_map(|location| library.relative_path(location))
+                    .collect();
+                (path, track_paths)
+            })
+            .collect();
+        common::write_subcrates(
+            library,
+            track_lists
+                .iter()
+                .map(|(path, track_paths)| (path.as_slice(), track_paths.clone())),
+        )
+    }
+}
+
+#[test]
+fn test_beats() {
+    let beats = Beats::Grid {
+        bpm: 120.0,
+        first_beat: 44100,
+    };
+    let data = beats.to_bytes();
+    assert_eq!(
+        data,
+        [
+            0x0A, 0x09, 0x09, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x5E, 0x40, 0x12, 0x04, 0x08,
+            0xC4, 0xD8, 0x02
+        ]
+    );
+    assert_eq!(Beats::parse(BEAT_GRID_VERSION, &data), Some(beats));
+
+    let beats = Beats::Map(vec![-2, 0, 300]);
+    let data = beats.to_bytes();
+    assert_eq!(data.len(), 2 + 11 + 2 + 2 + 2 + 3);
+    assert_eq!(Beats::parse(BEAT_MAP_VERSION, &data), Some(beats));
+
+    // Disabled beats are skipped, unknown fields are ignored.
+    let data = [
+        0x0A, 0x04, 0x08, 0x02, 0x10, 0x00, 0x0A, 0x07, 0x08, 0x04, 0x1D, 0, 0, 0, 0, 0x0A, 0x02,
+        0x08, 0x06,
+    ];
+    assert_eq!(
+        Beats::parse(BEAT_MAP_VERSION, &data),
+        Some(Beats::Map(vec![4, 6]))
+    );
+    assert_eq!(Beats::parse(BEAT_MAP_VERSION, &[0x0A, 0x05, 0x08]), None);
+    assert_eq!(Beats::parse("BeatGrid-1.0", &data), None);
+}
+
+#[test]
+fn test_beats_from_beatgrid() {
+    let terminal_marker = TerminalMarker {
+        position: 1.0,
+        bpm: 120.0,
+    };
+    assert_eq!(
+        beats(&[], &terminal_marker, 100.0, Some(Duration::from_secs(10))),
+        Some(Beats::Grid {
+            bpm: 120.0,
+            first_beat: 100
+        })
+    );
+
+    let non_terminal_markers = [NonTerminalMarker {
+        position: 0.75,
+        beats_till_next_marker: 2,
+    }];
+    let terminal_marker = TerminalMarker {
+        position: 1.75,
+        bpm: 60.0,
+    };
+    assert_eq!(
+        beats(
+            &non_terminal_markers,
+            &terminal_marker,
+            100.0,
+            Some(Duration::from_secs(4))
+        ),
+        Some(Beats::Map(vec![25, 75, 125, 175, 275, 375]))
+    );
+    assert_eq!(
+        beats(&non_terminal_markers, &terminal_marker, 100.0, None),
+        Some(Beats::Grid {
+            bpm: 120.0,
+            first_beat: 75
+        })
+    );
+}
+
+#[test]
+fn test_write_library_restores_foreign_keys() {
+    let connection = Connection::open_in_memory().unwrap();
+    connection
+        .execute_batch(include_str!("../../tests/data/mixxx/schema.sql"))
+        .unwrap();
+    connection
+        .pragma_update(None, "foreign_keys", true)
+        .unwrap();
+    let mut database = MixxxDatabase::from_connection(connection);
+    let library = Library::read_from_path("tests/data/library/usb_drive").unwrap();
+    database.write_library(&library, &HashMap::new()).unwrap();
+    let foreign_keys: bool = database
+        .connection
+        .pragma_query_value(None, "foreign_keys", |row| row.get(0))
+        .unwrap();
+    assert!(foreign_keys);
+}
+
+#[test]
+fn test_datetime() {
+    let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_580_985_296);
+    assert_eq!(datetime(time), "2020-02-06 10:34:56");
+}
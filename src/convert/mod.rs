@@ -0,0 +1,34 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Converting Serato libraries and tags from/to the formats of other DJ software.
+//!
+//! Each format is gated behind a cargo feature:
+//!
+//! | Feature     | Software  | Format                    | Module
+//! | ----------- | --------- | ------------------------- | --------------------
+//! | `rekordbox` | Rekordbox | XML (`DJ_PLAYLISTS`)      | `convert::rekordbox`
+//! | `traktor`   | Traktor   | NML (`collection.nml`)    | `convert::traktor`
+//! | `mixxx`     | Mixxx     | SQLite (`mixxxdb.sqlite`) | `convert::mixxx`
+//! | `enginedj`  | Engine DJ | SQLite (`m.db`)           | `convert::enginedj`
+
+#[cfg(any(
+    feature = "rekordbox",
+    feature = "traktor",
+    feature = "mixxx",
+    feature = "enginedj"
+))]
+mod common;
+#[cfg(feature = "enginedj")]
+pub mod enginedj;
+#[cfg(feature = "mixxx")]
+pub mod mixxx;
+#[cfg(feature = "rekordbox")]
+pub mod rekordbox;
+#[cfg(feature = "traktor")]
+pub mod traktor;
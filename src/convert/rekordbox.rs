@@ -0,0 +1,805 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Rekordbox XML (`DJ_PLAYLISTS`) files.
+//!
+//! Rekordbox can import and export collections and playlists as XML files (*Preferences →
+//! Advanced → rekordbox xml*). Reading and writing is done using the
+//! [`quick-xml`](https://lib.rs/crates/quick-xml) crate.
+//!
+//! # Export
+//!
+//! The Serato data is mapped as follows:
+//!
+//! | Serato                       | Rekordbox
+//! | ---------------------------- | ------------------------------------------------------------
+//! | Library tracks               | `TRACK` elements in the `COLLECTION`
+//! | Beatgrid markers             | `TEMPO` elements (the BPM of non-terminal markers is computed)
+//! | Cues                         | Hot cues (`POSITION_MARK` with `Type="0"` and `Num` = index)
+//! | Saved loops                  | Memory loops (`POSITION_MARK` with `Type="4"` and `Num="-1"`)
+//! | Subcrates                    | Playlists in the `PLAYLISTS` tree
+//!
+//! Serato has separate slots for cues and saved loops, while Rekordbox uses the same slots for
+//! hot cues and hot loops. Saved loops are therefore exported as memory loops, so that they
+//! don't replace any hot cues. Since Rekordbox playlists can't contain both tracks and other
+//! playlists, a crate with subcrates is exported as a folder that contains a playlist with the
+//! crate's own tracks, followed by the subcrates.
+//!
+//! ```no_run
+//! use std::collections::HashMap;
+//! use triseratops::convert::rekordbox;
+//! use triseratops::library::Library;
+//!
+//! let library = Library::read_from_path("/Volumes/USB Drive").expect("Failed to read library!");
+//! // The Serato tags of each track, read from the files (e.g. using `TagContainer::read_from_path`).
+//! let tags = HashMap::new();
+//! rekordbox::write_xml_to_path("rekordbox.xml", &library, &tags)
+//!     .expect("Failed to export library!");
+//! ```
+//!
+//! # Import
+//!
+//! Rekordbox data is converted to Serato data as follows:
+//!
+//! | Rekordbox                    | Serato
+//! | ---------------------------- | ------------------------------------------------------------
+//! | `TEMPO` elements             | Beatgrid markers (see [`CollectionTrack::beatgrid()`])
+//! | Hot cues                     | Cues with the same index
+//! | Memory cues                  | Cues in the free slots (see [`CollectionTrack::cues()`])
+//! | Hot loops and memory loops   | Saved loops (see [`CollectionTrack::loops()`])
+//! | Colors                       | The closest Serato hotcue colors
+//! | Playlists and folders        | Subcrates (see [`RekordboxXml::write_subcrates()`])
+//!
+//! Fade-in, fade-out and load markers are ignored. The converted data can be written to tags
+//! using [`CollectionTrack::to_tag_container()`].
+//!
+//! ```no_run
+//! use triseratops::convert::rekordbox::RekordboxXml;
+//! use triseratops::library::Library;
+//! use triseratops::tag::TagFormat;
+//!
+//! let xml = RekordboxXml::read_from_path("rekordbox.xml").expect("Failed to read XML file!");
+//! for track in &xml.tracks {
+//!     let tags = track.to_tag_container(TagFormat::ID3).expect("Failed to convert track!");
+//!     // Write the tags to the file at `track.location` ...
+//! }
+//!
+//! let library = Library::read_from_path("/Volumes/USB Drive").expect("Failed to read library!");
+//! xml.write_subcrates(&library).expect("Failed to write crates!");
+//! ```
+
+use super::common::{self, ForeignMarker, PlaylistNode, date, tempo_markers};
+use crate::error::Error;
+use crate::library::{Library, Track};
+use crate::tag::color::Color;
+use crate::tag::generic::{Cue, Loop, Position};
+use crate::tag::{Beatgrid, TagContainer, TagFormat};
+use quick_xml::events::{BytesDecl, BytesStart, Event};
+use quick_xml::{Reader, Writer};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Name of the root element.
+const ROOT_ELEMENT: &str = "DJ_PLAYLISTS";
+
+/// Version of the XML format.
+const FORMAT_VERSION: &str = "1.0.0";
+
+/// Value of the `Type` attribute of folder nodes in the playlist tree.
+const NODE_TYPE_FOLDER: &str = "0";
+
+/// Value of the `Type` attribute of playlist nodes in the playlist tree.
+const NODE_TYPE_PLAYLIST: &str = "1";
+
+/// Value of the `Type` attribute of cue position marks.
+const POSITION_MARK_TYPE_CUE: &str = "0";
+
+/// Value of the `Type` attribute of loop position marks.
+const POSITION_MARK_TYPE_LOOP: &str = "4";
+
+/// Value of the `Num` attribute of memory cues and loops.
+const POSITION_MARK_NUM_MEMORY: &str = "-1";
+
+type Attributes = Vec<(&'static str, String)>;
+
+/// Returns the `file://localhost/...` URL that Rekordbox uses for the location of a file.
+fn location(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut location = String::from("file://localhost");
+    if !path.starts_with('/') {
+        location.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                location.push(char::from(byte));
+            }
+            _ => location.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    location
+}
+
+/// Formats a position in seconds, as used by `TEMPO` and `POSITION_MARK` elements.
+fn seconds(position: Position) -> String {
+    format!("{:.3}", f64::from(position.millis) / 1000.0)
+}
+
+fn track_attributes(track_id: usize, location: &Path, track: &Track) -> Attributes {
+    let mut attributes = vec![("TrackID", track_id.to_string())];
+    let text_attributes = [
+        ("Name", &track.title),
+        ("Artist", &track.artist),
+        ("Composer", &track.composer),
+        ("Album", &track.album),
+        ("Grouping", &track.grouping),
+        ("Genre", &track.genre),
+        ("Comments", &track.comment),
+        ("Label", &track.label),
+        ("Tonality", &track.key),
+        ("Year", &track.year),
+    ];
+    for (name, value) in text_attributes {
+        if let Some(value) = value {
+            attributes.push((name, value.clone()));
+        }
+    }
+    if let Some(file_type) = &track.file_type {
+        attributes.push(("Kind", format!("{} File", file_type.to_uppercase())));
+    }
+    if let Some(file_size) = track.file_size_bytes {
+        attributes.push(("Size", file_size.to_string()));
+    }
+    if let Some(length) = track.length {
+        attributes.push(("TotalTime", length.as_secs().to_string()));
+    }
+    if let Some(disc_number) = track.disc_number {
+        attributes.push(("DiscNumber", disc_number.to_string()));
+    }
+    if let Some(track_number) = track.track_number {
+        attributes.push(("TrackNumber", track_number.to_string()));
+    }
+    if let Some(bpm) = track.bpm {
+        attributes.push(("AverageBpm", format!("{bpm:.2}")));
+    }
+    if let Some(date_added) = track.date_added {
+        let (year, month, day) = date(date_added);
+        attributes.push(("DateAdded", format!("{year:04}-{month:02}-{day:02}")));
+    }
+    if let Some(bitrate) = track.bitrate {
+        attributes.push(("BitRate", format!("{bitrate:.0}")));
+    }
+    if let Some(sample_rate) = track.sample_rate {
+        attributes.push(("SampleRate", sample_rate.to_string()));
+    }
+    attributes.push(("Location", self::location(location)));
+    attributes
+}
+
+fn tempo_attributes(tags: &TagContainer) -> Vec<Attributes> {
+    let Some((non_terminal_markers, terminal_marker)) = tags.beatgrid() else {
+        return vec![];
+    };
+    tempo_markers(non_terminal_markers, terminal_marker)
+        .into_iter()
+        .map(|marker| {
+            vec![
+                ("Inizio", format!("{:.3}", marker.position)),
+                ("Bpm", format!("{:.2}", marker.bpm)),
+                ("Metro", "4/4".to_string()),
+                ("Battito", (marker.beat_index % 4 + 1).to_string()),
+            ]
+        })
+        .collect()
+}
+
+fn cue_attributes(cue: &Cue) -> Attributes {
+    let color = cue.color.into_pro_hotcue_color();
+    vec![
+        ("Name", cue.label.clone()),
+        ("Type", POSITION_MARK_TYPE_CUE.to_string()),
+        ("Start", seconds(cue.position)),
+        ("Num", cue.index.to_string()),
+        ("Red", color.red.to_string()),
+        ("Green", color.green.to_string()),
+        ("Blue", color.blue.to_string()),
+    ]
+}
+
+fn loop_attributes(saved_loop: &Loop) -> Attributes {
+    vec![
+        ("Name", saved_loop.label.clone()),
+        ("Type", POSITION_MARK_TYPE_LOOP.to_string()),
+        ("Start", seconds(saved_loop.start_position)),
+        ("End", seconds(saved_loop.end_position)),
+        ("Num", POSITION_MARK_NUM_MEMORY.to_string()),
+    ]
+}
+
+fn as_str_pairs(attributes: &Attributes) -> impl Iterator<Item = (&str, &str)> {
+    attributes
+        .iter()
+        .map(|(name, value)| (*name, value.as_str()))
+}
+
+/// Writes a node of the playlist tree, referencing tracks by their IDs.
+fn write_playlist_node(
+    writer: &mut Writer<impl io::Write>,
+    node: &PlaylistNode,
+    track_ids: &HashMap<&Path, usize>,
+) -> io::Result<()> {
+    match node {
+        PlaylistNode::Folder { name, children } => {
+            writer
+                .create_element("NODE")
+                .with_attribute(("Type", NODE_TYPE_FOLDER))
+                .with_attribute(("Name", name.as_str()))
+                .with_attribute(("Count", children.len().to_string().as_str()))
+                .write_inner_content(|writer| {
+                    for child in children {
+                        write_playlist_node(writer, child, track_ids)?;
+                    }
+                    Ok(())
+                })?;
+        }
+        PlaylistNode::Playlist { name, track_paths } => {
+            let track_ids: Vec<_> = track_paths
+                .iter()
+                .filter_map(|path| track_ids.get(path.as_path()))
+                .collect();
+            writer
+                .create_element("NODE")
+                .with_attribute(("Name", name.as_str()))
+                .with_attribute(("Type", NODE_TYPE_PLAYLIST))
+                .with_attribute(("KeyType", "0"))
+                .with_attribute(("Entries", track_ids.len().to_string().as_str()))
+                .write_inner_content(|writer| {
+                    for track_id in track_ids {
+                        writer
+                            .create_element("TRACK")
+                            .with_attribute(("Key", track_id.to_string().as_str()))
+                            .write_empty()?;
+                    }
+                    Ok(())
+                })?;
+        }
+    }
+    Ok(())
+}
+
+/// Write the library as Rekordbox XML to the given writer.
+///
+/// The `tags` map contains the Serato tags for each track, using the paths from the database as
+/// keys (see [`Library::tracks_with_paths()`]). Tracks without tags are exported without cues,
+/// loops and beatgrid. Tracks are sorted by their paths and numbered starting with 1.
+pub fn write_xml(
+    writer: impl io::Write,
+    library: &Library,
+    tags: &HashMap<PathBuf, TagContainer>,
+) -> Result<(), Error> {
+    let mut tracks: Vec<_> = library.tracks_with_paths().collect();
+    tracks.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let track_ids: HashMap<&Path, usize> = tracks
+        .iter()
+        .enumerate()
+        .map(|(i, (path, _))| (*path, i + 1))
+        .collect();
+
+    let root = PlaylistNode::Folder {
+        name: "ROOT".to_string(),
+        children: PlaylistNode::from_library(library)?,
+    };
+
+    let mut writer = Writer::new_with_indent(writer, b' ', 2);
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    writer
+        .create_element(ROOT_ELEMENT)
+        .with_attribute(("Version", FORMAT_VERSION))
+        .write_inner_content(|writer| {
+            writer
+                .create_element("PRODUCT")
+                .with_attribute(("Name", "triseratops"))
+                .with_attribute(("Version", env!("CARGO_PKG_VERSION")))
+                .with_attribute(("Company", ""))
+                .write_empty()?;
+            writer
+                .create_element("COLLECTION")
+                .with_attribute(("Entries", tracks.len().to_string().as_str()))
+                .write_inner_content(|writer| {
+                    for (path, track) in &tracks {
+                        let attributes =
+                            track_attributes(track_ids[path], &library.absolute_path(path), track);
+                        let tags = tags.get(*path);
+                        let element = writer
+                            .create_element("TRACK")
+                            .with_attributes(as_str_pairs(&attributes));
+                        let Some(tags) = tags else {
+                            element.write_empty()?;
+                            continue;
+                        };
+                        element.write_inner_content(|writer| {
+                            for attributes in tempo_attributes(tags) {
+                                writer
+                                    .create_element("TEMPO")
+                                    .with_attributes(as_str_pairs(&attributes))
+                                    .write_empty()?;
+                            }
+                            let position_marks = tags
+                                .cues()
+                                .iter()
+                                .map(cue_attributes)
+                                .chain(tags.loops().iter().map(loop_attributes))
+                                .collect::<Vec<_>>();
+                            for attributes in &position_marks {
+                                writer
+                                    .create_element("POSITION_MARK")
+                                    .with_attributes(as_str_pairs(attributes))
+                                    .write_empty()?;
+                            }
+                            Ok(())
+                        })?;
+                    }
+                    Ok(())
+                })?;
+            writer
+                .create_element("PLAYLISTS")
+                .write_inner_content(|writer| write_playlist_node(writer, &root, &track_ids))?;
+            Ok(())
+        })?;
+
+    Ok(())
+}
+
+/// Write the library as Rekordbox XML to the file at the given path.
+///
+/// If the file already exists, it is overwritten.
+pub fn write_xml_to_path(
+    path: impl AsRef<Path>,
+    library: &Library,
+    tags: &HashMap<PathBuf, TagContainer>,
+) -> Result<(), Error> {
+    let mut writer = io::BufWriter::new(fs::File::create(path)?);
+    write_xml(&mut writer, library, tags)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Value of the `KeyType` attribute of playlists that reference tracks by location.
+const KEY_TYPE_LOCATION: &str = "1";
+
+/// A `TEMPO` element, i.e. a beatgrid marker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tempo {
+    /// The position in seconds.
+    pub position: f64,
+    /// The tempo in beats per minute.
+    pub bpm: f64,
+    /// The position of the beat in the bar (1-4).
+    pub beat: u8,
+}
+
+/// The type of a [`PositionMark`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PositionMarkType {
+    Cue,
+    FadeIn,
+    FadeOut,
+    Load,
+    Loop,
+    Unknown(u8),
+}
+
+impl From<u8> for PositionMarkType {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Cue,
+            1 => Self::FadeIn,
+            2 => Self::FadeOut,
+            3 => Self::Load,
+            4 => Self::Loop,
+            value => Self::Unknown(value),
+        }
+    }
+}
+
+/// A `POSITION_MARK` element, i.e. a hot cue, memory cue or loop.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionMark {
+    pub name: String,
+    pub mark_type: PositionMarkType,
+    /// The start position in seconds.
+    pub start: f64,
+    /// The end position in seconds (for loops).
+    pub end: Option<f64>,
+    /// The hot cue number (starting at 0), or `-1` for memory cues and loops.
+    pub num: i32,
+    pub color: Option<Color>,
+}
+
+impl PositionMark {
+    fn to_foreign_marker(&self) -> ForeignMarker<'_> {
+        ForeignMarker {
+            name: &self.name,
+            start: self.start,
+            end: self.end,
+            hotcue: u8::try_from(self.num).ok(),
+            color: self.color,
+        }
+    }
+}
+
+/// A `TRACK` element in the `COLLECTION`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionTrack {
+    pub track_id: String,
+    /// The file system path of the track (decoded from the `Location` URL).
+    pub location: PathBuf,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub tempos: Vec<Tempo>,
+    pub position_marks: Vec<PositionMark>,
+}
+
+impl CollectionTrack {
+    /// Returns the cues, converted to Serato cues.
+    ///
+    /// Hot cues keep their number. Serato DJ has no memory cues and only 8 hotcue slots, so memory
+    /// cues and hot cues with a higher number are assigned to the free hotcue slots (in the order
+    /// of their position), unless there is already a hot cue at the same position. Colors are
+    /// mapped to the closest Serato hotcue colors, cues without a color get the default color of
+    /// their slot.
+    #[must_use]
+    pub fn cues(&self) -> Vec<Cue> {
+        self.foreign_markers(PositionMarkType::Cue, common::import_cues)
+    }
+
+    /// Returns the loops, converted to Serato saved loops.
+    ///
+    /// Hot loops (in the order of their number) come first, followed by memory loops (in the
+    /// order of their position). Duplicate loops and loops without an end position are skipped.
+    #[must_use]
+    pub fn loops(&self) -> Vec<Loop> {
+        self.foreign_markers(PositionMarkType::Loop, common::import_loops)
+    }
+
+    /// Returns the beatgrid, converted to a Serato beatgrid.
+    ///
+    /// Each `TEMPO` element becomes a beatgrid marker. The number of beats between two markers is
+    /// rounded to the nearest integer. The position of the beat in the bar is ignored, because
+    /// Serato beatgrids don't store it.
+    #[must_use]
+    pub fn beatgrid(&self) -> Option<Beatgrid> {
+        common::import_beatgrid(
+            self.tempos
+                .iter()
+                .map(|tempo| (tempo.position, tempo.bpm))
+                .collect(),
+        )
+    }
+
+    /// Converts the position marks of the given type.
+    fn foreign_markers<T>(
+        &self,
+        mark_type: PositionMarkType,
+        convert: impl FnOnce(&[ForeignMarker<'_>]) -> T,
+    ) -> T {
+        let markers: Vec<_> = self
+            .position_marks
+            .iter()
+            .filter(|mark| mark.mark_type == mark_type)
+            .map(PositionMark::to_foreign_marker)
+            .collect();
+        convert(&markers)
+    }
+
+    /// Returns a new tag container with the cues, loops and beatgrid of this track.
+    pub fn to_tag_container(&self, tag_format: TagFormat) -> Result<TagContainer, Error> {
+        let mut tags = TagContainer::with_empty_tags(tag_format);
+        for cue in self.cues() {
+            tags.set_cue(cue)?;
+        }
+        for saved_loop in self.loops() {
+            tags.set_loop(saved_loop)?;
+        }
+        if let Some(beatgrid) = self.beatgrid() {
+            tags.set_beatgrid(beatgrid);
+        }
+        Ok(tags)
+    }
+}
+
+/// A playlist in the `PLAYLISTS` tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Playlist {
+    /// The names of the parent folders and the playlist (without the `ROOT` folder).
+    pub path: Vec<String>,
+    /// The IDs of the tracks in the playlist.
+    pub track_ids: Vec<String>,
+}
+
+/// The content of a Rekordbox XML file.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RekordboxXml {
+    pub tracks: Vec<CollectionTrack>,
+    pub playlists: Vec<Playlist>,
+    /// The indices of the tracks by their ID (built when parsing).
+    track_indices: HashMap<String, usize>,
+}
+
+/// Returns the attributes of an element.
+fn parse_attributes(element: &BytesStart<'_>) -> Result<HashMap<String, String>, Error> {
+    let mut attributes = HashMap::new();
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(quick_xml::Error::from)?;
+        let key = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+        attributes.insert(key, attribute.unescape_value()?.into_owned());
+    }
+    Ok(attributes)
+}
+
+/// Returns the file system path for a `file://localhost/...` URL.
+fn parse_location(location: &str) -> PathBuf {
+    let path = location
+        .strip_prefix("file://localhost")
+        .or_else(|| location.strip_prefix("file://"))
+        .unwrap_or(location);
+    let mut bytes = vec![];
+    let mut input = path.as_bytes();
+    while let Some((byte, rest)) = input.split_first() {
+        let decoded = match (byte, rest) {
+            (b'%', [high, low, ..]) => std::str::from_utf8(&[*high, *low])
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        if let Some(decoded) = decoded {
+            bytes.push(decoded);
+            input = &rest[2..];
+        } else {
+            bytes.push(*byte);
+            input = rest;
+        }
+    }
+    let path = String::from_utf8_lossy(&bytes).into_owned();
+    // Windows paths (e.g. `/C:/Music`) don't start with a slash.
+    match path.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => PathBuf::from(&path[1..]),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn parse_tempo(attributes: &HashMap<String, String>) -> Option<Tempo> {
+    Some(Tempo {
+        position: attributes.get("Inizio")?.parse().ok()?,
+        bpm: attributes.get("Bpm")?.parse().ok()?,
+        beat: attributes
+            .get("Battito")
+            .and_then(|beat| beat.parse().ok())
+            .unwrap_or(1),
+    })
+}
+
+fn parse_position_mark(attributes: &HashMap<String, String>) -> Option<PositionMark> {
+    let color_component = |name| attributes.get(name)?.parse::<u8>().ok();
+    let color = match (
+        color_component("Red"),
+        color_component("Green"),
+        color_component("Blue"),
+    ) {
+        (Some(red), Some(green), Some(blue)) => Some(Color { red, green, blue }),
+        _ => None,
+    };
+    Some(PositionMark {
+        name: attributes.get("Name").cloned().unwrap_or_default(),
+        mark_type: PositionMarkType::from(attributes.get("Type")?.parse::<u8>().ok()?),
+        start: attributes.get("Start")?.parse().ok()?,
+        end: attributes.get("End").and_then(|end| end.parse().ok()),
+        num: attributes
+            .get("Num")
+            .and_then(|num| num.parse().ok())
+            .unwrap_or(-1),
+        color,
+    })
+}
+
+impl RekordboxXml {
+    /// Parse Rekordbox XML data from the given reader.
+    ///
+    /// Elements and attributes that are unknown or malformed are ignored.
+    pub fn parse(reader: impl io::BufRead) -> Result<Self, Error> {
+        let mut reader = Reader::from_reader(reader);
+        reader.config_mut().trim_text(true);
+
+        let mut xml = Self::default();
+        let mut buffer = vec![];
+        let mut is_in_collection = false;
+        let mut current_track: Option<CollectionTrack> = None;
+        let mut folders: Vec<String> = vec![];
+        let mut current_playlist: Option<(Playlist, bool)> = None;
+        let mut track_locations: HashMap<String, String> = HashMap::new();
+        loop {
+            buffer.clear();
+            let event = reader.read_event_into(&mut buffer)?;
+            let (element, is_empty) = match &event {
+                Event::Start(element) => (element, false),
+                Event::Empty(element) => (element, true),
+                Event::End(element) => {
+                    match element.name().as_ref() {
+                        b"COLLECTION" => is_in_collection = false,
+                        b"TRACK" => {
+                            if let Some(track) = current_track.take() {
+                                xml.tracks.push(track);
+                            }
+                        }
+                        b"NODE" => {
+                            if let Some((playlist, _)) = current_playlist.take() {
+                                xml.playlists.push(playlist);
+                            } else {
+                                folders.pop();
+                            }
+                        }
+                        _ => (),
+                    }
+                    continue;
+                }
+                Event::Eof => break,
+                _ => {
+                    continue;
+                }
+            };
+            let attributes = parse_attributes(element)?;
+            match element.name().as_ref() {
+                b"COLLECTION" => is_in_collection = !is_empty,
+                b"TRACK" if is_in_collection => {
+                    let Some(track_id) = attributes.get("TrackID") else {
+                        continue;
+                    };
+                    let location = attributes.get("Location").cloned().unwrap_or_default();
+                    track_locations
+                        .entry(location.clone())
+                        .or_insert_with(|| track_id.clone());
+                    let track = CollectionTrack {
+                        track_id: track_id.clone(),
+                        location: parse_location(&location),
+                        title: attributes.get("Name").cloned(),
+                        artist: attributes.get("Artist").cloned(),
+                        tempos: vec![],
+                        position_marks: vec![],
+                    };
+                    if is_empty {
+                        xml.tracks.push(track);
+                    } else {
+                        current_track = Some(track);
+                    }
+                }
+                b"TEMPO" => {
+                    if let (Some(track), Some(tempo)) =
+                        (&mut current_track, parse_tempo(&attributes))
+                    {
+                        track.tempos.push(tempo);
+                    }
+                }
+                b"POSITION_MARK" => {
+                    if let (Some(track), Some(mark)) =
+                        (&mut current_track, parse_position_mark(&attributes))
+                    {
+                        track.position_marks.push(mark);
+                    }
+                }
+                b"NODE" => {
+                    let name = attributes.get("Name").cloned().unwrap_or_default();
+                    let is_folder =
+                        attributes.get("Type").map(String::as_str) == Some(NODE_TYPE_FOLDER);
+                    if is_folder {
+                        if !is_empty {
+                            folders.push(name);
+                        }
+                        continue;
+                    }
+                    // The path doesn't include the `ROOT` folder.
+                    let mut path: Vec<_> = folders.iter().skip(1).cloned().collect();
+                    path.push(name);
+                    let playlist = Playlist {
+                        path,
+                        track_ids: vec![],
+                    };
+                    let is_keyed_by_location =
+                        attributes.get("KeyType").map(String::as_str) == Some(KEY_TYPE_LOCATION);
+                    if is_empty {
+                        xml.playlists.push(playlist);
+                    } else {
+                        current_playlist = Some((playlist, is_keyed_by_location));
+                    }
+                }
+                b"TRACK" => {
+                    if let (Some((playlist, is_keyed_by_location)), Some(key)) =
+                        (&mut current_playlist, attributes.get("Key"))
+                    {
+                        let track_id = if *is_keyed_by_location {
+                            track_locations.get(key).cloned()
+                        } else {
+                            Some(key.clone())
+                        };
+                        playlist.track_ids.extend(track_id);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        for (index, track) in xml.tracks.iter().enumerate() {
+            xml.track_indices
+                .entry(track.track_id.clone())
+                .or_insert(index);
+        }
+        Ok(xml)
+    }
+
+    /// Read the Rekordbox XML file at the given path.
+    pub fn read_from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let file = fs::File::open(path)?;
+        Self::parse(io::BufReader::new(file))
+    }
+
+    /// Returns the track with the given ID.
+    ///
+    /// The track is looked up in an index that is built when parsing. Tracks that have been added
+    /// to [`RekordboxXml::tracks`] afterwards are searched one by one.
+    #[must_use]
+    pub fn track(&self, track_id: &str) -> Option<&CollectionTrack> {
+        self.track_indices
+            .get(track_id)
+            .and_then(|index| self.tracks.get(*index))
+            .filter(|track| track.track_id == track_id)
+            .or_else(|| self.tracks.iter().find(|track| track.track_id == track_id))
+    }
+
+    /// Write the playlists as subcrates of the given library.
+    ///
+    /// Folders become parent crates. Existing crates are not replaced, instead a number is
+    /// appended to the name of the new crate (e.g. `Warmup (2)`). Tracks that are not located on
+    /// the library's volume are skipped. Returns the names of the written crates.
+    pub fn write_subcrates(&self, library: &Library) -> Result<Vec<String>, Error> {
+        let playlists = self.playlists.iter().map(|playlist| {
+            let track_paths = playlist
+                .track_ids
+                .iter()
+                .filter_map(|track_id| self.track(track_id))
+                .filter_map(|track| library.relative_path(&track.location))
+                .collect();
+            (playlist.path.as_slice(), track_paths)
+        });
+        common::write_subcrates(library, playlists)
+    }
+}
+
+#[test]
+fn test_location() {
+    assert_eq!(
+        location(Path::new("/Volumes/USB Drive/Music/Café & Bar.mp3")),
+        "file://localhost/Volumes/USB%20Drive/Music/Caf%C3%A9%20%26%20Bar.mp3"
+    );
+    assert_eq!(
+        location(Path::new("C:\\Music\\track.mp3")),
+        "file://localhost/C:/Music/track.mp3"
+    );
+
+    for path in [
+        "/Volumes/USB Drive/Music/Café & Bar.mp3",
+        "C:/Music/track.mp3",
+    ] {
+        assert_eq!(parse_location(&location(Path::new(path))), Path::new(path));
+    }
+    assert_eq!(
+        parse_location("file:///Music/100%25.mp3"),
+        Path::new("/Music/100%.mp3")
+    );
+}
@@ -0,0 +1,915 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Traktor collection files (`collection.nml`).
+//!
+//! Traktor stores its collection and playlists in an XML file called `collection.nml`, which can
+//! also be exported and imported via *File → Export/Import Collection*. Reading and writing is
+//! done using the [`quick-xml`](https://lib.rs/crates/quick-xml) crate.
+//!
+//! Traktor identifies tracks by the name of the volume (`VOLUME`) and the path on that volume
+//! (`DIR` and `FILE`). The volume name is the last component of the library's volume root (e.g.
+//! `USB Drive` for `/Volumes/USB Drive`, or `C:` for `C:\`). The name of the system volume on
+//! macOS (`/`) can't be determined, so it is left empty and Traktor may need to relocate these
+//! tracks.
+//!
+//! # Export
+//!
+//! The Serato data is mapped as follows:
+//!
+//! | Serato                       | Traktor
+//! | ---------------------------- | ------------------------------------------------------------
+//! | Library tracks               | `ENTRY` elements in the `COLLECTION`
+//! | Beatgrid markers             | `TEMPO` element and grid markers (`CUE_V2` with `TYPE="4"`)
+//! | Cues                         | Hot cues (`CUE_V2` with `TYPE="0"` and `HOTCUE` = index)
+//! | Saved loops                  | Stored loops (`CUE_V2` with `TYPE="5"` and `HOTCUE="-1"`)
+//! | Subcrates                    | Playlists in the `PLAYLISTS` tree
+//!
+//! Traktor only stores a single tempo for each track, while the tempo of Serato beatgrids may
+//! change at every marker. How beatgrids with multiple markers are exported is chosen using
+//! [`GridStrategy`]. Serato has separate slots for cues and saved loops, while Traktor uses the
+//! same slots for hot cues and loops. Saved loops are therefore exported as stored loops without
+//! a hotcue slot, so that they don't replace any hot cues. A crate with subcrates is exported as
+//! a folder that contains a playlist with the crate's own tracks, followed by the subcrates.
+//!
+//! ```no_run
+//! use std::collections::HashMap;
+//! use triseratops::convert::traktor::{self, GridStrategy};
+//! use triseratops::library::Library;
+//!
+//! let library = Library::read_from_path("/Volumes/USB Drive").expect("Failed to read library!");
+//! // The Serato tags of each track, read from the files (e.g. using `TagContainer::read_from_path`).
+//! let tags = HashMap::new();
+//! traktor::write_nml_to_path("collection.nml", &library, &tags, GridStrategy::SingleMarker)
+//!     .expect("Failed to export library!");
+//! ```
+//!
+//! # Import
+//!
+//! Traktor data is converted to Serato data as follows:
+//!
+//! | Traktor                      | Serato
+//! | ---------------------------- | ------------------------------------------------------------
+//! | `TEMPO` and grid markers     | Beatgrid markers (see [`CollectionEntry::beatgrid()`])
+//! | Hot cues                     | Cues with the same index
+//! | Cues without hotcue slot     | Cues in the free slots (see [`CollectionEntry::cues()`])
+//! | Loops                        | Saved loops (see [`CollectionEntry::loops()`])
+//! | Playlists and folders        | Subcrates (see [`TraktorNml::write_subcrates()`])
+//!
+//! Fade-in, fade-out and load markers are ignored. Traktor doesn't store cue colors in the NML
+//! file, so cues get the default color of their slot. The converted data can be written to tags
+//! using [`CollectionEntry::to_tag_container()`].
+//!
+//! ```no_run
+//! use triseratops::convert::traktor::TraktorNml;
+//! use triseratops::library::Library;
+//! use triseratops::tag::TagFormat;
+//!
+//! let nml = TraktorNml::read_from_path("collection.nml").expect("Failed to read NML file!");
+//! for entry in &nml.entries {
+//!     let tags = entry.to_tag_container(TagFormat::ID3).expect("Failed to convert entry!");
+//!     // Write the tags to the file at `entry.path` on the volume `entry.volume` ...
+//! }
+//!
+//! let library = Library::read_from_path("/Volumes/USB Drive").expect("Failed to read library!");
+//! nml.write_subcrates(&library).expect("Failed to write crates!");
+//! ```
+
+use super::common::{self, ForeignMarker, PlaylistNode, date, tempo_markers};
+use crate::error::Error;
+use crate::library::{Library, Track};
+use crate::tag::generic::{Cue, Loop, Position};
+use crate::tag::{Beatgrid, TagContainer, TagFormat};
+use quick_xml::events::{BytesDecl, BytesStart, Event};
+use quick_xml::{Reader, Writer};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Version of the NML format.
+const FORMAT_VERSION: &str = "19";
+
+/// Separator of the directories in the `DIR` attribute.
+const DIR_SEPARATOR: &str = "/:";
+
+/// Name of the root folder in the playlist tree.
+const ROOT_FOLDER: &str = "$ROOT";
+
+/// Value of the `TYPE` attribute of folder nodes in the playlist tree.
+const NODE_TYPE_FOLDER: &str = "FOLDER";
+
+/// Value of the `TYPE` attribute of playlist nodes in the playlist tree.
+const NODE_TYPE_PLAYLIST: &str = "PLAYLIST";
+
+/// Value of the `HOTCUE` attribute of cues and loops that are not assigned to a hotcue slot.
+const HOTCUE_NONE: &str = "-1";
+
+type Attributes = Vec<(&'static str, String)>;
+
+/// How Serato beatgrids with multiple markers are converted to Traktor grid markers.
+///
+/// Traktor only stores a single tempo for each track. For both strategies, the exported tempo is
+/// the average tempo between the first and the terminal marker (or the tempo of the terminal
+/// marker if the beatgrid has no other markers). The tempo after the terminal marker is lost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GridStrategy {
+    /// Export a single grid marker at the position of the first Serato marker.
+    ///
+    /// The grid matches the Serato beatgrid for tracks with a constant tempo, but drifts for
+    /// tracks with a variable tempo.
+    #[default]
+    SingleMarker,
+    /// Export a grid marker for each Serato marker.
+    ///
+    /// Traktor realigns the grid at every marker, so it drifts at most until the next marker. The
+    /// tempo between the markers isn't preserved, though.
+    AllMarkers,
+}
+
+/// Returns the name that Traktor uses for the volume with the given root directory.
+fn volume_name(volume_root: &Path) -> String {
+    if let Some(name) = volume_root.file_name() {
+        return name.to_string_lossy().into_owned();
+    }
+    volume_root
+        .to_string_lossy()
+        .trim_end_matches(['/', '\\'])
+        .to_string()
+}
+
+/// Returns the `DIR` attribute for a path relative to the volume root (e.g. `/:Music/:House/:`).
+fn dir(path: &Path) -> String {
+    let mut dir = String::from(DIR_SEPARATOR);
+    for component in path.parent().into_iter().flat_map(Path::components) {
+        dir.push_str(&component.as_os_str().to_string_lossy());
+        dir.push_str(DIR_SEPARATOR);
+    }
+    dir
+}
+
+/// Returns the path relative to the volume root for the `DIR` and `FILE` attributes.
+fn parse_dir(dir: &str, file: &str) -> PathBuf {
+    dir.split(DIR_SEPARATOR)
+        .filter(|directory| !directory.is_empty())
+        .chain(std::iter::once(file))
+        .collect()
+}
+
+/// Formats a position in milliseconds, as used by `CUE_V2` elements.
+fn millis(millis: f64) -> String {
+    format!("{millis:.6}")
+}
+
+/// A track in the exported collection.
+struct ExportEntry<'a> {
+    /// The primary key that playlists use to reference the track.
+    key: String,
+    volume: &'a str,
+    path: &'a Path,
+    track: &'a Track,
+    tags: Option<&'a TagContainer>,
+}
+
+fn location_attributes(volume: &str, path: &Path) -> Attributes {
+    let file = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    vec![
+        ("DIR", dir(path)),
+        ("FILE", file),
+        ("VOLUME", volume.to_string()),
+        ("VOLUMEID", volume.to_string()),
+    ]
+}
+
+fn album_attributes(track: &Track) -> Attributes {
+    let mut attributes = vec![];
+    if let Some(track_number) = track.track_number {
+        attributes.push(("TRACK", track_number.to_string()));
+    }
+    if let Some(album) = &track.album {
+        attributes.push(("TITLE", album.clone()));
+    }
+    attributes
+}
+
+fn info_attributes(track: &Track) -> Attributes {
+    let mut attributes = vec![];
+    if let Some(bitrate) = track.bitrate {
+        attributes.push(("BITRATE", format!("{:.0}", bitrate * 1000.0)));
+    }
+    let text_attributes = [
+        ("GENRE", &track.genre),
+        ("LABEL", &track.label),
+        ("COMMENT", &track.comment),
+        ("KEY", &track.key),
+    ];
+    for (name, value) in text_attributes {
+        if let Some(value) = value {
+            attributes.push((name, value.clone()));
+        }
+    }
+    if let Some(length) = track.length {
+        attributes.push(("PLAYTIME", length.as_secs().to_string()));
+    }
+    if let Some(date_added) = track.date_added {
+        let (year, month, day) = date(date_added);
+        attributes.push(("IMPORT_DATE", format!("{year}/{month}/{day}")));
+    }
+    if let Some(file_size) = track.file_size_bytes {
+        attributes.push(("FILESIZE", (file_size / 1024).to_string()));
+    }
+    attributes
+}
+
+/// Returns the tempo and the grid marker positions (in seconds) of the beatgrid.
+fn grid(tags: &TagContainer, strategy: GridStrategy) -> Option<(f64, Vec<f64>)> {
+    let (non_terminal_markers, terminal_marker) = tags.beatgrid()?;
+    let markers = tempo_markers(non_terminal_markers, terminal_marker);
+    let bpm = common::average_bpm(&markers)?;
+    let positions = match strategy {
+        GridStrategy::SingleMarker => vec![markers.first()?.position],
+        GridStrategy::AllMarkers => markers.iter().map(|marker| marker.position).collect(),
+    };
+    Some((bpm, positions))
+}
+
+fn grid_marker_attributes(position: f64) -> Attributes {
+    vec![
+        ("NAME", "Beat Marker".to_string()),
+        ("DISPL_ORDER", "0".to_string()),
+        ("TYPE", u8::from(CueType::Grid).to_string()),
+        ("START", millis(position * 1000.0)),
+        ("LEN", millis(0.0)),
+        ("REPEATS", "-1".to_string()),
+        ("HOTCUE", HOTCUE_NONE.to_string()),
+    ]
+}
+
+fn cue_attributes(cue: &Cue) -> Attributes {
+    vec![
+        ("NAME", cue.label.clone()),
+        ("DISPL_ORDER", "0".to_string()),
+        ("TYPE", u8::from(CueType::Cue).to_string()),
+        ("START", millis(f64::from(cue.position.millis))),
+        ("LEN", millis(0.0)),
+        ("REPEATS", "-1".to_string()),
+        ("HOTCUE", cue.index.to_string()),
+    ]
+}
+
+fn loop_attributes(saved_loop: &Loop) -> Attributes {
+    let Position { millis: start } = saved_loop.start_position;
+    let Position { millis: end } = saved_loop.end_position;
+    vec![
+        ("NAME", saved_loop.label.clone()),
+        ("DISPL_ORDER", "0".to_string()),
+        ("TYPE", u8::from(CueType::Loop).to_string()),
+        ("START", millis(f64::from(start))),
+        ("LEN", millis(f64::from(end.saturating_sub(start)))),
+        ("REPEATS", "-1".to_string()),
+        ("HOTCUE", HOTCUE_NONE.to_string()),
+    ]
+}
+
+fn as_str_pairs(attributes: &Attributes) -> impl Iterator<Item = (&str, &str)> {
+    attributes
+        .iter()
+        .map(|(name, value)| (*name, value.as_str()))
+}
+
+fn write_entry(
+    writer: &mut Writer<impl io::Write>,
+    entry: &ExportEntry<'_>,
+    strategy: GridStrategy,
+) -> io::Result<()> {
+    let mut attributes = vec![];
+    if let Some(title) = &entry.track.title {
+        attributes.push(("TITLE", title.clone()));
+    }
+    if let Some(artist) = &entry.track.artist {
+        attributes.push(("ARTIST", artist.clone()));
+    }
+    let grid = entry.tags.and_then(|tags| grid(tags, strategy));
+    writer
+        .create_element("ENTRY")
+        .with_attributes(as_str_pairs(&attributes))
+        .write_inner_content(|writer| {
+            writer
+                .create_element("LOCATION")
+                .with_attributes(as_str_pairs(&location_attributes(entry.volume, entry.path)))
+                .write_empty()?;
+            let album_attributes = album_attributes(entry.track);
+            if !album_attributes.is_empty() {
+                writer
+                    .create_element("ALBUM")
+                    .with_attributes(as_str_pairs(&album_attributes))
+                    .write_empty()?;
+            }
+            writer
+                .create_element("INFO")
+                .with_attributes(as_str_pairs(&info_attributes(entry.track)))
+                .write_empty()?;
+            let bpm = grid.as_ref().map(|(bpm, _)| *bpm).or(entry.track.bpm);
+            if let Some(bpm) = bpm {
+                writer
+                    .create_element("TEMPO")
+                    .with_attribute(("BPM", format!("{bpm:.6}").as_str()))
+                    .with_attribute(("BPM_QUALITY", "100.000000"))
+                    .write_empty()?;
+            }
+
+            let mut cues = vec![];
+            if let Some((_, positions)) = &grid {
+                cues.extend(positions.iter().copied().map(grid_marker_attributes));
+            }
+            if let Some(tags) = entry.tags {
+                cues.extend(tags.cues().iter().map(cue_attributes));
+                cues.extend(tags.loops().iter().map(loop_attributes));
+            }
+            for attributes in &cues {
+                writer
+                    .create_element("CUE_V2")
+                    .with_attributes(as_str_pairs(attributes))
+                    .write_empty()?;
+            }
+            Ok(())
+        })?;
+    Ok(())
+}
+
+/// Returns a (deterministic) UUID for the playlist with the given path.
+///
+/// The UUID is derived from the path using the 64-bit FNV-1a hash, which (unlike the hasher of
+/// the standard library) is guaranteed to return the same value across Rust versions. That way,
+/// Traktor recognizes playlists that are exported again.
+fn playlist_uuid(path: &[&str]) -> String {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let hash = |seed: u8| {
+        let bytes = path
+            .iter()
+            .flat_map(|name| name.bytes().chain([0]))
+            .chain([seed]);
+        bytes.fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+        })
+    };
+    format!("{:016x}{:016x}", hash(0), hash(1))
+}
+
+#[test]
+fn test_playlist_uuid() {
+    assert_eq!(
+        playlist_uuid(&["Gigs", "Club"]),
+        playlist_uuid(&["Gigs", "Club"])
+    );
+    assert_ne!(
+        playlist_uuid(&["Gigs", "Club"]),
+        playlist_uuid(&["GigsClub"])
+    );
+    assert_eq!(
+        playlist_uuid(&["Warmup"]),
+        "2e328e4d8afacd4d2e328d4d8afacb9a"
+    );
+}
+
+/// Writes a node of the playlist tree, referencing tracks by their primary keys.
+fn write_playlist_node(
+    writer: &mut Writer<impl io::Write>,
+    node: &PlaylistNode,
+    parents: &[&str],
+    keys: &HashMap<&Path, &str>,
+) -> io::Result<()> {
+    match node {
+        PlaylistNode::Folder { name, children } => {
+            let mut path = parents.to_vec();
+            path.push(name);
+            writer
+                .create_element("NODE")
+                .with_attribute(("TYPE", NODE_TYPE_FOLDER))
+                .with_attribute(("NAME", name.as_str()))
+                .write_inner_content(|writer| {
+                    writer
+                        .create_element("SUBNODES")
+                        .with_attribute(("COUNT", children.len().to_string().as_str()))
+                        .write_inner_content(|writer| {
+                            for child in children {
+                                write_playlist_node(writer, child, &path, keys)?;
+                            }
+                            Ok(())
+                        })?;
+                    Ok(())
+                })?;
+        }
+        PlaylistNode::Playlist { name, track_paths } => {
+            let mut path = parents.to_vec();
+            path.push(name);
+            let keys: Vec<_> = track_paths
+                .iter()
+                .filter_map(|path| keys.get(path.as_path()))
+                .collect();
+            writer
+                .create_element("NODE")
+                .with_attribute(("TYPE", NODE_TYPE_PLAYLIST))
+                .with_attribute(("NAME", name.as_str()))
+                .write_inner_content(|writer| {
+                    writer
+                        .create_element("PLAYLIST")
+                        .with_attribute(("ENTRIES", keys.len().to_string().as_str()))
+                        .with_attribute(("TYPE", "LIST"))
+                        .with_attribute(("UUID", playlist_uuid(&path).as_str()))
+                        .write_inner_content(|writer| {
+                            for key in keys {
+                                writer
+                                    .create_element("ENTRY")
+                                    .write_inner_content(|writer| {
+                                        writer
+                                            .create_element("PRIMARYKEY")
+                                            .with_attribute(("TYPE", "TRACK"))
+                                            .with_attribute(("KEY", *key))
+                                            .write_empty()?;
+                                        Ok(())
+                                    })?;
+                            }
+                            Ok(())
+                        })?;
+                    Ok(())
+                })?;
+        }
+    }
+    Ok(())
+}
+
+/// Write the library as Traktor NML to the given writer.
+///
+/// The `tags` map contains the Serato tags for each track, using the paths from the database as
+/// keys (see [`Library::tracks_with_paths()`]). Tracks without tags are exported without cues,
+/// loops and grid markers. Tracks are sorted by their paths.
+pub fn write_nml(
+    writer: impl io::Write,
+    library: &Library,
+    tags: &HashMap<PathBuf, TagContainer>,
+    strategy: GridStrategy,
+) -> Result<(), Error> {
+    let volume = volume_name(library.volume_root());
+    let mut entries: Vec<_> = library
+        .tracks_with_paths()
+        .map(|(path, track)| {
+            let file = path.file_name().unwrap_or_default().to_string_lossy();
+            ExportEntry {
+                key: format!("{volume}{}{file}", dir(path)),
+                volume: &volume,
+                path,
+                track,
+                tags: tags.get(path),
+            }
+        })
+        .collect();
+    entries.sort_by(|a, b| a.path.cmp(b.path));
+    let keys: HashMap<&Path, &str> = entries
+        .iter()
+        .map(|entry| (entry.path, entry.key.as_str()))
+        .collect();
+
+    let root = PlaylistNode::Folder {
+        name: ROOT_FOLDER.to_string(),
+        children: PlaylistNode::from_library(library)?,
+    };
+
+    let mut writer = Writer::new_with_indent(writer, b' ', 2);
+    writer.write_event(Event::Decl(BytesDecl::new(
+        "1.0",
+        Some("UTF-8"),
+        Some("no"),
+    )))?;
+    writer
+        .create_element("NML")
+        .with_attribute(("VERSION", FORMAT_VERSION))
+        .write_inner_content(|writer| {
+            writer
+                .create_element("HEAD")
+                .with_attribute(("COMPANY", "www.native-instruments.com"))
+                .with_attribute(("PROGRAM", "Traktor"))
+                .write_empty()?;
+            writer.create_element("MUSICFOLDERS").write_empty()?;
+            writer
+                .create_element("COLLECTION")
+                .with_attribute(("ENTRIES", entries.len().to_string().as_str()))
+                .write_inner_content(|writer| {
+                    for entry in &entries {
+                        write_entry(writer, entry, strategy)?;
+                    }
+                    Ok(())
+                })?;
+            writer
+                .create_element("PLAYLISTS")
+                .write_inner_content(|writer| write_playlist_node(writer, &root, &[], &keys))?;
+            Ok(())
+        })?;
+
+    Ok(())
+}
+
+/// Write the library as Traktor NML to the file at the given path.
+///
+/// If the file already exists, it is overwritten.
+pub fn write_nml_to_path(
+    path: impl AsRef<Path>,
+    library: &Library,
+    tags: &HashMap<PathBuf, TagContainer>,
+    strategy: GridStrategy,
+) -> Result<(), Error> {
+    let mut writer = io::BufWriter::new(fs::File::create(path)?);
+    write_nml(&mut writer, library, tags, strategy)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// The type of a [`CueV2`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CueType {
+    Cue,
+    FadeIn,
+    FadeOut,
+    Load,
+    Grid,
+    Loop,
+    Unknown(u8),
+}
+
+impl From<u8> for CueType {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Cue,
+            1 => Self::FadeIn,
+            2 => Self::FadeOut,
+            3 => Self::Load,
+            4 => Self::Grid,
+            5 => Self::Loop,
+            value => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<CueType> for u8 {
+    fn from(value: CueType) -> Self {
+        match value {
+            CueType::Cue => 0,
+            CueType::FadeIn => 1,
+            CueType::FadeOut => 2,
+            CueType::Load => 3,
+            CueType::Grid => 4,
+            CueType::Loop => 5,
+            CueType::Unknown(value) => value,
+        }
+    }
+}
+
+/// A `CUE_V2` element, i.e. a cue, loop or grid marker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CueV2 {
+    pub name: String,
+    pub cue_type: CueType,
+    /// The start position in milliseconds.
+    pub start: f64,
+    /// The length in milliseconds (for loops).
+    pub len: f64,
+    /// The hotcue slot (starting at 0), or `-1` if the cue is not assigned to a slot.
+    pub hotcue: i32,
+}
+
+impl CueV2 {
+    fn to_foreign_marker(&self) -> ForeignMarker<'_> {
+        ForeignMarker {
+            name: &self.name,
+            start: self.start / 1000.0,
+            end: (self.len > 0.0).then(|| (self.start + self.len) / 1000.0),
+            hotcue: u8::try_from(self.hotcue).ok(),
+            color: None,
+        }
+    }
+}
+
+/// An `ENTRY` element in the `COLLECTION`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionEntry {
+    /// The primary key that playlists use to reference the entry.
+    pub key: String,
+    /// The name of the volume the track is located on.
+    pub volume: String,
+    /// The path of the track relative to the volume root (decoded from `DIR` and `FILE`).
+    pub path: PathBuf,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    /// The tempo of the `TEMPO` element.
+    pub bpm: Option<f64>,
+    pub cues: Vec<CueV2>,
+}
+
+impl CollectionEntry {
+    /// Returns the cues, converted to Serato cues.
+    ///
+    /// Hot cues keep their slot. Cues that are not assigned to a hotcue slot are assigned to the
+    /// free slots (in the order of their position), unless there is already a hot cue at the
+    /// same position. All cues get the default color of their slot.
+    #[must_use]
+    pub fn cues(&self) -> Vec<Cue> {
+        self.foreign_markers(CueType::Cue, common::import_cues)
+    }
+
+    /// Returns the loops, converted to Serato saved loops.
+    ///
+    /// Loops with a hotcue slot (in the order of their slot) come first, followed by the other
+    /// loops (in the order of their position). Duplicate loops and loops without a length are
+    /// skipped.
+    #[must_use]
+    pub fn loops(&self) -> Vec<Loop> {
+        self.foreign_markers(CueType::Loop, common::import_loops)
+    }
+
+    /// Returns the beatgrid, converted to a Serato beatgrid.
+    ///
+    /// Each grid marker becomes a beatgrid marker with the tempo of the `TEMPO` element. The
+    /// number of beats between two markers is rounded to the nearest integer. Entries without a
+    /// tempo or without grid markers have no beatgrid.
+    #[must_use]
+    pub fn beatgrid(&self) -> Option<Beatgrid> {
+        let bpm = self.bpm.filter(|bpm| *bpm > 0.0)?;
+        common::import_beatgrid(
+            self.cues
+                .iter()
+                .filter(|cue| cue.cue_type == CueType::Grid)
+                .map(|cue| (cue.start / 1000.0, bpm))
+                .collect(),
+        )
+    }
+
+    /// Returns a new tag container with the cues, loops and beatgrid of this entry.
+    pub fn to_tag_container(&self, tag_format: TagFormat) -> Result<TagContainer, Error> {
+        let mut tags = TagContainer::with_empty_tags(tag_format);
+        for cue in self.cues() {
+            tags.set_cue(cue)?;
+        }
+        for saved_loop in self.loops() {
+            tags.set_loop(saved_loop)?;
+        }
+        if let Some(beatgrid) = self.beatgrid() {
+            tags.set_beatgrid(beatgrid);
+        }
+        Ok(tags)
+    }
+
+    /// Converts the cues of the given type.
+    fn foreign_markers<T>(
+        &self,
+        cue_type: CueType,
+        convert: impl FnOnce(&[ForeignMarker<'_>]) -> T,
+    ) -> T {
+        let markers: Vec<_> = self
+            .cues
+            .iter()
+            .filter(|cue| cue.cue_type == cue_type)
+            .map(CueV2::to_foreign_marker)
+            .collect();
+        convert(&markers)
+    }
+}
+
+/// A playlist in the `PLAYLISTS` tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Playlist {
+    /// The names of the parent folders and the playlist (without the `$ROOT` folder).
+    pub path: Vec<String>,
+    /// The primary keys of the entries in the playlist.
+    pub keys: Vec<String>,
+}
+
+/// The content of a Traktor NML file.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TraktorNml {
+    pub entries: Vec<CollectionEntry>,
+    pub playlists: Vec<Playlist>,
+    /// The indices of the entries by their primary key (built when parsing).
+    entry_indices: HashMap<String, usize>,
+}
+
+/// A node in the playlist tree that is currently being parsed.
+enum ParsedNode {
+    Folder(String),
+    Playlist(Playlist),
+    Other,
+}
+
+/// Returns the attributes of an element.
+fn parse_attributes(element: &BytesStart<'_>) -> Result<HashMap<String, String>, Error> {
+    let mut attributes = HashMap::new();
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(quick_xml::Error::from)?;
+        let key = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+        attributes.insert(key, attribute.unescape_value()?.into_owned());
+    }
+    Ok(attributes)
+}
+
+fn parse_cue(attributes: &HashMap<String, String>) -> Option<CueV2> {
+    Some(CueV2 {
+        name: attributes.get("NAME").cloned().unwrap_or_default(),
+        cue_type: CueType::from(attributes.get("TYPE")?.parse::<u8>().ok()?),
+        start: attributes.get("START")?.parse().ok()?,
+        len: attributes
+            .get("LEN")
+            .and_then(|len| len.parse().ok())
+            .unwrap_or_default(),
+        hotcue: attributes
+            .get("HOTCUE")
+            .and_then(|hotcue| hotcue.parse().ok())
+            .unwrap_or(-1),
+    })
+}
+
+impl TraktorNml {
+    /// Parse Traktor NML data from the given reader.
+    ///
+    /// Elements and attributes that are unknown or malformed are ignored.
+    pub fn parse(reader: impl io::BufRead) -> Result<Self, Error> {
+        let mut reader = Reader::from_reader(reader);
+        reader.config_mut().trim_text(true);
+
+        let mut nml = Self::default();
+        let mut buffer = vec![];
+        let mut is_in_collection = false;
+        let mut current_entry: Option<CollectionEntry> = None;
+        let mut nodes: Vec<ParsedNode> = vec![];
+        loop {
+            buffer.clear();
+            let event = reader.read_event_into(&mut buffer)?;
+            let (element, is_empty) = match &event {
+                Event::Start(element) => (element, false),
+                Event::Empty(element) => (element, true),
+                Event::End(element) => {
+                    match element.name().as_ref() {
+                        b"COLLECTION" => is_in_collection = false,
+                        b"ENTRY" => {
+                            if let Some(entry) = current_entry.take() {
+                                nml.entries.push(entry);
+                            }
+                        }
+                        b"NODE" => {
+                            if let Some(ParsedNode::Playlist(playlist)) = nodes.pop() {
+                                nml.playlists.push(playlist);
+                            }
+                        }
+                        _ => (),
+                    }
+                    continue;
+                }
+                Event::Eof => break,
+                _ => {
+                    continue;
+                }
+            };
+            let attributes = parse_attributes(element)?;
+            match element.name().as_ref() {
+                b"COLLECTION" => is_in_collection = !is_empty,
+                b"ENTRY" if is_in_collection => {
+                    let entry = CollectionEntry {
+                        key: String::new(),
+                        volume: String::new(),
+                        path: PathBuf::new(),
+                        title: attributes.get("TITLE").cloned(),
+                        artist: attributes.get("ARTIST").cloned(),
+                        bpm: None,
+                        cues: vec![],
+                    };
+                    if is_empty {
+                        nml.entries.push(entry);
+                    } else {
+                        current_entry = Some(entry);
+                    }
+                }
+                b"LOCATION" => {
+                    if let Some(entry) = &mut current_entry {
+                        let attribute = |name| attributes.get(name).map_or("", String::as_str);
+                        let (volume, dir, file) =
+                            (attribute("VOLUME"), attribute("DIR"), attribute("FILE"));
+                        entry.key = format!("{volume}{dir}{file}");
+                        entry.volume = volume.to_string();
+                        entry.path = parse_dir(dir, file);
+                    }
+                }
+                b"TEMPO" => {
+                    if let Some(entry) = &mut current_entry {
+                        entry.bpm = attributes.get("BPM").and_then(|bpm| bpm.parse().ok());
+                    }
+                }
+                b"CUE_V2" => {
+                    if let (Some(entry), Some(cue)) = (&mut current_entry, parse_cue(&attributes)) {
+                        entry.cues.push(cue);
+                    }
+                }
+                b"NODE" => {
+                    let name = attributes.get("NAME").cloned().unwrap_or_default();
+                    let node = match attributes.get("TYPE").map(String::as_str) {
+                        Some(NODE_TYPE_FOLDER) => ParsedNode::Folder(name),
+                        Some(NODE_TYPE_PLAYLIST) => {
+                            // The path doesn't include the `$ROOT` folder.
+                            let mut path: Vec<_> = nodes
+                                .iter()
+                                .filter_map(|node| match node {
+                                    ParsedNode::Folder(name) => Some(name.clone()),
+                                    _ => None,
+                                })
+                                .skip(1)
+                                .collect();
+                            path.push(name);
+                            ParsedNode::Playlist(Playlist { path, keys: vec![] })
+                        }
+                        _ => ParsedNode::Other,
+                    };
+                    if !is_empty {
+                        nodes.push(node);
+                    } else if let ParsedNode::Playlist(playlist) = node {
+                        nml.playlists.push(playlist);
+                    }
+                }
+                b"PRIMARYKEY" => {
+                    if let (Some(ParsedNode::Playlist(playlist)), Some(key)) =
+                        (nodes.last_mut(), attributes.get("KEY"))
+                    {
+                        playlist.keys.push(key.clone());
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        for (index, entry) in nml.entries.iter().enumerate() {
+            nml.entry_indices.entry(entry.key.clone()).or_insert(index);
+        }
+        Ok(nml)
+    }
+
+    /// Read the Traktor NML file at the given path.
+    pub fn read_from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let file = fs::File::open(path)?;
+        Self::parse(io::BufReader::new(file))
+    }
+
+    /// Returns the entry with the given primary key.
+    ///
+    /// The entry is looked up in an index that is built when parsing. Entries that have been added
+    /// to [`TraktorNml::entries`] afterwards are searched one by one.
+    #[must_use]
+    pub fn entry(&self, key: &str) -> Option<&CollectionEntry> {
+        self.entry_indices
+            .get(key)
+            .and_then(|index| self.entries.get(*index))
+            .filter(|entry| entry.key == key)
+            .or_else(|| self.entries.iter().find(|entry| entry.key == key))
+    }
+
+    /// Write the playlists as subcrates of the given library.
+    ///
+    /// Folders become parent crates. Existing crates are not replaced, instead a number is
+    /// appended to the name of the new crate (e.g. `Warmup (2)`). Tracks on other volumes than the
+    /// library's are skipped, unless the library is located on the system volume of macOS (whose
+    /// name is unknown). Returns the names of the written crates.
+    pub fn write_subcrates(&self, library: &Library) -> Result<Vec<String>, Error> {
+        let volume = volume_name(library.volume_root());
+        let playlists = self.playlists.iter().map(|playlist| {
+            let track_paths = playlist
+                .keys
+                .iter()
+                .filter_map(|key| self.entry(key))
+                .filter(|entry| volume.is_empty() || entry.volume == volume)
+                .map(|entry| entry.path.clone())
+                .collect();
+            (playlist.path.as_slice(), track_paths)
+        });
+        common::write_subcrates(library, playlists)
+    }
+}
+
+#[test]
+fn test_location() {
+    assert_eq!(volume_name(Path::new("/Volumes/USB Drive")), "USB Drive");
+    assert_eq!(volume_name(Path::new("/")), "");
+
+    assert_eq!(dir(Path::new("Music/House/track.mp3")), "/:Music/:House/:");
+    assert_eq!(dir(Path::new("track.mp3")), "/:");
+    assert_eq!(
+        parse_dir("/:Music/:House/:", "track.mp3"),
+        Path::new("Music/House/track.mp3")
+    );
+    assert_eq!(parse_dir("/:", "track.mp3"), Path::new("track.mp3"));
+}
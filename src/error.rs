@@ -53,6 +53,65 @@ pub enum Error {
     #[error("No tag data available")]
     NoTagDataAvailable,
 
+    /// Happens when a field in the Serato database exceeds the maximum size.
+    #[error("Database field too large")]
+    DatabaseFieldTooLarge { length: usize },
+
+    /// Happens when a crate name is empty or contains invalid characters.
+    #[error("Invalid crate name: {name}")]
+    InvalidCrateName { name: String },
+
+    /// Happens when trying to create a crate that already exists.
+    #[error("Crate already exists: {name}")]
+    CrateAlreadyExists { name: String },
+
+    /// Happens when a track is not found in the library database.
+    #[error("Track not found: {}", path.display())]
+    TrackNotFound { path: std::path::PathBuf },
+
+    /// Happens when relocating a track to a path that another track in the library already uses.
+    #[error("Track already exists: {}", path.display())]
+    TrackAlreadyExists { path: std::path::PathBuf },
+
+    /// Happens when a path is not located on the volume of the library.
+    #[error("Path is not located on the library's volume: {}", path.display())]
+    PathNotOnVolume { path: std::path::PathBuf },
+
+    /// Represents errors when reading or writing ID3 tags from/to files.
+    #[cfg(feature = "id3")]
+    #[error(transparent)]
+    ID3Error(#[from] id3::Error),
+
+    /// Happens when a FLAC metadata block exceeds the maximum size.
+    #[cfg(feature = "flac")]
+    #[error("FLAC metadata block too large")]
+    FLACMetadataBlockTooLarge { length: usize },
+
+    /// Happens when a required MP4 atom is missing.
+    #[cfg(feature = "mp4")]
+    #[error("MP4 atom not found: {name}")]
+    MP4AtomNotFound { name: &'static str },
+
+    /// Happens when trying to write to a fragmented MP4 file.
+    #[cfg(feature = "mp4")]
+    #[error("Fragmented MP4 files are not supported")]
+    MP4FragmentedFile,
+
+    /// Happens when an MP4 atom exceeds the maximum size.
+    #[cfg(feature = "mp4")]
+    #[error("MP4 atom too large")]
+    MP4AtomTooLarge { length: usize },
+
+    /// Represents errors when reading or writing XML files.
+    #[cfg(any(feature = "rekordbox", feature = "traktor"))]
+    #[error(transparent)]
+    XMLError(#[from] quick_xml::Error),
+
+    /// Represents errors when reading or writing SQLite databases.
+    #[cfg(any(feature = "mixxx", feature = "enginedj"))]
+    #[error(transparent)]
+    SQLiteError(#[from] rusqlite::Error),
+
     /// Represents all other cases of `std::io::Error`.
     #[error(transparent)]
     IOError(#[from] std::io::Error),
@@ -47,9 +47,11 @@
 //! }
 //! ```
 //!
-//! **Note:** This library does *not* provide means to read the metadata from music files directly, so you need to
-//! use other libraries (e.g. [`id3`](https://lib.rs/crates/id3) to do that. You can check the
-//! `examples/` directory for some toy examples.
+//! **Note:** By default, this library does *not* provide means to read the metadata from music
+//! files directly, so you need to use other libraries (e.g. [`id3`](https://lib.rs/crates/id3) to
+//! do that. You can check the `examples/` directory for some toy examples. Alternatively, you can
+//! enable one of the optional cargo features listed in the [`tag::file`] module to read and
+//! write the tags of some file types directly.
 //!
 //! ## Serialization
 //!
@@ -67,6 +69,26 @@
 //! }
 //! ```
 //!
+//! ## Editing
+//!
+//! Tags can also be created from scratch (e.g. for files that have never been opened in Serato
+//! DJ) and edited using the [`TagContainer` struct](`tag::TagContainer`), which keeps the
+//! redundant data in the different tags consistent:
+//!
+//! ```
+//! use triseratops::tag::{Autotags, TagContainer, TagFormat, color::Color, generic::{Cue, Position}};
+//!
+//! let mut tags = TagContainer::with_empty_tags(TagFormat::ID3);
+//! tags.set_autotags(Autotags::new(120.0, 0.0, 0.0));
+//! tags.set_cue(Cue {
+//!     index: 0,
+//!     position: Position { millis: 1500 },
+//!     color: Color { red: 0xCC, green: 0x00, blue: 0x00 },
+//!     label: "Drop".to_string(),
+//! }).expect("Failed to set cue!");
+//! assert_eq!(tags.cues().len(), 1);
+//! ```
+//!
 //! ## Supported File Types
 //!
 //! Support for the following tags has already been implemented:
@@ -78,7 +100,7 @@
 //! | `BeatGrid`   | **Yes** | **Yes** | **Yes** | No         | No             | Beatgrid Markers
 //! | `Markers_`   | **Yes** | *n/a*   | **Yes** | No         | No             | Hotcues, Saved Loops, etc.
 //! | `Markers2`   | **Yes** | **Yes** | **Yes** | **Yes**    | No             | Hotcues, Saved Loops, etc.
-//! | `Offsets_`   | No      | No      | No      | No         | No             | ?
+//! | `Offsets_`   | No      | No      | No      | No         | No             | Decoder offsets (?)
 //! | `Overview`   | **Yes** | **Yes** | **Yes** | No         | No             | Overview Waveform data
 //! | `RelVol`     | *n/a*   | Partial | Partial | *n/a*      | No             | Relative Volume Adjument data (?)
 //! | `VideoAssoc` | *n/a*   | Partial | Partial | *n/a*      | No             | Video Association data (?)
@@ -87,10 +109,11 @@
 //!
 //! - Most Ogg tags are currently not supported. Their format is completely different from
 //!   the other tag types and need to be reverse-engineered first.
-//! - The `Serato Offsets_` tag haven't been reverse engineed yet, and no support has been
+//! - The `Serato Offsets_` tag hasn't been reverse engineered yet, and no support has been
 //!   implemented.
-//! - The `Serato RelVolAd` and the `Serato VidAssoc` tags haven't been reverse engineed yet, but
-//!   preliminary support has been added. For now, they just return a tag version and a byte vector.
+//! - The `Serato RelVolAd` and the `Serato VidAssoc` tags haven't been reverse engineered yet,
+//!   but preliminary support has been added. For now, they just return a tag version and a byte
+//!   vector.
 //! - AAC files (among others) do not store metadata in tags, and use XML files in the
 //!   `_Serato_/Metadata` directory instead. No support has been added yet.
 //! - The cue colors stored in the metadata are *not* the same as displayed in Serato DJ Pro.
@@ -109,6 +132,18 @@
 //! Parsing the Serato library (e.g. the `database V2` file in the `_Serato_` directory) is also
 //! possible, but since this feature is still under development, the API is *not* stable yet and
 //! might change in the future.
+//!
+//! The low-level fields of the `database V2` and `.crate` files can be written back using
+//! [`library::database::write`], which reproduces the original data if nothing was changed.
+//!
+//! Libraries on multiple drives (each with its own `_Serato_` directory) can be combined using
+//! [`library::MergedLibrary`]. Tracks whose files have been moved can be found and relocated using
+//! [`library::Library::relocate_missing_tracks`].
+//!
+//! # Converting
+//!
+//! Libraries and tags can be converted to the formats of other DJ software. Each format is gated
+//! behind an optional cargo feature, see the [`convert`] module for details.
 
 #![warn(unsafe_code)]
 #![warn(rust_2018_idioms)]
@@ -125,6 +160,7 @@
 #![cfg_attr(not(test), warn(clippy::panic_in_result_fn))]
 #![cfg_attr(not(debug_assertions), warn(clippy::used_underscore_binding))]
 
+pub mod convert;
 pub mod error;
 pub mod library;
 pub mod tag;
@@ -6,8 +6,17 @@
 //
 // SPDX-License-Identifier: MPL-2.0
 
+//! Parser and writer for the fields in the `database V2` and `.crate` files.
+//!
+//! Both file types consist of a flat list of fields. Each field starts with a 4-byte descriptor
+//! (a type character followed by a 3-character name), followed by the big-endian 32-bit length
+//! and the content. Container fields (e.g. tracks) contain a nested list of fields. Writing parsed
+//! fields reproduces the original data, as long as all text fields are valid UTF-16 and boolean
+//! fields only contain `0x00` or `0x01`.
+
 use crate::error::Error;
 use crate::util::Res;
+use std::io;
 use std::path::PathBuf;
 
 pub type Path = String;
@@ -20,7 +29,7 @@ const FIELD_TEXT: u8 = b't';
 const FIELD_U16: u8 = b's';
 const FIELD_U32: u8 = b'u';
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Field {
     Unknown {
         field_type: u8,
@@ -80,6 +89,20 @@ pub enum Field {
     Track(Vec<Field>),
     Version(String),
     Year(String),
+    /// The file is corrupt (`bcrt`).
+    Corrupt(bool),
+    /// The track has been played (`bply`).
+    Played(bool),
+    /// The track has been imported from iTunes (`bitu`).
+    ITunesImport(bool),
+    /// The track color as `0xRRGGBB` (`ulbl`, `0xFFFFFF` if no color is set).
+    Color(u32),
+    /// The file size in bytes (`ufsb`).
+    FileSizeBytes(u32),
+    /// The track number (`utkn`).
+    TrackNumber(u32),
+    /// The disc number (`udsc`).
+    DiscNumber(u32),
     // Crates
     Sorting(Vec<Field>),
     ReverseOrder(bool),
@@ -135,13 +158,13 @@ fn parse_field<'a>(input: &'a [u8], name: &[u8], field_type: u8) -> Res<&'a [u8]
                 b"bgl" => Field::BeatgridLocked(value),
                 b"mis" => Field::Missing(value),
                 b"rev" => Field::ReverseOrder(value),
-                //b"crt" => ???
+                b"crt" => Field::Corrupt(value),
                 //b"hrt" => ???
                 //b"iro" => ???
-                //b"itu" => ???
+                b"itu" => Field::ITunesImport(value),
                 //b"krk" => ???
                 //b"ovc" => ???
-                //b"ply" => ???
+                b"ply" => Field::Played(value),
                 //b"uns" => ???
                 //b"wlb" => ???
                 //b"wll" => ???
@@ -168,10 +191,10 @@ fn parse_field<'a>(input: &'a [u8], name: &[u8], field_type: u8) -> Res<&'a [u8]
             let field = match name {
                 b"add" => Field::DateAdded(value),
                 b"tme" => Field::FileTime(value),
-                //b"lbl" => ???
-                //b"fsb" => ???
-                //b"tkn" => ???
-                //b"dsc" => ???
+                b"lbl" => Field::Color(value),
+                b"fsb" => Field::FileSizeBytes(value),
+                b"tkn" => Field::TrackNumber(value),
+                b"dsc" => Field::DiscNumber(value),
                 _ => Field::UnknownU32Field {
                     name: name.to_owned(),
                     value,
@@ -281,3 +304,180 @@ pub fn parse(input: &[u8]) -> Result<Vec<Field>, Error> {
     let (_, fields) = nom::combinator::all_consuming(take_fields)(input)?;
     Ok(fields)
 }
+
+/// Returns the field type, the field name and the content of a field.
+///
+/// The `vrsn` field is special, because its descriptor doesn't start with a type character. In
+/// that case, the complete descriptor is returned as name and the field type is `None`.
+fn field_parts(field: &Field) -> (Option<u8>, &[u8], FieldContent<'_>) {
+    match field {
+        Field::Unknown {
+            field_type,
+            name,
+            content,
+        } => (Some(*field_type), name, FieldContent::Bytes(content)),
+        Field::UnknownBoolean { name, value } => {
+            (Some(FIELD_BOOL), name, FieldContent::Bool(*value))
+        }
+        Field::UnknownContainerField { name, fields } => {
+            (Some(FIELD_CONTAINER), name, FieldContent::Fields(fields))
+        }
+        Field::UnknownContainerRField { name, fields } => {
+            (Some(FIELD_CONTAINER_R), name, FieldContent::Fields(fields))
+        }
+        Field::UnknownPathField { name, path } => {
+            (Some(FIELD_PATH), name, FieldContent::Path(path))
+        }
+        Field::UnknownU16Field { name, value } => {
+            (Some(FIELD_U16), name, FieldContent::U16(*value))
+        }
+        Field::UnknownU32Field { name, value } => {
+            (Some(FIELD_U32), name, FieldContent::U32(*value))
+        }
+        Field::UnknownTextField { name, text } => {
+            (Some(FIELD_TEXT), name, FieldContent::Text(text))
+        }
+        Field::Album(text) => (Some(FIELD_TEXT), b"alb", FieldContent::Text(text)),
+        Field::Artist(text) => (Some(FIELD_TEXT), b"art", FieldContent::Text(text)),
+        Field::BPM(text) => (Some(FIELD_TEXT), b"bpm", FieldContent::Text(text)),
+        Field::BeatgridLocked(value) => (Some(FIELD_BOOL), b"bgl", FieldContent::Bool(*value)),
+        Field::Bitrate(text) => (Some(FIELD_TEXT), b"bit", FieldContent::Text(text)),
+        Field::Comment(text) => (Some(FIELD_TEXT), b"com", FieldContent::Text(text)),
+        Field::Composer(text) => (Some(FIELD_TEXT), b"cmp", FieldContent::Text(text)),
+        Field::DateAdded(value) => (Some(FIELD_U32), b"add", FieldContent::U32(*value)),
+        Field::DateAddedStr(text) => (Some(FIELD_TEXT), b"add", FieldContent::Text(text)),
+        Field::FilePath(path) => (Some(FIELD_PATH), b"fil", FieldContent::Path(path)),
+        Field::FileSize(text) => (Some(FIELD_TEXT), b"siz", FieldContent::Text(text)),
+        Field::FileTime(value) => (Some(FIELD_U32), b"tme", FieldContent::U32(*value)),
+        Field::FileType(text) => (Some(FIELD_TEXT), b"typ", FieldContent::Text(text)),
+        Field::Genre(text) => (Some(FIELD_TEXT), b"gen", FieldContent::Text(text)),
+        Field::Grouping(text) => (Some(FIELD_TEXT), b"grp", FieldContent::Text(text)),
+        Field::Key(text) => (Some(FIELD_TEXT), b"key", FieldContent::Text(text)),
+        Field::Label(text) => (Some(FIELD_TEXT), b"lbl", FieldContent::Text(text)),
+        Field::Length(text) => (Some(FIELD_TEXT), b"len", FieldContent::Text(text)),
+        Field::Missing(value) => (Some(FIELD_BOOL), b"mis", FieldContent::Bool(*value)),
+        Field::SampleRate(text) => (Some(FIELD_TEXT), b"smp", FieldContent::Text(text)),
+        Field::SongTitle(text) => (Some(FIELD_TEXT), b"sng", FieldContent::Text(text)),
+        Field::Track(fields) => (Some(FIELD_CONTAINER), b"trk", FieldContent::Fields(fields)),
+        Field::Version(text) => (None, b"vrsn", FieldContent::Text(text)),
+        Field::Year(text) => (Some(FIELD_TEXT), b"tyr", FieldContent::Text(text)),
+        Field::Corrupt(value) => (Some(FIELD_BOOL), b"crt", FieldContent::Bool(*value)),
+        Field::Played(value) => (Some(FIELD_BOOL), b"ply", FieldContent::Bool(*value)),
+        Field::ITunesImport(value) => (Some(FIELD_BOOL), b"itu", FieldContent::Bool(*value)),
+        Field::Color(value) => (Some(FIELD_U32), b"lbl", FieldContent::U32(*value)),
+        Field::FileSizeBytes(value) => (Some(FIELD_U32), b"fsb", FieldContent::U32(*value)),
+        Field::TrackNumber(value) => (Some(FIELD_U32), b"tkn", FieldContent::U32(*value)),
+        Field::DiscNumber(value) => (Some(FIELD_U32), b"dsc", FieldContent::U32(*value)),
+        Field::Sorting(fields) => (Some(FIELD_CONTAINER), b"srt", FieldContent::Fields(fields)),
+        Field::ReverseOrder(value) => (Some(FIELD_BOOL), b"rev", FieldContent::Bool(*value)),
+        Field::ColumnTitle(fields) => (Some(FIELD_CONTAINER), b"vct", FieldContent::Fields(fields)),
+        Field::ColumnName(text) => (Some(FIELD_TEXT), b"vcn", FieldContent::Text(text)),
+        Field::ColumnWidth(text) => (Some(FIELD_TEXT), b"vcw", FieldContent::Text(text)),
+        Field::TrackPath(path) => (Some(FIELD_PATH), b"trk", FieldContent::Path(path)),
+    }
+}
+
+/// The content of a field, borrowed from a [`Field`].
+enum FieldContent<'a> {
+    Bool(bool),
+    Bytes(&'a [u8]),
+    Fields(&'a [Field]),
+    Path(&'a std::path::Path),
+    Text(&'a str),
+    U16(u16),
+    U32(u32),
+}
+
+fn write_u16_text(writer: &mut impl io::Write, text: &str) -> Result<usize, Error> {
+    let mut bytes_written = 0;
+    for code_unit in text.encode_utf16() {
+        bytes_written += writer.write(&code_unit.to_be_bytes())?;
+    }
+    Ok(bytes_written)
+}
+
+#[test]
+fn test_write_u16_text() {
+    let mut writer = io::Cursor::new(vec![]);
+    let bytes_written = write_u16_text(&mut writer, "A\u{1F600}").unwrap();
+    assert_eq!(bytes_written, 6);
+    assert_eq!(
+        writer.get_ref().as_slice(),
+        &[0x00, 0x41, 0xD8, 0x3D, 0xDE, 0x00]
+    );
+    let (_, text) = parse_u16_text(writer.get_ref()).unwrap();
+    assert_eq!(text, "A\u{1F600}");
+}
+
+fn write_field_content(
+    writer: &mut impl io::Write,
+    content: &FieldContent<'_>,
+) -> Result<usize, Error> {
+    match content {
+        FieldContent::Bool(value) => Ok(writer.write(&[u8::from(*value)])?),
+        FieldContent::Bytes(data) => Ok(writer.write(data)?),
+        FieldContent::Fields(fields) => write(writer, fields),
+        FieldContent::Path(path) => write_u16_text(writer, &path.to_string_lossy()),
+        FieldContent::Text(text) => write_u16_text(writer, text),
+        FieldContent::U16(value) => Ok(writer.write(&value.to_be_bytes())?),
+        FieldContent::U32(value) => Ok(writer.write(&value.to_be_bytes())?),
+    }
+}
+
+/// Serialize a single field (including nested fields of containers).
+pub fn write_field(writer: &mut impl io::Write, field: &Field) -> Result<usize, Error> {
+    let (field_type, name, content) = field_parts(field);
+
+    let mut data = vec![];
+    let length = write_field_content(&mut data, &content)?;
+    let length = u32::try_from(length).map_err(|_| Error::DatabaseFieldTooLarge { length })?;
+
+    let mut bytes_written = 0;
+    if let Some(field_type) = field_type {
+        bytes_written += writer.write(&[field_type])?;
+    }
+    bytes_written += writer.write(name)?;
+    bytes_written += writer.write(&length.to_be_bytes())?;
+    bytes_written += writer.write(&data)?;
+    Ok(bytes_written)
+}
+
+#[test]
+fn test_write_field() {
+    let mut writer = io::Cursor::new(vec![]);
+    write_field(&mut writer, &Field::Missing(true)).unwrap();
+    assert_eq!(writer.get_ref().as_slice(), b"bmis\x00\x00\x00\x01\x01");
+
+    let mut writer = io::Cursor::new(vec![]);
+    write_field(&mut writer, &Field::Version("1.0".to_string())).unwrap();
+    assert_eq!(
+        writer.get_ref().as_slice(),
+        b"vrsn\x00\x00\x00\x06\x001\x00.\x000"
+    );
+    let (_, field) = take_field(writer.get_ref()).unwrap();
+    assert_eq!(field, Field::Version("1.0".to_string()));
+
+    let field = Field::Track(vec![
+        Field::FilePath(PathBuf::from("Music/track.mp3")),
+        Field::DateAdded(1_600_000_000),
+        Field::Unknown {
+            field_type: b'x',
+            name: b"abc".to_vec(),
+            content: vec![0xFF],
+        },
+    ]);
+    let mut writer = io::Cursor::new(vec![]);
+    write_field(&mut writer, &field).unwrap();
+    assert_eq!(&writer.get_ref()[..8], b"otrk\x00\x00\x00\x3B");
+    let (_, parsed) = take_field(writer.get_ref()).unwrap();
+    assert_eq!(parsed, field);
+}
+
+/// Serialize a list of fields, e.g. for writing a `database V2` or `.crate` file.
+pub fn write(writer: &mut impl io::Write, fields: &[Field]) -> Result<usize, Error> {
+    let mut bytes_written = 0;
+    for field in fields {
+        bytes_written += write_field(writer, field)?;
+    }
+    Ok(bytes_written)
+}
@@ -0,0 +1,137 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Reading the play history (`.session` files in `_Serato_/History/Sessions`).
+//!
+//! Session files use the same encoding as the `database V2` file. Each played track is stored as
+//! an `oent` container, which contains an `adat` field. The content of the `adat` field is a list
+//! of fields with numeric IDs (big-endian 32-bit ID, followed by the big-endian 32-bit length and
+//! the content).
+//!
+//! **Note:** There are no session files written by Serato DJ in the test data yet, so the meaning
+//! of the field IDs is not known and the fields are kept as [`HistoryField`]s for now. The
+//! `history.database` file contains the same data for all sessions, but isn't parsed yet.
+
+use super::database::{self, Field};
+use crate::error::Error;
+use crate::util::Res;
+use std::fs;
+use std::path::Path;
+
+/// A single field in the `adat` data of a history entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryField {
+    pub id: u32,
+    pub content: Vec<u8>,
+}
+
+fn take_history_field(input: &[u8]) -> Res<&[u8], HistoryField> {
+    let (input, id) = nom::number::complete::be_u32(input)?;
+    let (input, content) = nom::multi::length_data(nom::number::complete::be_u32)(input)?;
+    let field = HistoryField {
+        id,
+        content: content.to_owned(),
+    };
+    Ok((input, field))
+}
+
+fn parse_history_fields(input: &[u8]) -> Result<Vec<HistoryField>, Error> {
+    let (_, fields) = nom::combinator::all_consuming(nom::multi::many0(take_history_field))(input)?;
+    Ok(fields)
+}
+
+/// A track that was played during a session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    fields: Vec<HistoryField>,
+}
+
+impl HistoryEntry {
+    /// Returns the low-level fields of the entry.
+    #[must_use]
+    pub fn fields(&self) -> &[HistoryField] {
+        &self.fields
+    }
+}
+
+/// A DJ session, i.e. the tracks that were played from starting Serato DJ until closing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistorySession {
+    fields: Vec<Field>,
+    entries: Vec<HistoryEntry>,
+}
+
+impl HistorySession {
+    /// Parse the content of a `.session` file.
+    pub fn parse(input: &[u8]) -> Result<Self, Error> {
+        let fields = database::parse(input)?;
+        let mut entries = vec![];
+        for field in &fields {
+            let Field::UnknownContainerField { name, fields } = field else {
+                continue;
+            };
+            if name != b"ent" {
+                continue;
+            }
+            for field in fields {
+                if let Field::Unknown {
+                    field_type: b'a',
+                    name,
+                    content,
+                } = field
+                    && name == b"dat"
+                {
+                    let fields = parse_history_fields(content)?;
+                    entries.push(HistoryEntry { fields });
+                }
+            }
+        }
+        Ok(Self { fields, entries })
+    }
+
+    /// Read the `.session` file at the given path.
+    pub fn read_from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let data = fs::read(path)?;
+        Self::parse(&data)
+    }
+
+    /// Returns the low-level fields of the session file.
+    #[must_use]
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// Returns the entries of the session (in the order they are stored in the file).
+    #[must_use]
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+}
+
+#[test]
+fn test_parse_history_fields() {
+    let data = [
+        0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x02, // deck
+        0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x04, 0x00, 0x41, 0x00, 0x00, // title
+    ];
+    let fields = parse_history_fields(&data).unwrap();
+    assert_eq!(
+        fields,
+        [
+            HistoryField {
+                id: 31,
+                content: vec![0x00, 0x00, 0x00, 0x02],
+            },
+            HistoryField {
+                id: 6,
+                content: vec![0x00, 0x41, 0x00, 0x00],
+            },
+        ]
+    );
+    assert!(parse_history_fields(&data[..10]).is_err());
+}
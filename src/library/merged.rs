@@ -0,0 +1,213 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Combined view of multiple Serato libraries.
+//!
+//! Serato DJ keeps one `_Serato_` directory in the home directory and one on each external drive.
+//! Each of them contains a separate database and separate crates, which are combined by
+//! [`MergedLibrary`].
+//!
+//! ```no_run
+//! use triseratops::library::MergedLibrary;
+//!
+//! let library = MergedLibrary::read_from_paths(["/Users/user/Music", "/Volumes/USB Drive"])
+//!     .expect("Failed to read libraries!");
+//! for duplicates in library.duplicates() {
+//!     for track in duplicates {
+//!         println!("{}", track.path.display());
+//!     }
+//! }
+//! ```
+
+use super::parser::{Library, Track, parse_file_size};
+use crate::error::Error;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The number of bytes in a megabyte, as used in the human-readable file size (e.g. `14.6MB`).
+const BYTES_PER_MEGABYTE: f64 = 1024.0 * 1024.0;
+
+/// A track in a [`MergedLibrary`], together with the library it belongs to.
+#[derive(Debug, Clone, Copy)]
+pub struct MergedTrack<'a> {
+    /// The library that contains the track.
+    pub library: &'a Library,
+    /// The path of the track as stored in the database (relative to the volume root).
+    pub path: &'a Path,
+    /// The track.
+    pub track: &'a Track,
+}
+
+impl MergedTrack<'_> {
+    /// Returns the root directory of the volume the track is located on.
+    #[must_use]
+    pub fn volume_root(&self) -> &Path {
+        self.library.volume_root()
+    }
+
+    /// Returns the absolute file system path of the track.
+    #[must_use]
+    pub fn absolute_path(&self) -> PathBuf {
+        self.library.absolute_path(self.path)
+    }
+
+    /// Returns the key that is used to detect duplicates.
+    ///
+    /// Tracks are considered duplicates if they have the same file name (compared
+    /// case-insensitively) and the same file size. Not all databases contain the file size in
+    /// bytes, so the size is compared at the precision of the human-readable file size (in tenths
+    /// of a megabyte).
+    fn duplicate_key(&self) -> Option<(String, u64)> {
+        let file_name = self.path.file_name()?.to_string_lossy().to_lowercase();
+        let file_size = self
+            .track
+            .file_size_bytes
+            .map(|size| (f64::from(size) * 10.0 / BYTES_PER_MEGABYTE).round())
+            .or_else(|| {
+                let (number, unit_size) = parse_file_size(self.track.file_size.as_deref()?)?;
+                Some((number * unit_size * 10.0 / BYTES_PER_MEGABYTE).round())
+            })?;
+        Some((file_name, file_size as u64))
+    }
+}
+
+/// Combined view of multiple Serato libraries (e.g. the home directory and external drives).
+#[derive(Debug, Clone, Default)]
+pub struct MergedLibrary {
+    libraries: Vec<Library>,
+}
+
+impl MergedLibrary {
+    /// Create an empty merged library.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { libraries: vec![] }
+    }
+
+    /// Read the libraries in the given paths.
+    pub fn read_from_paths(
+        paths: impl IntoIterator<Item = impl AsRef<Path>>,
+    ) -> Result<Self, Error> {
+        let libraries = paths
+            .into_iter()
+            .map(Library::read_from_path)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { libraries })
+    }
+
+    /// Add a library.
+    pub fn add_library(&mut self, library: Library) {
+        self.libraries.push(library);
+    }
+
+    /// Get the merged libraries.
+    #[must_use]
+    pub fn libraries(&self) -> &[Library] {
+        &self.libraries
+    }
+
+    /// Get all tracks of all libraries.
+    pub fn tracks(&self) -> impl Iterator<Item = MergedTrack<'_>> {
+        self.libraries.iter().flat_map(|library| {
+            library
+                .tracks_with_paths()
+                .map(move |(path, track)| MergedTrack {
+                    library,
+                    path,
+                    track,
+                })
+        })
+    }
+
+    /// Get the track for the given absolute file system path.
+    #[must_use]
+    pub fn track(&self, file_path: &Path) -> Option<MergedTrack<'_>> {
+        self.libraries.iter().find_map(|library| {
+            let (path, track) = library.track_with_path(file_path)?;
+            Some(MergedTrack {
+                library,
+                path,
+                track,
+            })
+        })
+    }
+
+    /// Get the names of the subcrates of all libraries (sorted and without duplicates).
+    #[must_use]
+    pub fn subcrates(&self) -> Vec<String> {
+        let mut names: Vec<_> = self.libraries.iter().flat_map(Library::subcrates).collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Get the tracks of the subcrate with the given name from all libraries that contain it.
+    pub fn subcrate(&self, name: &str) -> Result<Vec<MergedTrack<'_>>, Error> {
+        let mut tracks = vec![];
+        for library in &self.libraries {
+            if !library
+                .subcrates()
+                .any(|subcrate_name| subcrate_name == name)
+            {
+                continue;
+            }
+            let subcrate = library.read_subcrate(name)?;
+            tracks.extend(subcrate.track_paths().filter_map(|path| {
+                let (path, track) = library.track_with_path(path)?;
+                Some(MergedTrack {
+                    library,
+                    path,
+                    track,
+                })
+            }));
+        }
+        Ok(tracks)
+    }
+
+    /// Get groups of tracks that are likely duplicates of each other.
+    ///
+    /// Tracks are considered duplicates if they have the same file name (compared
+    /// case-insensitively) and the same file size. Tracks without a known file size are ignored.
+    #[must_use]
+    pub fn duplicates(&self) -> Vec<Vec<MergedTrack<'_>>> {
+        let mut groups: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for track in self.tracks() {
+            if let Some(key) = track.duplicate_key() {
+                groups.entry(key).or_default().push(track);
+            }
+        }
+        groups
+            .into_values()
+            .filter(|tracks| tracks.len() > 1)
+            .collect()
+    }
+}
+
+#[test]
+fn test_duplicate_key() {
+    let library = Library::read_from_path("tests/data/library/synthetic").unwrap();
+    let path = Path::new("Music/Track.MP3");
+    let mut with_bytes = Track::new();
+    with_bytes.file_size_bytes = Some(15_319_300);
+    let mut with_text = Track::new();
+    with_text.file_size = Some("14.6MB".to_string());
+    let key = |track| {
+        MergedTrack {
+            library: &library,
+            path,
+            track,
+        }
+        .duplicate_key()
+    };
+    assert_eq!(key(&with_bytes), Some(("track.mp3".to_string(), 146)));
+    assert_eq!(key(&with_bytes), key(&with_text));
+    let mut with_kilobytes = Track::new();
+    with_kilobytes.file_size = Some("14960.3KB".to_string());
+    assert_eq!(key(&with_bytes), key(&with_kilobytes));
+    assert_eq!(key(&Track::new()), None);
+}
@@ -8,6 +8,16 @@
 
 //! Parsers for the Serato library database and crates
 pub mod database;
+mod history;
+mod merged;
 mod parser;
+mod relocate;
+mod smartcrate;
+mod subcrate;
 
+pub use history::{HistoryEntry, HistoryField, HistorySession};
+pub use merged::{MergedLibrary, MergedTrack};
 pub use parser::{Library, Track};
+pub use relocate::RelocationCandidate;
+pub use smartcrate::SmartCrate;
+pub use subcrate::{Column, SUBCRATE_SEPARATOR, Subcrate, SubcrateNode, subcrate_full_name};
@@ -22,13 +22,19 @@
 //! ```
 
 use super::database;
+use super::history::HistorySession;
+use super::relocate::{self, RelocationCandidate};
+use super::smartcrate::SmartCrate;
+use super::subcrate::{SUBCRATE_SEPARATOR, Subcrate, SubcrateNode, subcrate_full_name};
 use crate::error::Error;
+use crate::util;
 use std::collections::HashMap;
 use std::fs;
 use std::fs::File;
 use std::io;
 use std::io::{BufReader, Read};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
 /// File name of the main database file
 const DATABASE_FILENAME: &str = "database V2";
@@ -38,7 +44,24 @@ const CRATE_EXTENSION: &str = "crate";
 const SERATO_DIR: &str = "_Serato_";
 /// Name of the directory containing subcrates inside the Serato directory
 const SUBCRATE_DIR: &str = "Subcrates";
+/// File extension of the smart crate files
+const SMART_CRATE_EXTENSION: &str = "scrate";
+/// Name of the directory containing smart crates inside the Serato directory
+const SMART_CRATE_DIR: &str = "SmartCrates";
+/// File extension of the history session files
+const SESSION_EXTENSION: &str = "session";
+/// Path of the directory containing history sessions inside the Serato directory
+const SESSION_DIR: &str = "History/Sessions";
+/// Name of the file that stores the order of the crates inside the Serato directory
+const CRATE_ORDER_FILENAME: &str = "neworder.pref";
+/// Prefix of crate entries in the crate order file
+const CRATE_ORDER_PREFIX: &str = "[crate]";
 
+/// A track in the Serato library.
+///
+/// The typed fields are parsed from the text that Serato DJ stores in the database (e.g. the BPM
+/// is stored as `126.00`). Fields that are not known or can't be parsed are kept in
+/// [`unknown_fields`](Self::unknown_fields), so that no information is lost.
 #[derive(Clone, Debug)]
 pub struct Track {
     pub file_type: Option<String>,
@@ -51,8 +74,33 @@ pub struct Track {
     pub grouping: Option<String>,
     pub label: Option<String>,
     pub key: Option<String>,
+    pub year: Option<String>,
+    /// The tempo in beats per minute.
+    pub bpm: Option<f64>,
+    /// The bitrate in kbps.
+    pub bitrate: Option<f64>,
+    /// The sample rate in Hz.
+    pub sample_rate: Option<u32>,
+    /// The duration of the track.
+    pub length: Option<Duration>,
+    /// The human-readable file size (e.g. `14.6MB`).
+    pub file_size: Option<String>,
+    /// The time when the track was added to the library.
+    pub date_added: Option<SystemTime>,
+    /// The modification time of the file when it was last analyzed.
+    pub file_time: Option<SystemTime>,
+    /// The file size in bytes.
+    pub file_size_bytes: Option<u32>,
+    pub track_number: Option<u32>,
+    pub disc_number: Option<u32>,
+    /// The track color as `0xRRGGBB`.
+    pub color: Option<u32>,
     pub missing: bool,
     pub beatgrid_locked: bool,
+    pub played: bool,
+    pub corrupt: bool,
+    /// Database fields that are not stored in any of the other struct members.
+    pub unknown_fields: Vec<database::Field>,
 }
 
 impl Track {
@@ -70,8 +118,23 @@ impl Track {
             grouping: None,
             label: None,
             key: None,
+            year: None,
+            bpm: None,
+            bitrate: None,
+            sample_rate: None,
+            length: None,
+            file_size: None,
+            date_added: None,
+            file_time: None,
+            file_size_bytes: None,
+            track_number: None,
+            disc_number: None,
+            color: None,
             missing: false,
             beatgrid_locked: false,
+            played: false,
+            corrupt: false,
+            unknown_fields: vec![],
         }
     }
 
@@ -79,6 +142,7 @@ impl Track {
     pub fn from_fields(fields: Vec<database::Field>) -> Result<(PathBuf, Self), Error> {
         let mut file_path = PathBuf::new();
         let mut track = Self::new();
+        let mut date_added_str = None;
         for field in fields {
             match field {
                 database::Field::FilePath(db_file_path) => {
@@ -87,6 +151,12 @@ impl Track {
                 database::Field::FileType(file_type) => {
                     track.file_type = Some(file_type);
                 }
+                database::Field::SongTitle(title) => {
+                    track.title = Some(title);
+                }
+                database::Field::Genre(genre) => {
+                    track.genre = Some(genre);
+                }
                 database::Field::Album(album) => {
                     track.album = Some(album);
                 }
@@ -108,20 +178,154 @@ impl Track {
                 database::Field::Key(key) => {
                     track.key = Some(key);
                 }
+                database::Field::Year(year) => {
+                    track.year = Some(year);
+                }
+                database::Field::BPM(ref bpm) if parse_bpm(bpm).is_some() => {
+                    track.bpm = parse_bpm(bpm);
+                }
+                database::Field::Bitrate(ref bitrate) if parse_bitrate(bitrate).is_some() => {
+                    track.bitrate = parse_bitrate(bitrate);
+                }
+                database::Field::SampleRate(ref sample_rate)
+                    if parse_sample_rate(sample_rate).is_some() =>
+                {
+                    track.sample_rate = parse_sample_rate(sample_rate);
+                }
+                database::Field::Length(ref length) if parse_length(length).is_some() => {
+                    track.length = parse_length(length);
+                }
+                database::Field::FileSize(file_size) => {
+                    track.file_size = Some(file_size);
+                }
+                database::Field::DateAdded(timestamp) => {
+                    track.date_added = Some(timestamp_to_time(timestamp));
+                }
+                database::Field::DateAddedStr(ref timestamp)
+                    if timestamp.parse::<u32>().is_ok() =>
+                {
+                    date_added_str = timestamp.parse::<u32>().ok().map(timestamp_to_time);
+                }
+                database::Field::FileTime(timestamp) => {
+                    track.file_time = Some(timestamp_to_time(timestamp));
+                }
+                database::Field::FileSizeBytes(file_size) => {
+                    track.file_size_bytes = Some(file_size);
+                }
+                database::Field::TrackNumber(track_number) => {
+                    track.track_number = Some(track_number);
+                }
+                database::Field::DiscNumber(disc_number) => {
+                    track.disc_number = Some(disc_number);
+                }
+                database::Field::Color(color) => {
+                    track.color = Some(color);
+                }
                 database::Field::Missing(missing) => {
                     track.missing = missing;
                 }
+                database::Field::Played(played) => {
+                    track.played = played;
+                }
+                database::Field::Corrupt(corrupt) => {
+                    track.corrupt = corrupt;
+                }
                 database::Field::BeatgridLocked(beatgrid_lock) => {
                     track.beatgrid_locked = beatgrid_lock;
                 }
-                _ => (),
+                field => track.unknown_fields.push(field),
             }
         }
+        track.date_added = track.date_added.or(date_added_str);
 
         Ok((file_path, track))
     }
 }
 
+fn timestamp_to_time(timestamp: u32) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(timestamp.into())
+}
+
+/// Parses a BPM value (e.g. `126.00`).
+fn parse_bpm(text: &str) -> Option<f64> {
+    text.trim().parse().ok()
+}
+
+/// Parses a bitrate (e.g. `320.0kbps`) and returns the value in kbps.
+fn parse_bitrate(text: &str) -> Option<f64> {
+    text.trim().trim_end_matches("kbps").parse().ok()
+}
+
+/// Parses a sample rate (e.g. `44.1k`) and returns the value in Hz.
+fn parse_sample_rate(text: &str) -> Option<u32> {
+    let khz: f64 = text.trim().trim_end_matches('k').parse().ok()?;
+    let hz = (khz * 1000.0).round();
+    if !(0.0..=f64::from(u32::MAX)).contains(&hz) {
+        return None;
+    }
+    Some(hz as u32)
+}
+
+/// Parses a human-readable file size (e.g. `14.6MB`, where `1MB = 1024 * 1024` bytes).
+///
+/// Returns the number and the size of its unit in bytes.
+pub(crate) fn parse_file_size(text: &str) -> Option<(f64, f64)> {
+    let text = text.trim();
+    let (number, unit_size) = if let Some(number) = text.strip_suffix("GB") {
+        (number, 1024.0 * 1024.0 * 1024.0)
+    } else if let Some(number) = text.strip_suffix("MB") {
+        (number, 1024.0 * 1024.0)
+    } else if let Some(number) = text.strip_suffix("KB") {
+        (number, 1024.0)
+    } else {
+        return None;
+    };
+    let number: f64 = number.trim().parse().ok()?;
+    (number.is_finite() && number >= 0.0).then_some((number, unit_size))
+}
+
+/// Parses a track length (e.g. `06:22.93` or `1:02:03.50`).
+fn parse_length(text: &str) -> Option<Duration> {
+    let mut parts = text.trim().rsplit(':');
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    let mut total = seconds;
+    let mut factor = 60.0;
+    for part in parts {
+        let value: u32 = part.parse().ok()?;
+        total += f64::from(value) * factor;
+        factor *= 60.0;
+    }
+    Duration::try_from_secs_f64(total).ok()
+}
+
+#[test]
+fn test_parse_track_values() {
+    assert_eq!(parse_bpm("126.00"), Some(126.0));
+    assert_eq!(parse_bpm(""), None);
+    assert_eq!(parse_bitrate("320.0kbps"), Some(320.0));
+    assert_eq!(parse_sample_rate("44.1k"), Some(44100));
+    assert_eq!(parse_sample_rate("48.0k"), Some(48000));
+    assert_eq!(parse_sample_rate("-1k"), None);
+    assert_eq!(parse_file_size("14.6MB"), Some((14.6, 1024.0 * 1024.0)));
+    assert_eq!(
+        parse_file_size("1.2GB"),
+        Some((1.2, 1024.0 * 1024.0 * 1024.0))
+    );
+    assert_eq!(parse_file_size("980.0KB"), Some((980.0, 1024.0)));
+    assert_eq!(parse_file_size("14.6"), None);
+    assert_eq!(parse_file_size("-1MB"), None);
+    assert_eq!(
+        parse_length("06:22.93"),
+        Some(Duration::from_millis(382_930))
+    );
+    assert_eq!(
+        parse_length("1:02:03.50"),
+        Some(Duration::from_millis(3_723_500))
+    );
+    assert_eq!(parse_length("abc"), None);
+    assert_eq!(parse_length("-1"), None);
+}
+
 impl Default for Track {
     fn default() -> Self {
         Self::new()
@@ -132,6 +336,8 @@ impl Default for Track {
 #[derive(Debug, Clone)]
 pub struct Library {
     path: PathBuf,
+    volume_root: PathBuf,
+    version: Option<String>,
     tracks: HashMap<PathBuf, Track>,
 }
 
@@ -139,8 +345,14 @@ impl Library {
     /// Read the library in the given path.
     pub fn read_from_path_ref(path: &Path) -> Result<Self, Error> {
         let path = fs::canonicalize(path)?;
+        let volume_root = find_volume_root(&path);
         let tracks = HashMap::new();
-        let mut library = Library { path, tracks };
+        let mut library = Library {
+            path,
+            volume_root,
+            version: None,
+            tracks,
+        };
         library.reload()?;
 
         Ok(library)
@@ -158,6 +370,54 @@ impl Library {
         self.path.join(SERATO_DIR)
     }
 
+    /// Get the root directory of the volume that contains the library.
+    ///
+    /// Serato DJ stores the paths of tracks relative to the root of the volume that contains the
+    /// `_Serato_` directory (e.g. `/Volumes/USB Drive` for a library on a USB drive or `/` for
+    /// the library in the home directory). The volume root is detected automatically when reading
+    /// the library, but can be overridden using [`set_volume_root()`](Self::set_volume_root).
+    #[must_use]
+    pub fn volume_root(&self) -> &Path {
+        &self.volume_root
+    }
+
+    /// Set the root directory of the volume that contains the library.
+    pub fn set_volume_root(&mut self, path: impl Into<PathBuf>) {
+        self.volume_root = path.into();
+    }
+
+    /// Convert a track path from the database to an absolute file system path.
+    ///
+    /// Paths that are already absolute are returned unchanged.
+    #[must_use]
+    pub fn absolute_path(&self, file_path: &Path) -> PathBuf {
+        self.volume_root.join(file_path)
+    }
+
+    /// Convert an absolute file system path to the path used in the database.
+    ///
+    /// Returns `None` if the path is not located on the library's volume. Relative paths are
+    /// returned unchanged.
+    #[must_use]
+    pub fn relative_path(&self, file_path: &Path) -> Option<PathBuf> {
+        if file_path.is_relative() {
+            return Some(file_path.to_path_buf());
+        }
+
+        file_path
+            .strip_prefix(&self.volume_root)
+            .ok()
+            .map(Path::to_path_buf)
+            .or_else(|| {
+                let file_path = fs::canonicalize(file_path).ok()?;
+                let volume_root = fs::canonicalize(&self.volume_root).ok()?;
+                file_path
+                    .strip_prefix(volume_root)
+                    .ok()
+                    .map(Path::to_path_buf)
+            })
+    }
+
     /// Reload the library database from the hard disk.
     pub fn reload(&mut self) -> Result<(), Error> {
         let database_path = self.serato_path().join(DATABASE_FILENAME);
@@ -166,14 +426,16 @@ impl Library {
         file.read_to_end(&mut data)?;
 
         let fields = database::parse(&data)?;
+        self.version = None;
         self.tracks = fields
             .into_iter()
-            .filter_map(|field| {
-                if let database::Field::Track(t) = field {
-                    Some(t)
-                } else {
+            .filter_map(|field| match field {
+                database::Field::Track(t) => Some(t),
+                database::Field::Version(version) => {
+                    self.version = Some(version);
                     None
                 }
+                _ => None,
             })
             .map(Track::from_fields)
             .collect::<Result<HashMap<_, _>, _>>()?;
@@ -181,15 +443,200 @@ impl Library {
         Ok(())
     }
 
+    /// Get the version of the library database (e.g. `2.0/Serato Scratch LIVE Database`).
+    #[must_use]
+    pub fn database_version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
     /// Get all tracks in the library.
     pub fn tracks(&self) -> impl Iterator<Item = &Track> {
         self.tracks.values()
     }
 
+    /// Get all tracks in the library, together with their file paths.
+    pub fn tracks_with_paths(&self) -> impl Iterator<Item = (&Path, &Track)> {
+        self.tracks
+            .iter()
+            .map(|(path, track)| (path.as_path(), track))
+    }
+
     /// Get the track struct for the given path.
+    ///
+    /// The path can either be the path from the database (relative to the volume root) or an
+    /// absolute file system path.
     #[must_use]
     pub fn track(&self, file_path: &Path) -> Option<&Track> {
-        self.tracks.get(file_path)
+        self.track_with_path(file_path).map(|(_, track)| track)
+    }
+
+    /// Get the track struct for the given path, together with its path from the database.
+    ///
+    /// See [`track()`](Self::track) for details.
+    #[must_use]
+    pub fn track_with_path(&self, file_path: &Path) -> Option<(&Path, &Track)> {
+        self.tracks
+            .get_key_value(file_path)
+            .or_else(|| {
+                let file_path = self.relative_path(file_path)?;
+                self.tracks.get_key_value(&file_path)
+            })
+            .map(|(path, track)| (path.as_path(), track))
+    }
+
+    /// Returns the path from the database for the given path (see [`track()`](Self::track)).
+    fn database_track_path(&self, file_path: &Path) -> Option<PathBuf> {
+        if self.tracks.contains_key(file_path) {
+            return Some(file_path.to_path_buf());
+        }
+        self.relative_path(file_path)
+            .filter(|file_path| self.tracks.contains_key(file_path))
+    }
+
+    /// Get the paths of all tracks whose files don't exist on disk (in the order of their paths).
+    ///
+    /// Unlike [`Track::missing`], which is only updated by Serato DJ, this checks the file system.
+    #[must_use]
+    pub fn missing_tracks(&self) -> Vec<&Path> {
+        let mut paths: Vec<_> = self
+            .tracks
+            .keys()
+            .filter(|path| !self.absolute_path(path).is_file())
+            .map(PathBuf::as_path)
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    /// Search the given directory (and its subdirectories) for files that could be the new
+    /// location of the track with the given path.
+    ///
+    /// See [`RelocationCandidate`] for how candidates are rated. They are returned in the order of
+    /// their score (highest first).
+    pub fn find_relocation_candidates(
+        &self,
+        file_path: &Path,
+        search_path: impl AsRef<Path>,
+    ) -> Result<Vec<RelocationCandidate>, Error> {
+        let path = self
+            .database_track_path(file_path)
+            .ok_or_else(|| Error::TrackNotFound {
+                path: file_path.to_path_buf(),
+            })?;
+        let files = relocate::list_files(search_path.as_ref())?;
+        Ok(relocate::find_candidates(
+            &path,
+            &self.tracks[&path],
+            &files,
+        ))
+    }
+
+    /// Change the path of a track, e.g. after the file has been moved.
+    ///
+    /// The new path is written to the database and all subcrates that contain the track. The new
+    /// path needs to be located on the library's volume.
+    pub fn relocate_track(&mut self, file_path: &Path, new_file_path: &Path) -> Result<(), Error> {
+        let path = self
+            .database_track_path(file_path)
+            .ok_or_else(|| Error::TrackNotFound {
+                path: file_path.to_path_buf(),
+            })?;
+        let new_path = self
+            .relative_path(new_file_path)
+            .ok_or_else(|| Error::PathNotOnVolume {
+                path: new_file_path.to_path_buf(),
+            })?;
+        if path == new_path {
+            return Ok(());
+        }
+        if self.tracks.contains_key(&new_path) {
+            return Err(Error::TrackAlreadyExists { path: new_path });
+        }
+        self.relocate_tracks(&HashMap::from([(path, new_path)]))
+    }
+
+    /// Search the given directory (and its subdirectories) for the files of all missing tracks
+    /// and relocate them.
+    ///
+    /// A track is only relocated if there is a single best candidate that matches at least two
+    /// of the file name, the file size and the metadata. Returns the old and new paths of the
+    /// relocated tracks.
+    pub fn relocate_missing_tracks(
+        &mut self,
+        search_path: impl AsRef<Path>,
+    ) -> Result<Vec<(PathBuf, PathBuf)>, Error> {
+        let files = relocate::list_files(search_path.as_ref())?;
+        let mut relocations = HashMap::new();
+        for path in self.missing_tracks() {
+            let candidates = relocate::find_candidates(path, &self.tracks[path], &files);
+            let Some(candidate) = candidates.first() else {
+                continue;
+            };
+            if candidate.score() < 2
+                || candidates
+                    .get(1)
+                    .is_some_and(|next| next.score() == candidate.score())
+            {
+                continue;
+            }
+            let Some(new_path) = self.relative_path(&candidate.path) else {
+                continue;
+            };
+            if self.tracks.contains_key(&new_path) || relocations.values().any(|p| *p == new_path) {
+                continue;
+            }
+            relocations.insert(path.to_path_buf(), new_path);
+        }
+
+        self.relocate_tracks(&relocations)?;
+        let mut relocations: Vec<_> = relocations.into_iter().collect();
+        relocations.sort();
+        Ok(relocations)
+    }
+
+    /// Replace the paths of tracks in the database and all subcrates, then reload the library.
+    fn relocate_tracks(&mut self, relocations: &HashMap<PathBuf, PathBuf>) -> Result<(), Error> {
+        if relocations.is_empty() {
+            return Ok(());
+        }
+
+        let database_path = self.serato_path().join(DATABASE_FILENAME);
+        let mut fields = database::parse(&fs::read(&database_path)?)?;
+        for field in &mut fields {
+            let database::Field::Track(fields) = field else {
+                continue;
+            };
+            let new_path = fields.iter().find_map(|field| match field {
+                database::Field::FilePath(path) => relocations.get(path),
+                _ => None,
+            });
+            let Some(new_path) = new_path else {
+                continue;
+            };
+            for field in fields {
+                match field {
+                    database::Field::FilePath(path) => path.clone_from(new_path),
+                    database::Field::Missing(missing) => *missing = false,
+                    _ => (),
+                }
+            }
+        }
+        let mut data = vec![];
+        database::write(&mut data, &fields)?;
+        util::write_atomically(&database_path, &[&data])?;
+
+        for name in self.subcrates().collect::<Vec<_>>() {
+            let mut subcrate = self.read_subcrate(&name)?;
+            let mut is_changed = false;
+            for (path, new_path) in relocations {
+                is_changed |= subcrate.replace_track(path, new_path);
+            }
+            if is_changed {
+                self.write_subcrate(&name, &subcrate)?;
+            }
+        }
+
+        self.reload()
     }
 
     /// Get a list of subcrate names.
@@ -204,28 +651,390 @@ impl Library {
             .filter_map(|x| crate_name_from_path(&x).ok())
     }
 
+    /// Get the subcrate hierarchy.
+    ///
+    /// Top-level crates are returned in the order of their names.
+    #[must_use]
+    pub fn subcrate_tree(&self) -> Vec<SubcrateNode> {
+        SubcrateNode::build_tree(self.subcrates())
+    }
+
+    /// Read the subcrate with the given path (e.g. `["House", "Deep"]`).
+    pub fn read_subcrate_by_path(&self, path: &[&str]) -> Result<Subcrate, Error> {
+        self.read_subcrate(&subcrate_full_name(path)?)
+    }
+
+    /// Create a new, empty subcrate with the given path (e.g. `["House", "Deep"]`).
+    ///
+    /// Parent crates that don't exist yet are created, too. Fails if the crate itself already
+    /// exists.
+    pub fn create_subcrate_by_path(&self, path: &[&str]) -> Result<Subcrate, Error> {
+        let full_name = subcrate_full_name(path)?;
+        for i in 1..path.len() {
+            let parent_name = subcrate_full_name(&path[..i])?;
+            if !self.subcrate_path(&parent_name)?.exists() {
+                self.create_subcrate(&parent_name)?;
+            }
+        }
+        self.create_subcrate(&full_name)
+    }
+
     /// Get a list of tracks from the subcrate with the given name.
     pub fn subcrate(&self, name: &str) -> Result<impl Iterator<Item = &Track>, Error> {
+        let subcrate = self.read_subcrate(name)?;
+        let tracks: Vec<_> = subcrate
+            .track_paths()
+            .filter_map(|path| self.track(path))
+            .collect();
+        Ok(tracks.into_iter())
+    }
+
+    fn subcrate_path(&self, name: &str) -> Result<PathBuf, Error> {
+        if name.is_empty() || name.contains(['/', '\\']) {
+            return Err(Error::InvalidCrateName {
+                name: name.to_string(),
+            });
+        }
         let filename = format!("{name}.{CRATE_EXTENSION}");
-        let crate_path = self.serato_path().join(SUBCRATE_DIR).join(filename);
-        let mut file = BufReader::new(File::open(crate_path)?);
-        let mut data = vec![];
-        file.read_to_end(&mut data)?;
+        Ok(self.serato_path().join(SUBCRATE_DIR).join(filename))
+    }
 
-        let fields = database::parse(&data)?;
-        let tracks = fields.into_iter().filter_map(move |field| {
-            if let database::Field::Track(track_fields) = field {
-                for track_field in track_fields {
-                    if let database::Field::TrackPath(path) = track_field {
-                        return self.track(&path);
-                    }
-                }
+    /// Read the subcrate with the given name.
+    pub fn read_subcrate(&self, name: &str) -> Result<Subcrate, Error> {
+        Subcrate::read_from_path(self.subcrate_path(name)?)
+    }
+
+    /// Write the subcrate with the given name, replacing an existing crate with that name.
+    pub fn write_subcrate(&self, name: &str, subcrate: &Subcrate) -> Result<(), Error> {
+        let path = self.subcrate_path(name)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        subcrate.write_to_path(path)
+    }
+
+    /// Create a new, empty subcrate with the given name.
+    ///
+    /// Fails if a crate with that name already exists.
+    pub fn create_subcrate(&self, name: &str) -> Result<Subcrate, Error> {
+        if self.subcrate_path(name)?.exists() {
+            return Err(Error::CrateAlreadyExists {
+                name: name.to_string(),
+            });
+        }
+        let subcrate = Subcrate::new();
+        self.write_subcrate(name, &subcrate)?;
+        Ok(subcrate)
+    }
+
+    /// Returns the names of the subcrate with the given name and all of its descendants (e.g.
+    /// `House%%Deep` for `House`).
+    fn subcrate_with_descendants(&self, name: &str) -> Vec<String> {
+        let prefix = format!("{name}{SUBCRATE_SEPARATOR}");
+        self.subcrates()
+            .filter(|subcrate_name| subcrate_name == name || subcrate_name.starts_with(&prefix))
+            .collect()
+    }
+
+    /// Rename the subcrate with the given name.
+    ///
+    /// Its descendants are moved to the new name, too (e.g. `House%%Deep` becomes `Disco%%Deep`
+    /// when renaming `House` to `Disco`). Fails if a crate with one of the new names already
+    /// exists. If the crates are listed in the crate order, the entries are renamed, too.
+    pub fn rename_subcrate(&self, name: &str, new_name: &str) -> Result<(), Error> {
+        self.subcrate_path(name)?;
+        self.subcrate_path(new_name)?;
+        let names = self.subcrate_with_descendants(name);
+        if names.is_empty() {
+            return Err(io::Error::from(io::ErrorKind::NotFound).into());
+        }
+        let renames: Vec<_> = names
+            .into_iter()
+            .map(|old| {
+                let new = format!("{new_name}{}", &old[name.len()..]);
+                (old, new)
+            })
+            .collect();
+        for (_, new) in &renames {
+            if self.subcrate_path(new)?.exists() {
+                return Err(Error::CrateAlreadyExists { name: new.clone() });
             }
+        }
+        for (old, new) in &renames {
+            fs::rename(self.subcrate_path(old)?, self.subcrate_path(new)?)?;
+        }
 
-            None
-        });
-        Ok(tracks)
+        let order = self.subcrate_order()?;
+        let renamed = |entry: &String| renames.iter().find(|(old, _)| old == entry);
+        if order.iter().any(|entry| renamed(entry).is_some()) {
+            let order: Vec<_> = order
+                .iter()
+                .map(|entry| renamed(entry).map_or(entry, |(_, new)| new).clone())
+                .collect();
+            self.set_subcrate_order(&order)?;
+        }
+        Ok(())
+    }
+
+    /// Delete the subcrate with the given name and all of its descendants.
+    ///
+    /// If the crates are listed in the crate order, the entries are removed, too.
+    pub fn delete_subcrate(&self, name: &str) -> Result<(), Error> {
+        self.subcrate_path(name)?;
+        let names = self.subcrate_with_descendants(name);
+        if names.is_empty() {
+            return Err(io::Error::from(io::ErrorKind::NotFound).into());
+        }
+        for subcrate_name in &names {
+            fs::remove_file(self.subcrate_path(subcrate_name)?)?;
+        }
+
+        let order = self.subcrate_order()?;
+        if order.iter().any(|entry| names.contains(entry)) {
+            let order: Vec<_> = order
+                .into_iter()
+                .filter(|entry| !names.contains(entry))
+                .collect();
+            self.set_subcrate_order(&order)?;
+        }
+        Ok(())
+    }
+
+    /// Get a list of smart crate names.
+    pub fn smart_crates(&self) -> impl Iterator<Item = String> {
+        let crates_path = self.serato_path().join(SMART_CRATE_DIR);
+        crates_path
+            .read_dir()
+            .into_iter()
+            .flatten()
+            .filter_map(|x| x.ok())
+            .map(|x| x.path())
+            .filter(|x| {
+                x.extension()
+                    .is_some_and(|ext| ext == SMART_CRATE_EXTENSION)
+            })
+            .filter_map(|x| x.file_stem()?.to_str().map(ToString::to_string))
     }
+
+    /// Read the smart crate with the given name.
+    pub fn read_smart_crate(&self, name: &str) -> Result<SmartCrate, Error> {
+        let filename = format!("{name}.{SMART_CRATE_EXTENSION}");
+        let path = self.serato_path().join(SMART_CRATE_DIR).join(filename);
+        SmartCrate::read_from_path(path)
+    }
+
+    /// Read all history sessions.
+    ///
+    /// Sessions are returned in the order of their file names. If there is no history yet, an
+    /// empty list is returned.
+    pub fn history_sessions(&self) -> Result<Vec<HistorySession>, Error> {
+        let sessions_path = self.serato_path().join(SESSION_DIR);
+        let mut paths = vec![];
+        for entry in sessions_path.read_dir().into_iter().flatten() {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == SESSION_EXTENSION) {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        paths.iter().map(HistorySession::read_from_path).collect()
+    }
+
+    fn read_crate_order_file(&self) -> Result<Vec<u8>, Error> {
+        match fs::read(self.serato_path().join(CRATE_ORDER_FILENAME)) {
+            Ok(data) => Ok(data),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Get the custom order of the subcrates, as displayed in Serato DJ.
+    ///
+    /// The order is read from the `neworder.pref` file in the Serato directory, which is UTF-16
+    /// (big endian) text with one `[crate]<name>` line per crate. If the file does not exist, an
+    /// empty list is returned (Serato DJ then sorts the crates by name).
+    pub fn subcrate_order(&self) -> Result<Vec<String>, Error> {
+        Ok(parse_crate_order(&self.read_crate_order_file()?))
+    }
+
+    /// Set the custom order of the subcrates, as displayed in Serato DJ.
+    ///
+    /// Only the `[crate]<name>` lines of the `neworder.pref` file are replaced, all other lines
+    /// are kept as they are. If the file does not exist yet, it is created.
+    pub fn set_subcrate_order(&self, names: &[String]) -> Result<(), Error> {
+        let data = write_crate_order(&self.read_crate_order_file()?, names);
+        util::write_atomically(&self.serato_path().join(CRATE_ORDER_FILENAME), &[&data])?;
+        Ok(())
+    }
+}
+
+/// Returns the root directory of the volume that contains the given path.
+///
+/// On macOS, external drives are always mounted in `/Volumes/<name>`, and paths on the system
+/// volume are stored relative to `/` by Serato DJ (even if they are located on a separate APFS data
+/// volume, e.g. `/System/Volumes/Data/Users`). Therefore, the mount point is not used here.
+#[cfg(target_os = "macos")]
+fn find_volume_root(path: &Path) -> PathBuf {
+    macos_volume_root(path)
+}
+
+/// Returns `/Volumes/<name>` if the given path is located below it, otherwise `/`.
+#[cfg(any(target_os = "macos", test))]
+fn macos_volume_root(path: &Path) -> PathBuf {
+    use std::path::Component;
+
+    let mut components = path.components();
+    match (components.next(), components.next(), components.next()) {
+        (
+            Some(Component::RootDir),
+            Some(Component::Normal(volumes)),
+            Some(Component::Normal(name)),
+        ) if volumes == "Volumes" => Path::new("/Volumes").join(name),
+        _ => PathBuf::from("/"),
+    }
+}
+
+#[test]
+fn test_macos_volume_root() {
+    assert_eq!(
+        macos_volume_root(Path::new("/Volumes/USB Drive/Music")),
+        Path::new("/Volumes/USB Drive")
+    );
+    assert_eq!(
+        macos_volume_root(Path::new("/Volumes/USB Drive")),
+        Path::new("/Volumes/USB Drive")
+    );
+    assert_eq!(macos_volume_root(Path::new("/Volumes")), Path::new("/"));
+    assert_eq!(
+        macos_volume_root(Path::new("/Users/dj/Music")),
+        Path::new("/")
+    );
+    assert_eq!(
+        macos_volume_root(Path::new("/System/Volumes/Data/Users/dj/Music")),
+        Path::new("/")
+    );
+}
+
+/// Returns the root directory of the volume (i.e. the mount point) that contains the given path.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn find_volume_root(path: &Path) -> PathBuf {
+    use std::os::unix::fs::MetadataExt;
+
+    let Ok(device) = fs::metadata(path).map(|metadata| metadata.dev()) else {
+        return path.to_path_buf();
+    };
+    let mut root = path;
+    while let Some(parent) = root.parent() {
+        match fs::metadata(parent) {
+            Ok(metadata) if metadata.dev() == device => root = parent,
+            _ => break,
+        }
+    }
+    root.to_path_buf()
+}
+
+/// Returns the root directory of the volume (i.e. the drive) that contains the given path.
+#[cfg(not(unix))]
+fn find_volume_root(path: &Path) -> PathBuf {
+    use std::path::Component;
+
+    path.components()
+        .take_while(|component| matches!(component, Component::Prefix(_) | Component::RootDir))
+        .collect()
+}
+
+#[test]
+fn test_find_volume_root() {
+    let path = fs::canonicalize(".").unwrap();
+    let root = find_volume_root(&path);
+    assert!(path.starts_with(&root));
+    assert_eq!(find_volume_root(&root), root);
+}
+
+fn parse_crate_order(data: &[u8]) -> Vec<String> {
+    let code_units = data
+        .chunks_exact(2)
+        .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]));
+    let text = std::char::decode_utf16(code_units)
+        .map(|r| r.unwrap_or(std::char::REPLACEMENT_CHARACTER))
+        .collect::<String>();
+    text.trim_start_matches('\u{FEFF}')
+        .lines()
+        .filter_map(|line| line.trim_end_matches('\r').strip_prefix(CRATE_ORDER_PREFIX))
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// Returns the content of the crate order file `data` with the crate entries replaced by `names`.
+///
+/// The new entries are inserted where the first crate entry was (or before the `[end record]`
+/// line if there was none). The byte order mark and the line endings of `data` are kept.
+fn write_crate_order(data: &[u8], names: &[String]) -> Vec<u8> {
+    let code_units = data
+        .chunks_exact(2)
+        .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]));
+    let text = std::char::decode_utf16(code_units)
+        .map(|r| r.unwrap_or(std::char::REPLACEMENT_CHARACTER))
+        .collect::<String>();
+    let (byte_order_mark, text) = match text.strip_prefix('\u{FEFF}') {
+        Some(text) => ("\u{FEFF}", text),
+        None => ("", text.as_str()),
+    };
+    let line_ending = if text.contains("\r\n") { "\r\n" } else { "\n" };
+
+    let mut lines = vec![];
+    let mut crates_index = None;
+    for line in text.lines() {
+        if line.starts_with(CRATE_ORDER_PREFIX) {
+            crates_index.get_or_insert(lines.len());
+        } else {
+            lines.push(line.to_string());
+        }
+    }
+    if lines.is_empty() {
+        lines = vec!["[begin record]".to_string(), "[end record]".to_string()];
+    }
+    let crates_index = crates_index
+        .or_else(|| lines.iter().position(|line| line == "[end record]"))
+        .unwrap_or(lines.len());
+    lines.splice(
+        crates_index..crates_index,
+        names
+            .iter()
+            .map(|name| format!("{CRATE_ORDER_PREFIX}{name}")),
+    );
+
+    let mut text = byte_order_mark.to_string();
+    for line in lines {
+        text.push_str(&line);
+        text.push_str(line_ending);
+    }
+    text.encode_utf16().flat_map(u16::to_be_bytes).collect()
+}
+
+#[test]
+fn test_write_crate_order() {
+    let names = vec!["House".to_string(), "80s Mashup".to_string()];
+    let data = write_crate_order(&[], &names);
+    let text = "[begin record]\n[crate]House\n[crate]80s Mashup\n[end record]\n";
+    let expected: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+    assert_eq!(data, expected);
+    assert_eq!(parse_crate_order(&data), names);
+
+    let text =
+        "\u{FEFF}[begin record]\r\n[crate]Techno\r\n[crate]Disco\r\n[other]\r\n[end record]\r\n";
+    let data: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+    let data = write_crate_order(&data, &names);
+    let text = "\u{FEFF}[begin record]\r\n[crate]House\r\n[crate]80s Mashup\r\n[other]\r\n[end record]\r\n";
+    let expected: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+    assert_eq!(data, expected);
+}
+
+#[test]
+fn test_parse_crate_order() {
+    let text = "\u{FEFF}[begin record]\r\n[crate]House\r\n[crate]80s Mashup\r\n[end record]\r\n";
+    let data: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+    assert_eq!(parse_crate_order(&data), ["House", "80s Mashup"]);
 }
 
 fn crate_name_from_path(path: &Path) -> Result<String, Error> {
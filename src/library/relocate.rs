@@ -0,0 +1,230 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Finding new locations for tracks that are missing on disk.
+//!
+//! Files that may have been moved are searched for in a directory tree. A file is a candidate for
+//! a missing track if it has the same extension and either the same file name or the same file
+//! size. Candidates are then rated by comparing the file name, the file size and the metadata of
+//! the track.
+//!
+//! If the `id3` feature is enabled, the title and artist are read from the ID3 tags of MP3 and
+//! AIFF files. For all other files (or if the feature is disabled), the metadata is considered
+//! matching if the file name contains the title and artist of the track.
+
+use super::parser::{Track, parse_file_size};
+use crate::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Name of the Serato directory, which is skipped when searching for files
+const SERATO_DIR: &str = "_Serato_";
+
+/// A file that might be the new location of a missing track.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelocationCandidate {
+    /// The absolute path of the file.
+    pub path: PathBuf,
+    /// Whether the file name is the same (compared case-insensitively).
+    pub file_name_matches: bool,
+    /// Whether the file size is the same.
+    pub file_size_matches: bool,
+    /// Whether the title and artist are the same.
+    pub metadata_matches: bool,
+}
+
+impl RelocationCandidate {
+    /// Returns the number of matching properties (between 0 and 3).
+    #[must_use]
+    pub fn score(&self) -> usize {
+        [
+            self.file_name_matches,
+            self.file_size_matches,
+            self.metadata_matches,
+        ]
+        .into_iter()
+        .filter(|matches| *matches)
+        .count()
+    }
+}
+
+/// A file in the searched directory tree.
+#[derive(Debug, Clone)]
+pub(super) struct SearchFile {
+    path: PathBuf,
+    size: u64,
+}
+
+/// Returns all files in the given directory and its subdirectories.
+///
+/// Symbolic links and `_Serato_` directories are skipped. Subdirectories and files that can't be
+/// read (e.g. because of missing permissions) are skipped, too.
+pub(super) fn list_files(path: &Path) -> Result<Vec<SearchFile>, Error> {
+    let mut files = vec![];
+    let mut entries: Vec<_> = path.read_dir()?.collect();
+    while let Some(entry) = entries.pop() {
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if entry.file_name() != SERATO_DIR
+                && let Ok(directory) = entry.path().read_dir()
+            {
+                entries.extend(directory);
+            }
+        } else if file_type.is_file()
+            && let Ok(metadata) = entry.metadata()
+        {
+            files.push(SearchFile {
+                path: entry.path(),
+                size: metadata.len(),
+            });
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Returns the candidates for the track with the given path (as stored in the database).
+///
+/// The candidates are sorted by their score (highest first).
+pub(super) fn find_candidates(
+    file_path: &Path,
+    track: &Track,
+    files: &[SearchFile],
+) -> Vec<RelocationCandidate> {
+    let file_name = lowercase_file_name(file_path);
+    let extension = lowercase_extension(file_path);
+    let mut candidates: Vec<_> = files
+        .iter()
+        .filter(|file| lowercase_extension(&file.path) == extension)
+        .filter_map(|file| {
+            let file_name_matches =
+                file_name.is_some() && lowercase_file_name(&file.path) == file_name;
+            let file_size_matches = file_size_matches(track, file.size);
+            if !file_name_matches && !file_size_matches {
+                return None;
+            }
+            Some(RelocationCandidate {
+                path: file.path.clone(),
+                file_name_matches,
+                file_size_matches,
+                metadata_matches: metadata_matches(track, &file.path),
+            })
+        })
+        .collect();
+    candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.score()));
+    candidates
+}
+
+fn lowercase_file_name(path: &Path) -> Option<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+}
+
+fn lowercase_extension(path: &Path) -> Option<String> {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+}
+
+/// Returns `true` if the file size matches the size stored in the database.
+///
+/// If the exact size in bytes is not known, the human-readable size (e.g. `14.6MB`, where
+/// `1MB = 1024 * 1024` bytes) is compared instead.
+fn file_size_matches(track: &Track, size: u64) -> bool {
+    if let Some(expected) = track.file_size_bytes {
+        return size == u64::from(expected);
+    }
+
+    let Some((expected, unit_size)) = track.file_size.as_deref().and_then(parse_file_size) else {
+        return false;
+    };
+    // The size is rounded to one decimal place.
+    let actual = size as f64 / unit_size;
+    (actual - expected).abs() <= 0.05
+}
+
+/// Returns `true` if the title and artist of the file match those of the track.
+///
+/// Tracks without a title never match.
+fn metadata_matches(track: &Track, path: &Path) -> bool {
+    let Some(title) = &track.title else {
+        return false;
+    };
+
+    #[cfg(feature = "id3")]
+    if let Some((file_title, file_artist)) = read_id3_metadata(path) {
+        let is_same = |a: &str, b: &str| a.trim().eq_ignore_ascii_case(b.trim());
+        return is_same(title, &file_title)
+            && track.artist.as_deref().is_none_or(|artist| {
+                file_artist.is_some_and(|file_artist| is_same(artist, &file_artist))
+            });
+    }
+
+    let Some(file_stem) = path.file_stem() else {
+        return false;
+    };
+    let file_stem = file_stem.to_string_lossy().to_lowercase();
+    file_stem.contains(&title.to_lowercase())
+        && track
+            .artist
+            .as_deref()
+            .is_none_or(|artist| file_stem.contains(&artist.to_lowercase()))
+}
+
+/// Returns the title and artist from the ID3 tag of MP3 and AIFF files.
+#[cfg(feature = "id3")]
+fn read_id3_metadata(path: &Path) -> Option<(String, Option<String>)> {
+    use ::id3::TagLike;
+
+    let extension = lowercase_extension(path)?;
+    if !["mp3", "aif", "aiff"].contains(&extension.as_str()) {
+        return None;
+    }
+    let tag = ::id3::Tag::read_from_path(path).ok()?;
+    let title = tag.title()?.to_string();
+    Some((title, tag.artist().map(ToString::to_string)))
+}
+
+#[test]
+fn test_file_size_matches() {
+    let track = Track {
+        file_size: Some("14.6MB".to_string()),
+        ..Track::new()
+    };
+    assert!(file_size_matches(&track, 15_319_300));
+    assert!(!file_size_matches(&track, 15_419_300));
+
+    let track = Track {
+        file_size_bytes: Some(15_319_300),
+        ..track
+    };
+    assert!(file_size_matches(&track, 15_319_300));
+    assert!(!file_size_matches(&track, 15_319_301));
+    assert!(!file_size_matches(&Track::new(), 15_319_300));
+}
+
+#[test]
+fn test_metadata_matches() {
+    let track = Track {
+        title: Some("Big Love".to_string()),
+        artist: Some("Pete Heller".to_string()),
+        ..Track::new()
+    };
+    assert!(metadata_matches(
+        &track,
+        Path::new("Music/01 - pete heller - big love.wav")
+    ));
+    assert!(!metadata_matches(&track, Path::new("Music/big love.wav")));
+    assert!(!metadata_matches(
+        &Track::new(),
+        Path::new("Music/big love.wav")
+    ));
+}
@@ -0,0 +1,54 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Reading smart crates (`.scrate` files).
+//!
+//! Smart crates use the same encoding as `.crate` files, but instead of a list of tracks they
+//! contain a set of rules. Tracks that match all (or any) of the rules are part of the crate.
+//!
+//! **Note:** There are no smart crates written by Serato DJ in the test data yet, so the fields
+//! that store the rules are not known and are kept as unknown [`Field`]s for now.
+
+use super::database::{self, Field};
+use crate::error::Error;
+use std::fs;
+use std::path::Path;
+
+/// The content of a `.scrate` file.
+///
+/// The fields are kept in the order they were read, including fields that are unknown to this
+/// library, so that writing an unmodified smart crate reproduces the original file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmartCrate {
+    fields: Vec<Field>,
+}
+
+impl SmartCrate {
+    /// Parse the content of a `.scrate` file.
+    pub fn parse(input: &[u8]) -> Result<Self, Error> {
+        let fields = database::parse(input)?;
+        Ok(Self { fields })
+    }
+
+    /// Serialize the smart crate.
+    pub fn write(&self, writer: &mut impl std::io::Write) -> Result<usize, Error> {
+        database::write(writer, &self.fields)
+    }
+
+    /// Read the `.scrate` file at the given path.
+    pub fn read_from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let data = fs::read(path)?;
+        Self::parse(&data)
+    }
+
+    /// Returns the low-level fields of the smart crate.
+    #[must_use]
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+}
@@ -0,0 +1,505 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Reading, creating and editing `.crate` files.
+//!
+//! ```
+//! use std::path::PathBuf;
+//! use triseratops::library::Subcrate;
+//!
+//! let mut subcrate = Subcrate::new();
+//! subcrate.add_track(PathBuf::from("Music/Some Artist - Some Track.mp3"));
+//! subcrate.set_sorting("artist", false);
+//!
+//! let mut data = vec![];
+//! subcrate.write(&mut data).expect("Failed to write crate!");
+//! ```
+
+use super::database::{self, Field};
+use crate::error::Error;
+use crate::util;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Separator between the names of parent and child crates in `.crate` file names.
+pub const SUBCRATE_SEPARATOR: &str = "%%";
+
+/// The version string that Serato DJ writes to `.crate` files.
+const VERSION: &str = "1.0/Serato ScratchLive Crate";
+
+/// The column that Serato DJ sorts new crates by.
+const DEFAULT_SORTING_COLUMN: &str = "#";
+
+/// The columns (and their widths) that Serato DJ writes to new crates.
+const DEFAULT_COLUMNS: [(&str, &str); 7] = [
+    ("song", "450"),
+    ("artist", "0"),
+    ("bpm", "0"),
+    ("key", "0"),
+    ("album", "0"),
+    ("length", "0"),
+    ("comment", "0"),
+];
+
+/// A column in the track list of a crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    /// The column name (e.g. `song` or `bpm`).
+    pub name: String,
+    /// The column width (as text, e.g. `450`).
+    pub width: String,
+}
+
+impl Column {
+    fn from_fields(fields: &[Field]) -> Self {
+        let mut column = Self {
+            name: String::new(),
+            width: String::new(),
+        };
+        for field in fields {
+            match field {
+                Field::ColumnName(name) => column.name.clone_from(name),
+                Field::ColumnWidth(width) => column.width.clone_from(width),
+                _ => (),
+            }
+        }
+        column
+    }
+
+    fn to_field(&self) -> Field {
+        Field::ColumnTitle(vec![
+            Field::ColumnName(self.name.clone()),
+            Field::ColumnWidth(self.width.clone()),
+        ])
+    }
+}
+
+/// A crate in the crate hierarchy.
+///
+/// Nested crates are stored as flat files, where the names of the parent crates are prepended to
+/// the file name, separated by [`SUBCRATE_SEPARATOR`] (e.g. `House%%Deep.crate`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubcrateNode {
+    /// The full name of the crate (e.g. `House%%Deep`), as used by
+    /// [`Library::read_subcrate()`](super::Library::read_subcrate).
+    pub full_name: String,
+    /// The name of the crate that is displayed in Serato DJ (e.g. `Deep`).
+    pub name: String,
+    /// Whether a `.crate` file exists for this crate.
+    ///
+    /// This is `false` for parent crates that only exist implicitly, because there is a `.crate`
+    /// file for one of their children.
+    pub exists: bool,
+    /// The child crates (sorted by name).
+    pub children: Vec<SubcrateNode>,
+}
+
+impl SubcrateNode {
+    /// Returns the names of this crate and its parents (starting with the top-level crate).
+    pub fn path(&self) -> impl Iterator<Item = &str> {
+        self.full_name.split(SUBCRATE_SEPARATOR)
+    }
+
+    /// Returns the descendant of this crate with the given path (relative to this crate).
+    #[must_use]
+    pub fn find(&self, path: &[&str]) -> Option<&Self> {
+        match path.split_first() {
+            None => Some(self),
+            Some((name, rest)) => self
+                .children
+                .iter()
+                .find(|child| child.name == *name)?
+                .find(rest),
+        }
+    }
+
+    /// Build the crate hierarchy from a list of full crate names.
+    pub(crate) fn build_tree(full_names: impl IntoIterator<Item = String>) -> Vec<Self> {
+        let mut roots: Vec<Self> = vec![];
+        for full_name in full_names {
+            let mut nodes = &mut roots;
+            let mut parent_name: Option<String> = None;
+            let names: Vec<&str> = full_name.split(SUBCRATE_SEPARATOR).collect();
+            for (i, name) in names.iter().enumerate() {
+                let node_name = match &parent_name {
+                    Some(parent_name) => format!("{parent_name}{SUBCRATE_SEPARATOR}{name}"),
+                    None => (*name).to_string(),
+                };
+                let index = match nodes.iter().position(|node| node.name == *name) {
+                    Some(index) => index,
+                    None => {
+                        nodes.push(Self {
+                            full_name: node_name.clone(),
+                            name: (*name).to_string(),
+                            exists: false,
+                            children: vec![],
+                        });
+                        nodes.len() - 1
+                    }
+                };
+                if i == names.len() - 1 {
+                    nodes[index].exists = true;
+                }
+                nodes = &mut nodes[index].children;
+                parent_name = Some(node_name);
+            }
+        }
+        Self::sort(&mut roots);
+        roots
+    }
+
+    fn sort(nodes: &mut [Self]) {
+        nodes.sort_by(|a, b| a.name.cmp(&b.name));
+        for node in nodes {
+            Self::sort(&mut node.children);
+        }
+    }
+}
+
+/// Returns the full crate name for the given crate path (e.g. `["House", "Deep"]`).
+///
+/// Fails if the path is empty or one of the names is empty or contains the
+/// [`SUBCRATE_SEPARATOR`].
+pub fn subcrate_full_name(path: &[&str]) -> Result<String, Error> {
+    if path.is_empty()
+        || path
+            .iter()
+            .any(|name| name.is_empty() || name.contains(SUBCRATE_SEPARATOR))
+    {
+        return Err(Error::InvalidCrateName {
+            name: path.join(SUBCRATE_SEPARATOR),
+        });
+    }
+    Ok(path.join(SUBCRATE_SEPARATOR))
+}
+
+#[test]
+fn test_build_tree() {
+    let names = [
+        "House%%Deep",
+        "Techno",
+        "House",
+        "House%%Deep%%Dub",
+        "Disco%%Italo",
+    ];
+    let tree = SubcrateNode::build_tree(names.iter().map(ToString::to_string));
+    let root_names: Vec<_> = tree.iter().map(|node| node.name.as_str()).collect();
+    assert_eq!(root_names, ["Disco", "House", "Techno"]);
+
+    assert!(!tree[0].exists);
+    assert_eq!(tree[0].children[0].full_name, "Disco%%Italo");
+    assert!(tree[0].children[0].exists);
+
+    let dub = tree[1].find(&["Deep", "Dub"]).unwrap();
+    assert_eq!(dub.full_name, "House%%Deep%%Dub");
+    assert_eq!(dub.name, "Dub");
+    assert_eq!(dub.path().collect::<Vec<_>>(), ["House", "Deep", "Dub"]);
+    assert!(tree[1].find(&["Dub"]).is_none());
+}
+
+#[test]
+fn test_subcrate_full_name() {
+    assert_eq!(
+        subcrate_full_name(&["House", "Deep"]).unwrap(),
+        "House%%Deep"
+    );
+    assert!(subcrate_full_name(&[]).is_err());
+    assert!(subcrate_full_name(&["House", ""]).is_err());
+    assert!(subcrate_full_name(&["House%%Deep"]).is_err());
+}
+
+/// The content of a `.crate` file.
+///
+/// The fields are kept in the order they were read, including fields that are unknown to this
+/// library, so that writing an unmodified crate reproduces the original file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subcrate {
+    fields: Vec<Field>,
+}
+
+impl Subcrate {
+    /// Create an empty crate with the sorting and columns that Serato DJ uses for new crates.
+    #[must_use]
+    pub fn new() -> Self {
+        let mut fields = vec![
+            Field::Version(VERSION.to_string()),
+            Field::Sorting(vec![
+                Field::ColumnName(DEFAULT_SORTING_COLUMN.to_string()),
+                Field::ReverseOrder(false),
+            ]),
+        ];
+        fields.extend(DEFAULT_COLUMNS.iter().map(|(name, width)| {
+            Column {
+                name: (*name).to_string(),
+                width: (*width).to_string(),
+            }
+            .to_field()
+        }));
+        Self { fields }
+    }
+
+    /// Parse the content of a `.crate` file.
+    pub fn parse(input: &[u8]) -> Result<Self, Error> {
+        let fields = database::parse(input)?;
+        Ok(Self { fields })
+    }
+
+    /// Serialize the crate.
+    pub fn write(&self, writer: &mut impl io::Write) -> Result<usize, Error> {
+        database::write(writer, &self.fields)
+    }
+
+    /// Read the `.crate` file at the given path.
+    pub fn read_from_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let data = fs::read(path)?;
+        Self::parse(&data)
+    }
+
+    /// Write the crate to the `.crate` file at the given path.
+    ///
+    /// If the file already exists, it is overwritten.
+    pub fn write_to_path(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let mut data = vec![];
+        self.write(&mut data)?;
+        util::write_atomically(path.as_ref(), &[&data])?;
+        Ok(())
+    }
+
+    /// Returns the low-level fields of the crate.
+    #[must_use]
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// Returns the paths of the tracks in the crate (in the order they are displayed).
+    pub fn track_paths(&self) -> impl Iterator<Item = &Path> {
+        self.fields.iter().filter_map(|field| match field {
+            Field::Track(fields) => fields.iter().find_map(|field| match field {
+                Field::TrackPath(path) => Some(path.as_path()),
+                _ => None,
+            }),
+            _ => None,
+        })
+    }
+
+    /// Returns `true` if the crate contains the track with the given path.
+    #[must_use]
+    pub fn contains_track(&self, path: &Path) -> bool {
+        self.track_paths().any(|track_path| track_path == path)
+    }
+
+    /// Returns the indices of the track fields in `self.fields`.
+    fn track_field_indices(&self) -> Vec<usize> {
+        self.fields
+            .iter()
+            .enumerate()
+            .filter(|(_, field)| matches!(field, Field::Track(_)))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Append the track with the given path to the crate.
+    ///
+    /// Returns `false` (and leaves the crate unchanged) if the crate already contains the track.
+    pub fn add_track(&mut self, path: PathBuf) -> bool {
+        if self.contains_track(&path) {
+            return false;
+        }
+        self.fields.push(Field::Track(vec![Field::TrackPath(path)]));
+        true
+    }
+
+    /// Remove the track with the given path from the crate.
+    ///
+    /// Returns `false` if the crate does not contain the track.
+    pub fn remove_track(&mut self, path: &Path) -> bool {
+        let length = self.fields.len();
+        self.fields.retain(|field| match field {
+            Field::Track(fields) => !fields
+                .iter()
+                .any(|field| matches!(field, Field::TrackPath(track_path) if track_path == path)),
+            _ => true,
+        });
+        self.fields.len() != length
+    }
+
+    /// Replace the path of a track in the crate (e.g. after the file has been moved).
+    ///
+    /// The position of the track in the crate is kept. Returns `false` if the crate does not
+    /// contain the track.
+    pub fn replace_track(&mut self, path: &Path, new_path: &Path) -> bool {
+        let mut is_replaced = false;
+        for field in &mut self.fields {
+            let Field::Track(fields) = field else {
+                continue;
+            };
+            for field in fields {
+                if let Field::TrackPath(track_path) = field
+                    && track_path == path
+                {
+                    *track_path = new_path.to_path_buf();
+                    is_replaced = true;
+                }
+            }
+        }
+        is_replaced
+    }
+
+    /// Move the track at index `from` to index `to` (both counting tracks only).
+    ///
+    /// Returns `false` (and leaves the crate unchanged) if one of the indices is out of range.
+    pub fn move_track(&mut self, from: usize, to: usize) -> bool {
+        let indices = self.track_field_indices();
+        if from >= indices.len() || to >= indices.len() {
+            return false;
+        }
+
+        let field = self.fields.remove(indices[from]);
+        let indices = self.track_field_indices();
+        let position = if to < indices.len() {
+            indices[to]
+        } else {
+            indices.last().map_or(self.fields.len(), |index| index + 1)
+        };
+        self.fields.insert(position, field);
+        true
+    }
+
+    /// Returns the column the tracks are sorted by and whether the order is reversed.
+    #[must_use]
+    pub fn sorting(&self) -> Option<(&str, bool)> {
+        self.fields.iter().find_map(|field| match field {
+            Field::Sorting(fields) => {
+                let column = fields.iter().find_map(|field| match field {
+                    Field::ColumnName(name) => Some(name.as_str()),
+                    _ => None,
+                })?;
+                let is_reversed = fields.contains(&Field::ReverseOrder(true));
+                Some((column, is_reversed))
+            }
+            _ => None,
+        })
+    }
+
+    /// Sort the tracks by the column with the given name.
+    pub fn set_sorting(&mut self, column: &str, is_reversed: bool) {
+        let sorting = Field::Sorting(vec![
+            Field::ColumnName(column.to_string()),
+            Field::ReverseOrder(is_reversed),
+        ]);
+        if let Some(field) = self
+            .fields
+            .iter_mut()
+            .find(|field| matches!(field, Field::Sorting(_)))
+        {
+            *field = sorting;
+            return;
+        }
+
+        let position = self
+            .fields
+            .iter()
+            .position(|field| matches!(field, Field::Version(_)))
+            .map_or(0, |index| index + 1);
+        self.fields.insert(position, sorting);
+    }
+
+    /// Returns the columns of the track list.
+    #[must_use]
+    pub fn columns(&self) -> Vec<Column> {
+        self.fields
+            .iter()
+            .filter_map(|field| match field {
+                Field::ColumnTitle(fields) => Some(Column::from_fields(fields)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Replace the columns of the track list.
+    pub fn set_columns(&mut self, columns: &[Column]) {
+        let position = self
+            .fields
+            .iter()
+            .position(|field| matches!(field, Field::ColumnTitle(_)))
+            .or_else(|| {
+                self.fields
+                    .iter()
+                    .rposition(|field| matches!(field, Field::Version(_) | Field::Sorting(_)))
+                    .map(|index| index + 1)
+            })
+            .unwrap_or(0);
+        self.fields
+            .retain(|field| !matches!(field, Field::ColumnTitle(_)));
+        let position = position.min(self.fields.len());
+        self.fields
+            .splice(position..position, columns.iter().map(Column::to_field));
+    }
+}
+
+impl Default for Subcrate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn test_new_subcrate_roundtrip() {
+    let mut subcrate = Subcrate::new();
+    assert_eq!(subcrate.sorting(), Some(("#", false)));
+    assert_eq!(subcrate.columns().len(), 7);
+    assert!(subcrate.add_track(PathBuf::from("Music/a.mp3")));
+    assert!(subcrate.add_track(PathBuf::from("Music/b.mp3")));
+    assert!(!subcrate.add_track(PathBuf::from("Music/a.mp3")));
+
+    let mut data = vec![];
+    subcrate.write(&mut data).unwrap();
+    assert!(data.starts_with(b"vrsn"));
+    assert_eq!(Subcrate::parse(&data).unwrap(), subcrate);
+}
+
+#[test]
+fn test_edit_subcrate() {
+    let mut subcrate = Subcrate::new();
+    for name in ["a", "b", "c"] {
+        subcrate.add_track(PathBuf::from(format!("{name}.mp3")));
+    }
+    let paths = |subcrate: &Subcrate| -> Vec<String> {
+        subcrate
+            .track_paths()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect()
+    };
+
+    assert!(subcrate.move_track(0, 2));
+    assert_eq!(paths(&subcrate), ["b.mp3", "c.mp3", "a.mp3"]);
+    assert!(subcrate.move_track(2, 1));
+    assert_eq!(paths(&subcrate), ["b.mp3", "a.mp3", "c.mp3"]);
+    assert!(!subcrate.move_track(0, 3));
+
+    assert!(subcrate.remove_track(Path::new("a.mp3")));
+    assert!(!subcrate.remove_track(Path::new("a.mp3")));
+    assert_eq!(paths(&subcrate), ["b.mp3", "c.mp3"]);
+
+    assert!(subcrate.replace_track(Path::new("b.mp3"), Path::new("d.mp3")));
+    assert!(!subcrate.replace_track(Path::new("b.mp3"), Path::new("d.mp3")));
+    assert_eq!(paths(&subcrate), ["d.mp3", "c.mp3"]);
+
+    subcrate.set_sorting("bpm", true);
+    assert_eq!(subcrate.sorting(), Some(("bpm", true)));
+
+    let columns = vec![Column {
+        name: "song".to_string(),
+        width: "200".to_string(),
+    }];
+    subcrate.set_columns(&columns);
+    assert_eq!(subcrate.columns(), columns);
+    assert!(matches!(subcrate.fields()[2], Field::ColumnTitle(_)));
+    assert!(matches!(subcrate.fields()[3], Field::Track(_)));
+}
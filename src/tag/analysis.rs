@@ -31,12 +31,31 @@ use std::io;
 ///     println!("{:?}", markers);
 /// }
 /// ```
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct Analysis {
     /// The analysis version.
     pub version: Version,
 }
 
+impl Analysis {
+    /// The analysis version written by current versions of Serato DJ.
+    pub const DEFAULT_VERSION: Version = Version { major: 2, minor: 1 };
+
+    /// Create a new `Serato Analysis` tag with the [default version](Self::DEFAULT_VERSION).
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            version: Self::DEFAULT_VERSION,
+        }
+    }
+}
+
+impl Default for Analysis {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Tag for Analysis {
     const NAME: &'static str = "Serato Analysis";
 
@@ -47,6 +47,22 @@ pub struct Autotags {
     pub gain_db: f64,
 }
 
+impl Autotags {
+    /// The tag version written by Serato DJ.
+    pub const VERSION: Version = Version { major: 1, minor: 1 };
+
+    /// Create a new `Serato Autotags` tag with the given values.
+    #[must_use]
+    pub const fn new(bpm: f64, auto_gain: f64, gain_db: f64) -> Self {
+        Self {
+            version: Self::VERSION,
+            bpm,
+            auto_gain,
+            gain_db,
+        }
+    }
+}
+
 impl Tag for Autotags {
     const NAME: &'static str = "Serato Autotags";
 
@@ -67,6 +67,35 @@ pub struct Beatgrid {
     pub footer: u8,
 }
 
+impl Beatgrid {
+    /// The tag version written by Serato DJ.
+    pub const VERSION: Version = Version { major: 1, minor: 0 };
+
+    /// Create a new `Serato BeatGrid` tag from the given markers.
+    ///
+    /// The footer byte is set to `0x00`.
+    #[must_use]
+    pub const fn new(
+        non_terminal_markers: Vec<NonTerminalMarker>,
+        terminal_marker: TerminalMarker,
+    ) -> Self {
+        Self {
+            version: Self::VERSION,
+            non_terminal_markers,
+            terminal_marker,
+            footer: 0,
+        }
+    }
+
+    /// Create a new `Serato BeatGrid` tag with a constant tempo.
+    ///
+    /// The beatgrid only consists of a single terminal marker at `position` (in seconds).
+    #[must_use]
+    pub const fn with_constant_bpm(position: f32, bpm: f32) -> Self {
+        Self::new(Vec::new(), TerminalMarker { position, bpm })
+    }
+}
+
 impl Tag for Beatgrid {
     const NAME: &'static str = "Serato BeatGrid";
 
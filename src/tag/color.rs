@@ -169,6 +169,63 @@ impl Color {
         self
     }
 
+    /// Return the default color of the hotcue with the given (zero-based) index.
+    ///
+    /// The color is returned as stored in the metadata (i.e. from the Serato DJ Intro palette).
+    /// Indices greater than 7 wrap around.
+    ///
+    /// ```
+    /// use triseratops::tag::color::Color;
+    ///
+    /// assert_eq!(Color::default_hotcue_color(0), Color { red: 0xCC, green: 0x00, blue: 0x00 });
+    /// assert_eq!(Color::default_hotcue_color(2), Color { red: 0x00, green: 0x00, blue: 0xCC });
+    /// assert_eq!(Color::default_hotcue_color(8), Color::default_hotcue_color(0));
+    /// ```
+    #[must_use]
+    pub fn default_hotcue_color(index: u8) -> Self {
+        let palette_index = DEFAULT_HOTCUE_PALETTE_INDICES[usize::from(index) % 8];
+        HOTCUE_COLORS_INTRO[palette_index]
+    }
+
+    /// Return the Serato hotcue color that is the closest to this color.
+    ///
+    /// This is useful for importing colors from other DJ software, where the user can choose from
+    /// a different palette. The color is compared with the Serato DJ Pro palette (i.e. the colors
+    /// that are displayed) and returned as stored in the metadata (i.e. from the Serato DJ Intro
+    /// palette).
+    ///
+    /// ```
+    /// use triseratops::tag::color::Color;
+    ///
+    /// // A color that is not in the palette...
+    /// let color = Color { red: 0xE0, green: 0x20, blue: 0x20 };
+    /// let stored_color = color.into_nearest_hotcue_color();
+    /// assert_eq!(stored_color, Color { red: 0xCC, green: 0x00, blue: 0x00 });
+    ///
+    /// // ... and colors that are already in the Serato DJ Pro palette.
+    /// let pro_color = Color { red: 0xF8, green: 0x82, blue: 0x1A };
+    /// assert_eq!(pro_color.into_nearest_hotcue_color(), pro_color.into_intro_hotcue_color());
+    /// ```
+    #[must_use]
+    pub fn into_nearest_hotcue_color(self) -> Self {
+        let distance = |color: &Color| {
+            [
+                (color.red, self.red),
+                (color.green, self.green),
+                (color.blue, self.blue),
+            ]
+            .into_iter()
+            .map(|(a, b)| u32::from(a.abs_diff(b)).pow(2))
+            .sum::<u32>()
+        };
+        let index = HOTCUE_COLORS_PRO
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, color)| distance(color))
+            .map_or(0, |(index, _)| index);
+        HOTCUE_COLORS_INTRO[index]
+    }
+
     /// Return the displayed track color from the stored track color value.
     ///
     /// Serato stores Track colors differently from how they are displayed in
@@ -282,6 +339,9 @@ impl From<Color> for u32 {
     }
 }
 
+/// Indices of the default colors of hotcues 1-8 in the hotcue palettes (see table above).
+const DEFAULT_HOTCUE_PALETTE_INDICES: [usize; 8] = [0, 2, 12, 3, 6, 15, 9, 14];
+
 const HOTCUE_COLORS_INTRO: [Color; 18] = [
     Color {
         red: 0xCC,
@@ -9,8 +9,9 @@
 //! Provides the [`TagContainer` struct](TagContainer), a simple interface to access data in
 //! Serato's tags.
 use super::{
-    Autotags, Beatgrid, Markers, Markers2, Overview, beatgrid, color::Color, format::flac::FLACTag,
-    format::id3::ID3Tag, format::mp4::MP4Tag, format::ogg::OggTag, generic, markers,
+    Analysis, Autotags, Beatgrid, Markers, Markers2, Overview, RelVolAd, VidAssoc, beatgrid,
+    color::Color, format::flac::FLACTag, format::id3::ID3Tag, format::mp4::MP4Tag,
+    format::ogg::OggTag, generic, markers,
 };
 use crate::error::Error;
 use std::io;
@@ -24,18 +25,21 @@ use std::io;
 /// implements the same merge strategies for inconsistent data that Serato uses, too.
 #[derive(Debug, Clone)]
 pub struct TagContainer {
+    analysis: Option<Analysis>,
     autotags: Option<Autotags>,
     beatgrid: Option<Beatgrid>,
     markers: Option<Markers>,
     markers2: Option<Markers2>,
     overview: Option<Overview>,
+    relvolad: Option<RelVolAd>,
+    vidassoc: Option<VidAssoc>,
 }
 
 /// The tag type of the data.
 ///
 /// The format of the Serato tag data differs between tag types.
 /// Therefore it's necessary to tell the parser from what kind of the the data originates from.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TagFormat {
     ID3,
     FLAC,
@@ -48,11 +52,72 @@ impl TagContainer {
     #[must_use]
     pub const fn new() -> Self {
         Self {
+            analysis: None,
             autotags: None,
             beatgrid: None,
             markers: None,
             markers2: None,
             overview: None,
+            relvolad: None,
+            vidassoc: None,
+        }
+    }
+
+    /// Create a container with the tags that Serato DJ writes to a file before it has been
+    /// analyzed.
+    ///
+    /// This contains a [`Serato Analysis`](Analysis) tag, an empty [`Serato
+    /// Markers2`](Markers2) tag and (if supported by the tag format) an empty [`Serato
+    /// Markers_`](Markers) tag. Cues, loops, the beatgrid, etc. can then be added using the
+    /// setters.
+    #[must_use]
+    pub fn with_empty_tags(tag_format: TagFormat) -> Self {
+        let markers = match tag_format {
+            TagFormat::ID3 | TagFormat::MP4 => Some(Markers::new()),
+            TagFormat::FLAC | TagFormat::Ogg => None,
+        };
+        Self {
+            analysis: Some(Analysis::new()),
+            markers,
+            markers2: Some(Markers2::new(tag_format)),
+            ..Self::new()
+        }
+    }
+
+    /// Parse the [`Serato Analysis`](Analysis) tag.
+    pub fn parse_analysis(&mut self, input: &[u8], tag_format: TagFormat) -> Result<(), Error> {
+        match tag_format {
+            TagFormat::ID3 => {
+                self.analysis = Some(Analysis::parse_id3(input)?);
+            }
+            TagFormat::FLAC => {
+                self.analysis = Some(Analysis::parse_flac(input)?);
+            }
+            TagFormat::MP4 => {
+                self.analysis = Some(Analysis::parse_mp4(input)?);
+            }
+            TagFormat::Ogg => {
+                self.analysis = Some(Analysis::parse_ogg(input)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the [`Serato Analysis`](Analysis) tag.
+    pub fn write_analysis(
+        &self,
+        writer: &mut impl io::Write,
+        tag_format: TagFormat,
+    ) -> Result<usize, Error> {
+        let tag = match &self.analysis {
+            Some(x) => x,
+            None => return Err(Error::NoTagDataAvailable),
+        };
+        match tag_format {
+            TagFormat::ID3 => tag.write_id3(writer),
+            TagFormat::FLAC => tag.write_flac(writer),
+            TagFormat::MP4 => tag.write_mp4(writer),
+            TagFormat::Ogg => tag.write_ogg(writer),
         }
     }
 
@@ -190,7 +255,7 @@ impl TagContainer {
             TagFormat::ID3 => tag.write_id3(writer),
             TagFormat::FLAC => tag.write_flac(writer),
             TagFormat::MP4 => tag.write_mp4(writer),
-            _ => Err(Error::UnsupportedTagFormat),
+            TagFormat::Ogg => tag.write_ogg(writer),
         }
     }
 
@@ -229,6 +294,114 @@ impl TagContainer {
         }
     }
 
+    /// Parse the [`Serato RelVolAd`](RelVolAd) tag.
+    pub fn parse_relvolad(&mut self, input: &[u8], tag_format: TagFormat) -> Result<(), Error> {
+        match tag_format {
+            TagFormat::FLAC => {
+                self.relvolad = Some(RelVolAd::parse_flac(input)?);
+            }
+            TagFormat::MP4 => {
+                self.relvolad = Some(RelVolAd::parse_mp4(input)?);
+            }
+            _ => return Err(Error::UnsupportedTagFormat),
+        }
+        Ok(())
+    }
+
+    /// Write the [`Serato RelVolAd`](RelVolAd) tag.
+    pub fn write_relvolad(
+        &self,
+        writer: &mut impl io::Write,
+        tag_format: TagFormat,
+    ) -> Result<usize, Error> {
+        let tag = match &self.relvolad {
+            Some(x) => x,
+            None => return Err(Error::NoTagDataAvailable),
+        };
+        match tag_format {
+            TagFormat::FLAC => tag.write_flac(writer),
+            TagFormat::MP4 => tag.write_mp4(writer),
+            _ => Err(Error::UnsupportedTagFormat),
+        }
+    }
+
+    /// Parse the [`Serato VidAssoc`](VidAssoc) tag.
+    pub fn parse_vidassoc(&mut self, input: &[u8], tag_format: TagFormat) -> Result<(), Error> {
+        match tag_format {
+            TagFormat::FLAC => {
+                self.vidassoc = Some(VidAssoc::parse_flac(input)?);
+            }
+            TagFormat::MP4 => {
+                self.vidassoc = Some(VidAssoc::parse_mp4(input)?);
+            }
+            _ => return Err(Error::UnsupportedTagFormat),
+        }
+        Ok(())
+    }
+
+    /// Write the [`Serato VidAssoc`](VidAssoc) tag.
+    pub fn write_vidassoc(
+        &self,
+        writer: &mut impl io::Write,
+        tag_format: TagFormat,
+    ) -> Result<usize, Error> {
+        let tag = match &self.vidassoc {
+            Some(x) => x,
+            None => return Err(Error::NoTagDataAvailable),
+        };
+        match tag_format {
+            TagFormat::FLAC => tag.write_flac(writer),
+            TagFormat::MP4 => tag.write_mp4(writer),
+            _ => Err(Error::UnsupportedTagFormat),
+        }
+    }
+
+    /// Set the [`Serato Analysis`](Analysis) tag.
+    pub fn set_analysis(&mut self, analysis: Analysis) {
+        self.analysis = Some(analysis);
+    }
+
+    /// Set the [`Serato Autotags`](Autotags) tag.
+    pub fn set_autotags(&mut self, autotags: Autotags) {
+        self.autotags = Some(autotags);
+    }
+
+    /// Set the [`Serato BeatGrid`](Beatgrid) tag.
+    pub fn set_beatgrid(&mut self, beatgrid: Beatgrid) {
+        self.beatgrid = Some(beatgrid);
+    }
+
+    /// Set the [`Serato Markers_`](Markers) tag.
+    pub fn set_markers(&mut self, markers: Markers) {
+        self.markers = Some(markers);
+    }
+
+    /// Set the [`Serato Markers2`](Markers2) tag.
+    pub fn set_markers2(&mut self, markers2: Markers2) {
+        self.markers2 = Some(markers2);
+    }
+
+    /// Set the [`Serato Overview`](Overview) tag.
+    pub fn set_overview(&mut self, overview: Overview) {
+        self.overview = Some(overview);
+    }
+
+    /// Set the [`Serato RelVolAd`](RelVolAd) tag.
+    pub fn set_relvolad(&mut self, relvolad: RelVolAd) {
+        self.relvolad = Some(relvolad);
+    }
+
+    /// Set the [`Serato VidAssoc`](VidAssoc) tag.
+    pub fn set_vidassoc(&mut self, vidassoc: VidAssoc) {
+        self.vidassoc = Some(vidassoc);
+    }
+
+    /// Returns the analysis version from the [`Serato Analysis`](Analysis) tag.
+    #[must_use]
+    pub fn analysis_version(&self) -> Option<generic::Version> {
+        self.analysis.as_ref().map(|tag| tag.version)
+    }
+
     /// Returns the [`auto_gain`](Autotags::auto_gain) value from the [`Serato Autotags`](Autotags) tag.
     #[must_use]
     pub fn auto_gain(&self) -> Option<f64> {
@@ -424,6 +597,119 @@ impl TagContainer {
     pub fn overview_data(&self) -> Option<&[Vec<u8>]> {
         self.overview.as_ref().map(|overview| &overview.data[..])
     }
+
+    /// Returns the path of the associated video file from the [`Serato VidAssoc`](VidAssoc) tag.
+    ///
+    /// See [`VidAssoc::video_path()`] for details.
+    #[must_use]
+    pub fn video_path(&self) -> Option<&str> {
+        self.vidassoc.as_ref().and_then(VidAssoc::video_path)
+    }
+
+    /// Sets a cue in the [`Serato Markers_`](Markers) and [`Serato Markers2`](Markers2) tags.
+    ///
+    /// An existing cue with the same index is replaced. Since `Serato Markers_` only stores the
+    /// first 5 cues (and no labels), cues with higher indices are only written to `Serato
+    /// Markers2`. Either way, [`cues()`](Self::cues) will return the cue afterwards.
+    ///
+    /// Returns an error if none of the tags is present or if the cue can't be stored in any of
+    /// the present tags.
+    pub fn set_cue(&mut self, cue: generic::Cue) -> Result<(), Error> {
+        let mut is_stored = false;
+        if let Some(m) = &mut self.markers {
+            is_stored |= m.set_cue(&cue);
+        }
+        if let Some(m) = &mut self.markers2 {
+            m.set_cue(cue);
+            is_stored = true;
+        }
+
+        if !is_stored {
+            return Err(Error::NoTagDataAvailable);
+        }
+
+        Ok(())
+    }
+
+    /// Removes the cue with the given index from the [`Serato Markers_`](Markers) and [`Serato
+    /// Markers2`](Markers2) tags.
+    pub fn remove_cue(&mut self, index: u8) {
+        if let Some(m) = &mut self.markers {
+            m.remove_cue(index);
+        }
+        if let Some(m) = &mut self.markers2 {
+            m.remove_cue(index);
+        }
+    }
+
+    /// Sets a saved loop in the [`Serato Markers_`](Markers) and [`Serato Markers2`](Markers2)
+    /// tags.
+    ///
+    /// An existing loop with the same index is replaced. Since `Serato Markers_` only stores the
+    /// first 9 loops (and no labels), loops with higher indices are only written to `Serato
+    /// Markers2`. Either way, [`loops()`](Self::loops) will return the loop afterwards.
+    ///
+    /// Returns an error if none of the tags is present or if the loop can't be stored in any of
+    /// the present tags.
+    pub fn set_loop(&mut self, saved_loop: generic::Loop) -> Result<(), Error> {
+        let mut is_stored = false;
+        if let Some(m) = &mut self.markers {
+            is_stored |= m.set_loop(&saved_loop);
+        }
+        if let Some(m) = &mut self.markers2 {
+            m.set_loop(saved_loop);
+            is_stored = true;
+        }
+
+        if !is_stored {
+            return Err(Error::NoTagDataAvailable);
+        }
+
+        Ok(())
+    }
+
+    /// Removes the saved loop with the given index from the [`Serato Markers_`](Markers) and
+    /// [`Serato Markers2`](Markers2) tags.
+    pub fn remove_loop(&mut self, index: u8) {
+        if let Some(m) = &mut self.markers {
+            m.remove_loop(index);
+        }
+        if let Some(m) = &mut self.markers2 {
+            m.remove_loop(index);
+        }
+    }
+
+    /// Sets the track color in the [`Serato Markers_`](Markers) and [`Serato
+    /// Markers2`](Markers2) tags.
+    ///
+    /// Returns an error if none of the tags is present.
+    pub fn set_track_color(&mut self, color: Color) -> Result<(), Error> {
+        if self.markers.is_none() && self.markers2.is_none() {
+            return Err(Error::NoTagDataAvailable);
+        }
+
+        if let Some(m) = &mut self.markers {
+            m.set_track_color(color);
+        }
+        if let Some(m) = &mut self.markers2 {
+            m.set_track_color(color);
+        }
+
+        Ok(())
+    }
+
+    /// Sets the BPM lock status in the [`Serato Markers2`](Markers2) tag.
+    ///
+    /// Returns an error if the tag is not present.
+    pub fn set_bpm_locked(&mut self, is_locked: bool) -> Result<(), Error> {
+        match &mut self.markers2 {
+            Some(m) => {
+                m.set_bpm_locked(is_locked);
+                Ok(())
+            }
+            None => Err(Error::NoTagDataAvailable),
+        }
+    }
 }
 
 impl Default for TagContainer {
@@ -0,0 +1,371 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! FLAC files.
+//!
+//! Serato stores its data in the `VORBIS_COMMENT` metadata block, where the field name is the
+//! tag's [`FLAC_COMMENT`](FLACTag::FLAC_COMMENT) (e.g. `SERATO_MARKERS_V2`) and the value is the
+//! base64-encoded tag data. The FLAC metadata blocks are parsed directly, so that no additional
+//! dependencies are required.
+//!
+//! When writing, all other metadata blocks and comments are kept as they are. If the file
+//! contains `PADDING` blocks, they are resized so that the audio data stays at the same offset.
+//! Only if the padding is too small new padding of [`DEFAULT_PADDING`] bytes is added. The file is
+//! written to a temporary file in the same directory first, which then replaces the original.
+//!
+//! The FLAC format only allows a single `VORBIS_COMMENT` block. If a file contains more than one,
+//! the Serato comments are written to the first block and removed from all other blocks.
+//!
+//! ```no_run
+//! use triseratops::tag::TagContainer;
+//!
+//! let mut tags = TagContainer::read_from_flac_path("track.flac").expect("Failed to read tags!");
+//! tags.set_bpm_locked(true).expect("Failed to lock beatgrid!");
+//! tags.write_to_flac_path("track.flac").expect("Failed to write tags!");
+//! ```
+
+use crate::error::Error;
+use crate::tag::format::flac::FLACTag;
+use crate::tag::{Analysis, Autotags, Beatgrid, Markers2, Overview, RelVolAd, VidAssoc};
+use crate::tag::{TagContainer, TagFormat};
+use crate::util::{Res, write_atomically};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// The magic bytes at the start of every FLAC file.
+const MAGIC: &[u8] = b"fLaC";
+
+/// The block type of `PADDING` metadata blocks.
+const BLOCK_TYPE_PADDING: u8 = 1;
+
+/// The block type of `VORBIS_COMMENT` metadata blocks.
+const BLOCK_TYPE_VORBIS_COMMENT: u8 = 4;
+
+/// The maximum length of a metadata block (the length is stored as 24-bit integer).
+const MAX_BLOCK_LENGTH: usize = 0xFF_FFFF;
+
+/// The length of a metadata block header.
+const BLOCK_HEADER_LENGTH: usize = 4;
+
+/// Amount of padding that is added when the whole file needs to be rewritten.
+pub const DEFAULT_PADDING: usize = 8192;
+
+/// Vendor string that is used if the file doesn't have a `VORBIS_COMMENT` block yet.
+const VENDOR: &str = concat!("triseratops ", env!("CARGO_PKG_VERSION"));
+
+/// A FLAC metadata block.
+#[derive(Debug)]
+struct MetadataBlock<'a> {
+    block_type: u8,
+    data: &'a [u8],
+}
+
+/// Contents of a `VORBIS_COMMENT` metadata block.
+#[derive(Debug)]
+struct VorbisComment<'a> {
+    vendor: &'a [u8],
+    fields: Vec<&'a [u8]>,
+}
+
+fn take_metadata_block(input: &[u8]) -> Res<&[u8], (bool, MetadataBlock<'_>)> {
+    let (input, header) = nom::number::complete::u8(input)?;
+    let (input, data) = nom::multi::length_data(nom::number::complete::be_u24)(input)?;
+    let is_last = header & 0x80 != 0;
+    let block_type = header & 0x7F;
+    Ok((input, (is_last, MetadataBlock { block_type, data })))
+}
+
+/// Parses the metadata blocks of a FLAC file and returns them with the remaining audio data.
+fn parse_metadata_blocks(input: &[u8]) -> Result<(Vec<MetadataBlock<'_>>, &[u8]), Error> {
+    let (mut input, _) = nom::bytes::complete::tag(MAGIC)(input)?;
+    let mut blocks = vec![];
+    loop {
+        let (remaining, (is_last, block)) = take_metadata_block(input)?;
+        input = remaining;
+        blocks.push(block);
+        if is_last {
+            break;
+        }
+    }
+
+    Ok((blocks, input))
+}
+
+fn take_vorbis_comment(input: &[u8]) -> Res<&[u8], VorbisComment<'_>> {
+    let (input, vendor) = nom::multi::length_data(nom::number::complete::le_u32)(input)?;
+    let (input, fields) = nom::multi::length_count(
+        nom::number::complete::le_u32,
+        nom::multi::length_data(nom::number::complete::le_u32),
+    )(input)?;
+    Ok((input, VorbisComment { vendor, fields }))
+}
+
+fn parse_vorbis_comment(input: &[u8]) -> Result<VorbisComment<'_>, Error> {
+    // Some encoders append a framing bit, so trailing data is ignored.
+    let (_, comment) = take_vorbis_comment(input)?;
+    Ok(comment)
+}
+
+/// Splits a Vorbis comment field into its name and value.
+fn split_field(field: &[u8]) -> Option<(&str, &[u8])> {
+    let index = field.iter().position(|&b| b == b'=')?;
+    let name = std::str::from_utf8(&field[..index]).ok()?;
+    Some((name, &field[index + 1..]))
+}
+
+fn write_u32_le(writer: &mut impl Write, value: usize) -> Result<usize, Error> {
+    let value =
+        u32::try_from(value).map_err(|_| Error::FLACMetadataBlockTooLarge { length: value })?;
+    writer.write_all(&value.to_le_bytes())?;
+    Ok(4)
+}
+
+impl VorbisComment<'_> {
+    fn write(&self, writer: &mut impl Write) -> Result<usize, Error> {
+        let mut bytes_written = write_u32_le(writer, self.vendor.len())?;
+        writer.write_all(self.vendor)?;
+        bytes_written += self.vendor.len();
+        bytes_written += write_u32_le(writer, self.fields.len())?;
+        for field in &self.fields {
+            bytes_written += write_u32_le(writer, field.len())?;
+            writer.write_all(field)?;
+            bytes_written += field.len();
+        }
+        Ok(bytes_written)
+    }
+}
+
+fn write_metadata_block(
+    writer: &mut impl Write,
+    block_type: u8,
+    is_last: bool,
+    data: &[u8],
+) -> Result<usize, Error> {
+    if data.len() > MAX_BLOCK_LENGTH {
+        return Err(Error::FLACMetadataBlockTooLarge { length: data.len() });
+    }
+    let header = if is_last {
+        block_type | 0x80
+    } else {
+        block_type
+    };
+    writer.write_all(&[header])?;
+    writer.write_all(&(data.len() as u32).to_be_bytes()[1..])?;
+    writer.write_all(data)?;
+    Ok(BLOCK_HEADER_LENGTH + data.len())
+}
+
+impl TagContainer {
+    /// Parse a single Vorbis comment from a FLAC file.
+    ///
+    /// The comment name is compared case-insensitively. Comments that don't belong to Serato or
+    /// that contain tags that are not supported by the tag container are ignored.
+    pub fn parse_flac_comment(&mut self, name: &str, value: &[u8]) -> Result<(), Error> {
+        let matches = |comment: &str| name.eq_ignore_ascii_case(comment);
+        if matches(Analysis::FLAC_COMMENT) {
+            self.parse_analysis(value, TagFormat::FLAC)
+        } else if matches(Autotags::FLAC_COMMENT) {
+            self.parse_autotags(value, TagFormat::FLAC)
+        } else if matches(Beatgrid::FLAC_COMMENT) {
+            self.parse_beatgrid(value, TagFormat::FLAC)
+        } else if matches(Markers2::FLAC_COMMENT) {
+            self.parse_markers2(value, TagFormat::FLAC)
+        } else if matches(Overview::FLAC_COMMENT) {
+            self.parse_overview(value, TagFormat::FLAC)
+        } else if matches(RelVolAd::FLAC_COMMENT) {
+            self.parse_relvolad(value, TagFormat::FLAC)
+        } else if matches(VidAssoc::FLAC_COMMENT) {
+            self.parse_vidassoc(value, TagFormat::FLAC)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Serialize all available Serato tags as name/value pairs for Vorbis comments.
+    pub fn write_flac_comments(&self) -> Result<Vec<(&'static str, Vec<u8>)>, Error> {
+        type WriteFn = fn(&TagContainer, &mut io::Cursor<Vec<u8>>) -> Result<usize, Error>;
+        let writers: [(&'static str, WriteFn); 7] = [
+            (Analysis::FLAC_COMMENT, |tags, w| {
+                tags.write_analysis(w, TagFormat::FLAC)
+            }),
+            (Autotags::FLAC_COMMENT, |tags, w| {
+                tags.write_autotags(w, TagFormat::FLAC)
+            }),
+            (Beatgrid::FLAC_COMMENT, |tags, w| {
+                tags.write_beatgrid(w, TagFormat::FLAC)
+            }),
+            (Markers2::FLAC_COMMENT, |tags, w| {
+                tags.write_markers2(w, TagFormat::FLAC)
+            }),
+            (Overview::FLAC_COMMENT, |tags, w| {
+                tags.write_overview(w, TagFormat::FLAC)
+            }),
+            (RelVolAd::FLAC_COMMENT, |tags, w| {
+                tags.write_relvolad(w, TagFormat::FLAC)
+            }),
+            (VidAssoc::FLAC_COMMENT, |tags, w| {
+                tags.write_vidassoc(w, TagFormat::FLAC)
+            }),
+        ];
+
+        let mut comments = vec![];
+        for (name, write) in writers {
+            let mut buffer = io::Cursor::new(vec![]);
+            match write(self, &mut buffer) {
+                Ok(_) => comments.push((name, buffer.into_inner())),
+                Err(Error::NoTagDataAvailable) => (),
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(comments)
+    }
+
+    /// Read the Serato tags from the `VORBIS_COMMENT` block of the FLAC file at the given path.
+    ///
+    /// If the file does not contain a `VORBIS_COMMENT` block, an empty container is returned.
+    pub fn read_from_flac_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let data = fs::read(path)?;
+        let (blocks, _) = parse_metadata_blocks(&data)?;
+        let mut container = Self::new();
+        for block in blocks
+            .iter()
+            .filter(|block| block.block_type == BLOCK_TYPE_VORBIS_COMMENT)
+        {
+            let comment = parse_vorbis_comment(block.data)?;
+            for (name, value) in comment.fields.into_iter().filter_map(split_field) {
+                container.parse_flac_comment(name, value)?;
+            }
+        }
+
+        Ok(container)
+    }
+
+    /// Write all available Serato tags to the `VORBIS_COMMENT` block of the FLAC file at the given
+    /// path.
+    ///
+    /// Existing Serato comments are replaced in place, new ones are appended. If the file does not
+    /// contain a `VORBIS_COMMENT` block yet, a new one is inserted after the `STREAMINFO` block.
+    ///
+    /// The file is replaced atomically, i.e. it's either fully updated or left unchanged.
+    pub fn write_to_flac_path(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let data = fs::read(path)?;
+        let (blocks, audio_data) = parse_metadata_blocks(&data)?;
+        let old_metadata_length = data.len() - audio_data.len();
+
+        let serato_comments: Vec<(&str, Vec<u8>)> = self
+            .write_flac_comments()?
+            .into_iter()
+            .map(|(name, value)| {
+                let mut field = name.as_bytes().to_vec();
+                field.push(b'=');
+                field.extend(value);
+                (name, field)
+            })
+            .collect();
+
+        // Build the new list of non-padding metadata blocks.
+        let mut new_blocks: Vec<(u8, Vec<u8>)> = vec![];
+        let mut vorbis_comment_written = false;
+        for block in &blocks {
+            match block.block_type {
+                BLOCK_TYPE_PADDING => (),
+                BLOCK_TYPE_VORBIS_COMMENT => {
+                    let comment = parse_vorbis_comment(block.data)?;
+                    let data =
+                        replace_comments(comment, &serato_comments, !vorbis_comment_written)?;
+                    new_blocks.push((BLOCK_TYPE_VORBIS_COMMENT, data));
+                    vorbis_comment_written = true;
+                }
+                block_type => new_blocks.push((block_type, block.data.to_vec())),
+            }
+        }
+        if !vorbis_comment_written {
+            let comment = VorbisComment {
+                vendor: VENDOR.as_bytes(),
+                fields: vec![],
+            };
+            let data = replace_comments(comment, &serato_comments, true)?;
+            // The STREAMINFO block always needs to be the first block.
+            let index = new_blocks.len().min(1);
+            new_blocks.insert(index, (BLOCK_TYPE_VORBIS_COMMENT, data));
+        }
+
+        // Reuse the existing space if possible, so that the audio data stays at the same offset.
+        let new_metadata_length = MAGIC.len()
+            + new_blocks
+                .iter()
+                .map(|(_, data)| BLOCK_HEADER_LENGTH + data.len())
+                .sum::<usize>();
+        let available = old_metadata_length.checked_sub(new_metadata_length);
+        let padding = match available {
+            Some(0) => None,
+            Some(length) if length >= BLOCK_HEADER_LENGTH => Some(length - BLOCK_HEADER_LENGTH),
+            _ => Some(DEFAULT_PADDING),
+        };
+
+        let mut metadata = io::Cursor::new(MAGIC.to_vec());
+        metadata.set_position(MAGIC.len() as u64);
+        let last_index = new_blocks.len() - 1;
+        for (i, (block_type, data)) in new_blocks.iter().enumerate() {
+            let is_last = padding.is_none() && i == last_index;
+            write_metadata_block(&mut metadata, *block_type, is_last, data)?;
+        }
+        if let Some(length) = padding {
+            let padding_data = vec![0; length];
+            write_metadata_block(&mut metadata, BLOCK_TYPE_PADDING, true, &padding_data)?;
+        }
+
+        write_atomically(path, &[metadata.get_ref(), audio_data])?;
+
+        Ok(())
+    }
+}
+
+/// Replaces the Serato comments in the given Vorbis comment and returns the serialized block.
+///
+/// If `primary` is `false`, the Serato comments are only removed from the block.
+fn replace_comments<'a>(
+    mut comment: VorbisComment<'a>,
+    serato_comments: &'a [(&str, Vec<u8>)],
+    primary: bool,
+) -> Result<Vec<u8>, Error> {
+    let mut written = vec![!primary; serato_comments.len()];
+    let mut fields = Vec::with_capacity(comment.fields.len());
+    for field in comment.fields {
+        let name = split_field(field).map(|(name, _)| name);
+        let index = name.and_then(|name| {
+            serato_comments
+                .iter()
+                .position(|(comment, _)| name.eq_ignore_ascii_case(comment))
+        });
+        match index {
+            // Replace the first occurrence and drop all duplicates.
+            Some(i) if !written[i] => {
+                fields.push(&serato_comments[i].1[..]);
+                written[i] = true;
+            }
+            Some(_) => (),
+            None => fields.push(field),
+        }
+    }
+    fields.extend(
+        serato_comments
+            .iter()
+            .zip(written)
+            .filter(|(_, written)| !written)
+            .map(|((_, field), _)| &field[..]),
+    );
+    comment.fields = fields;
+
+    let mut buffer = io::Cursor::new(vec![]);
+    comment.write(&mut buffer)?;
+    Ok(buffer.into_inner())
+}
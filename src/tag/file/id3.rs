@@ -0,0 +1,148 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Files with ID3v2 tags (e.g. MP3 or AIFF).
+//!
+//! Serato stores its data in `GEOB` (General Encapsulated Object) frames, where the frame
+//! description is the tag name (e.g. `Serato Markers2`). Reading and writing is done using the
+//! [`id3`](https://lib.rs/crates/id3) crate.
+//!
+//! ```no_run
+//! use triseratops::tag::TagContainer;
+//!
+//! let mut tags = TagContainer::read_from_id3_path("track.mp3").expect("Failed to read tags!");
+//! tags.set_bpm_locked(true).expect("Failed to lock beatgrid!");
+//! tags.write_to_id3_path("track.mp3").expect("Failed to write tags!");
+//! ```
+
+use crate::error::Error;
+use crate::tag::format::id3::ID3Tag;
+use crate::tag::{Analysis, Autotags, Beatgrid, Markers, Markers2, Overview};
+use crate::tag::{TagContainer, TagFormat};
+use ::id3::{Encoding, Frame, Tag, TagLike, frame::EncapsulatedObject};
+use std::io;
+use std::path::Path;
+
+/// MIME type that Serato uses for its `GEOB` frames.
+const GEOB_MIME_TYPE: &str = "application/octet-stream";
+
+impl TagContainer {
+    /// Parse all Serato `GEOB` frames from the given ID3 tag.
+    ///
+    /// Frames that don't belong to Serato or that contain tags that are not supported by the tag
+    /// container are ignored.
+    pub fn parse_id3_tag(&mut self, tag: &Tag) -> Result<(), Error> {
+        for geob in tag.encapsulated_objects() {
+            let data = &geob.data[..];
+            match geob.description.as_str() {
+                Analysis::ID3_TAG => self.parse_analysis(data, TagFormat::ID3)?,
+                Autotags::ID3_TAG => self.parse_autotags(data, TagFormat::ID3)?,
+                Beatgrid::ID3_TAG => self.parse_beatgrid(data, TagFormat::ID3)?,
+                Markers::ID3_TAG => self.parse_markers(data, TagFormat::ID3)?,
+                Markers2::ID3_TAG => self.parse_markers2(data, TagFormat::ID3)?,
+                Overview::ID3_TAG => self.parse_overview(data, TagFormat::ID3)?,
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read the Serato tags from the ID3 tag of the file at the given path.
+    ///
+    /// If the file does not contain an ID3 tag, an empty container is returned.
+    pub fn read_from_id3_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let mut container = Self::new();
+        if let Some(tag) = ::id3::no_tag_ok(Tag::read_from_path(path))? {
+            container.parse_id3_tag(&tag)?;
+        }
+
+        Ok(container)
+    }
+
+    /// Write all available Serato tags into `GEOB` frames of the given ID3 tag.
+    ///
+    /// Existing Serato frames are replaced and keep their text encoding. All other frames are left
+    /// untouched.
+    pub fn write_id3_tag(&self, tag: &mut Tag) -> Result<(), Error> {
+        write_geob(tag, Analysis::ID3_TAG, |w| {
+            self.write_analysis(w, TagFormat::ID3)
+        })?;
+        write_geob(tag, Autotags::ID3_TAG, |w| {
+            self.write_autotags(w, TagFormat::ID3)
+        })?;
+        write_geob(tag, Beatgrid::ID3_TAG, |w| {
+            self.write_beatgrid(w, TagFormat::ID3)
+        })?;
+        write_geob(tag, Markers::ID3_TAG, |w| {
+            self.write_markers(w, TagFormat::ID3)
+        })?;
+        write_geob(tag, Markers2::ID3_TAG, |w| {
+            self.write_markers2(w, TagFormat::ID3)
+        })?;
+        write_geob(tag, Overview::ID3_TAG, |w| {
+            self.write_overview(w, TagFormat::ID3)
+        })?;
+
+        Ok(())
+    }
+
+    /// Write all available Serato tags to the ID3 tag of the file at the given path.
+    ///
+    /// If the file does not contain an ID3 tag yet, a new ID3v2.4 tag is created. Otherwise, the
+    /// existing tag version is kept.
+    pub fn write_to_id3_path(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let mut tag = ::id3::no_tag_ok(Tag::read_from_path(path))?.unwrap_or_default();
+        self.write_id3_tag(&mut tag)?;
+        let version = tag.version();
+        tag.write_to_path(path, version)?;
+
+        Ok(())
+    }
+}
+
+/// Replace (or add) the `GEOB` frame with the given description with the written data.
+///
+/// If no tag data is available, the tag is left untouched.
+fn write_geob(
+    tag: &mut Tag,
+    description: &str,
+    write: impl FnOnce(&mut io::Cursor<Vec<u8>>) -> Result<usize, Error>,
+) -> Result<(), Error> {
+    let mut buffer = io::Cursor::new(vec![]);
+    match write(&mut buffer) {
+        Ok(_) => (),
+        Err(Error::NoTagDataAvailable) => return Ok(()),
+        Err(err) => return Err(err),
+    }
+
+    // Serato writes Latin-1 encoded `GEOB` frames, but existing frames may use a different
+    // encoding that we should preserve.
+    let encoding = tag
+        .frames()
+        .find(|frame| {
+            frame
+                .content()
+                .encapsulated_object()
+                .is_some_and(|geob| geob.description == description)
+        })
+        .and_then(Frame::encoding)
+        .unwrap_or(Encoding::Latin1);
+    let frame = Frame::from(EncapsulatedObject {
+        mime_type: GEOB_MIME_TYPE.to_owned(),
+        filename: String::new(),
+        description: description.to_owned(),
+        data: buffer.into_inner(),
+    })
+    .set_encoding(Some(encoding));
+    tag.remove_encapsulated_object(Some(description), None, None, None);
+    tag.add_frame(frame);
+
+    Ok(())
+}
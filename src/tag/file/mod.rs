@@ -0,0 +1,24 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Reading and writing Serato tags from/to music files directly.
+//!
+//! Each file type is gated behind a cargo feature:
+//!
+//! | Feature | File Types               | Module
+//! | ------- | ------------------------ | ------------------
+//! | `id3`   | MP3, AIFF (ID3v2 `GEOB`) | `tag::file::id3`
+//! | `flac`  | FLAC (`VORBIS_COMMENT`)  | `tag::file::flac`
+//! | `mp4`   | MP4, M4A (`----` atoms)  | `tag::file::mp4`
+
+#[cfg(feature = "flac")]
+pub mod flac;
+#[cfg(feature = "id3")]
+pub mod id3;
+#[cfg(feature = "mp4")]
+pub mod mp4;
@@ -0,0 +1,537 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! MP4/M4A files.
+//!
+//! Serato stores its data in freeform (`----`) atoms in `moov/udta/meta/ilst`, where the `mean`
+//! child atom is [`com.serato.dj`](MP4Tag::MP4_ATOM_FREEFORM_MEAN), the `name` child atom is the
+//! tag's [`MP4_ATOM_FREEFORM_NAME`](MP4Tag::MP4_ATOM_FREEFORM_NAME) (e.g. `markersv2`) and the
+//! `data` child atom contains the base64-encoded tag data. The atoms are parsed directly, so that
+//! no additional dependencies are required.
+//!
+//! When writing, all other atoms are kept as they are. If the `ilst` atom is followed by a `free`
+//! atom, that space is used so that the media data stays at the same offset. Otherwise, the chunk
+//! offsets (`stco`/`co64`) of the tracks are adjusted if the media data is located after the
+//! `moov` atom. The file is written to a temporary file in the same directory first, which then
+//! replaces the original. Fragmented MP4 files (with `moof` or `mvex` atoms) are not supported.
+//!
+//! ```no_run
+//! use triseratops::tag::TagContainer;
+//!
+//! let mut tags = TagContainer::read_from_mp4_path("track.m4a").expect("Failed to read tags!");
+//! tags.set_bpm_locked(true).expect("Failed to lock beatgrid!");
+//! tags.write_to_mp4_path("track.m4a").expect("Failed to write tags!");
+//! ```
+
+use crate::error::Error;
+use crate::tag::format::mp4::MP4Tag;
+use crate::tag::{Analysis, Autotags, Beatgrid, Markers, Markers2, Overview, RelVolAd, VidAssoc};
+use crate::tag::{TagContainer, TagFormat};
+use crate::util::{Res, write_atomically};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The `mean` of freeform atoms written by Serato.
+const FREEFORM_MEAN: &str = Markers2::MP4_ATOM_FREEFORM_MEAN;
+
+/// Well-known type of `data` atoms with UTF-8 content.
+const DATA_TYPE_UTF8: u32 = 1;
+
+/// The length of an atom header (without the 64-bit extended size).
+const ATOM_HEADER_LENGTH: usize = 8;
+
+/// An MP4 atom (also known as box).
+#[derive(Debug)]
+struct Atom<'a> {
+    /// The four character code of the atom.
+    kind: &'a [u8],
+    /// The position of the atom relative to the start of its parent's content.
+    offset: usize,
+    /// The length of the atom header.
+    header_length: usize,
+    /// The complete atom, including the header.
+    raw: &'a [u8],
+    /// The content of the atom (without the header).
+    data: &'a [u8],
+}
+
+/// A list of atoms and the trailing data that couldn't be parsed as atom.
+///
+/// Some files contain a few null bytes at the end of the `udta` atom which need to be preserved.
+#[derive(Debug)]
+struct Atoms<'a> {
+    atoms: Vec<Atom<'a>>,
+    trailing: &'a [u8],
+}
+
+fn take_atom(input: &[u8]) -> Res<&[u8], (&[u8], usize, usize)> {
+    let (rest, size) = nom::number::complete::be_u32(input)?;
+    let (rest, kind) = nom::bytes::complete::take(4usize)(rest)?;
+    let (rest, size, header_length) = match size {
+        0 => (rest, input.len() as u64, ATOM_HEADER_LENGTH),
+        1 => {
+            let (rest, size) = nom::number::complete::be_u64(rest)?;
+            (rest, size, ATOM_HEADER_LENGTH + 8)
+        }
+        size => (rest, u64::from(size), ATOM_HEADER_LENGTH),
+    };
+    let content_length = usize::try_from(size)
+        .ok()
+        .and_then(|size| size.checked_sub(header_length))
+        .ok_or_else(|| {
+            nom::Err::Error(nom::error::ParseError::from_error_kind(
+                input,
+                nom::error::ErrorKind::LengthValue,
+            ))
+        })?;
+    let (rest, _) = nom::bytes::complete::take(content_length)(rest)?;
+    Ok((rest, (kind, header_length, header_length + content_length)))
+}
+
+fn parse_atoms(input: &[u8]) -> Atoms<'_> {
+    let mut atoms = vec![];
+    let mut offset = 0;
+    let mut rest = input;
+    while let Ok((remaining, (kind, header_length, length))) = take_atom(rest) {
+        atoms.push(Atom {
+            kind,
+            offset,
+            header_length,
+            raw: &rest[..length],
+            data: &rest[header_length..length],
+        });
+        offset += length;
+        rest = remaining;
+    }
+
+    Atoms {
+        atoms,
+        trailing: rest,
+    }
+}
+
+impl<'a> Atoms<'a> {
+    fn position(&self, kind: &[u8]) -> Option<usize> {
+        self.atoms.iter().position(|atom| atom.kind == kind)
+    }
+
+    fn find(&self, kind: &[u8]) -> Option<&Atom<'a>> {
+        self.atoms.iter().find(|atom| atom.kind == kind)
+    }
+}
+
+/// Splits the version and flags from the content of full atoms (e.g. `meta`).
+fn take_version_and_flags(input: &[u8]) -> Res<&[u8], u32> {
+    nom::number::complete::be_u32(input)
+}
+
+/// A freeform (`----`) atom.
+#[derive(Debug)]
+struct FreeformAtom<'a> {
+    mean: &'a str,
+    name: &'a str,
+    data_type: u32,
+    data: &'a [u8],
+}
+
+fn parse_freeform_string<'a>(atom: &Atom<'a>) -> Option<&'a str> {
+    let (input, _) = take_version_and_flags(atom.data).ok()?;
+    std::str::from_utf8(input).ok()
+}
+
+fn parse_freeform_atom<'a>(atom: &Atom<'a>) -> Option<FreeformAtom<'a>> {
+    if atom.kind != b"----" {
+        return None;
+    }
+    let children = parse_atoms(atom.data);
+    let mean = parse_freeform_string(children.find(b"mean")?)?;
+    let name = parse_freeform_string(children.find(b"name")?)?;
+    let (input, data_type) = take_version_and_flags(children.find(b"data")?.data).ok()?;
+    let (data, _locale) = nom::number::complete::be_u32::<_, ()>(input).ok()?;
+    Some(FreeformAtom {
+        mean,
+        name,
+        data_type,
+        data,
+    })
+}
+
+fn write_atom(writer: &mut Vec<u8>, kind: &[u8], content: &[u8]) -> Result<(), Error> {
+    let length = ATOM_HEADER_LENGTH + content.len();
+    let size = u32::try_from(length).map_err(|_| Error::MP4AtomTooLarge { length })?;
+    writer.extend_from_slice(&size.to_be_bytes());
+    writer.extend_from_slice(kind);
+    writer.extend_from_slice(content);
+    Ok(())
+}
+
+fn write_freeform_atom(
+    writer: &mut Vec<u8>,
+    name: &str,
+    data_type: u32,
+    data: &[u8],
+) -> Result<(), Error> {
+    let mut content = vec![];
+    let mut mean_content = 0u32.to_be_bytes().to_vec();
+    mean_content.extend_from_slice(FREEFORM_MEAN.as_bytes());
+    write_atom(&mut content, b"mean", &mean_content)?;
+    let mut name_content = 0u32.to_be_bytes().to_vec();
+    name_content.extend_from_slice(name.as_bytes());
+    write_atom(&mut content, b"name", &name_content)?;
+    let mut data_content = data_type.to_be_bytes().to_vec();
+    data_content.extend_from_slice(&0u32.to_be_bytes());
+    data_content.extend_from_slice(data);
+    write_atom(&mut content, b"data", &data_content)?;
+    write_atom(writer, b"----", &content)
+}
+
+/// Returns the content of the `ilst` atom with the Serato atoms replaced.
+///
+/// Existing atoms are replaced in place (keeping their data type), new ones are appended.
+fn replace_freeform_atoms(ilst: &[u8], serato_atoms: &[(&str, Vec<u8>)]) -> Result<Vec<u8>, Error> {
+    let children = parse_atoms(ilst);
+    let mut written = vec![false; serato_atoms.len()];
+    let mut content = vec![];
+    for atom in &children.atoms {
+        let freeform = parse_freeform_atom(atom).filter(|freeform| freeform.mean == FREEFORM_MEAN);
+        let index = freeform.as_ref().and_then(|freeform| {
+            serato_atoms
+                .iter()
+                .position(|(name, _)| *name == freeform.name)
+        });
+        match (index, freeform) {
+            // Replace the first occurrence and drop all duplicates.
+            (Some(i), Some(freeform)) if !written[i] => {
+                let (name, data) = &serato_atoms[i];
+                write_freeform_atom(&mut content, name, freeform.data_type, data)?;
+                written[i] = true;
+            }
+            (Some(_), _) => (),
+            (None, _) => content.extend_from_slice(atom.raw),
+        }
+    }
+    for ((name, data), _) in serato_atoms
+        .iter()
+        .zip(written)
+        .filter(|(_, written)| !written)
+    {
+        write_freeform_atom(&mut content, name, DATA_TYPE_UTF8, data)?;
+    }
+    content.extend_from_slice(children.trailing);
+
+    Ok(content)
+}
+
+/// Returns the content of the `meta` atom with the `ilst` atom replaced.
+///
+/// If the `ilst` atom is followed by a `free` atom, it is resized to keep the size of the `meta`
+/// atom the same (if possible).
+fn rebuild_meta(meta: &[u8], serato_atoms: &[(&str, Vec<u8>)]) -> Result<Vec<u8>, Error> {
+    let (children_data, _) = take_version_and_flags(meta)?;
+    let version_and_flags = &meta[..meta.len() - children_data.len()];
+    let mut children = parse_atoms(children_data);
+    if children.position(b"hdlr").is_none() && children.position(b"ilst").is_none() {
+        // The `meta` atom has just been created, so we need to add the handler.
+        let mut hdlr = vec![];
+        write_atom(&mut hdlr, b"hdlr", HDLR_CONTENT)?;
+        let mut content = version_and_flags.to_vec();
+        content.extend(hdlr);
+        let mut ilst = vec![];
+        write_atom(
+            &mut ilst,
+            b"ilst",
+            &replace_freeform_atoms(&[], serato_atoms)?,
+        )?;
+        content.extend(ilst);
+        return Ok(content);
+    }
+
+    let mut ilst = vec![];
+    let index = children.position(b"ilst");
+    let old_ilst = index.map_or(&[][..], |i| children.atoms[i].data);
+    write_atom(
+        &mut ilst,
+        b"ilst",
+        &replace_freeform_atoms(old_ilst, serato_atoms)?,
+    )?;
+
+    let mut content = version_and_flags.to_vec();
+    let Some(index) = index else {
+        for atom in &children.atoms {
+            content.extend_from_slice(atom.raw);
+        }
+        content.extend(ilst);
+        content.extend_from_slice(children.trailing);
+        return Ok(content);
+    };
+
+    // Try to reuse the space of the following `free` atom.
+    let mut free = None;
+    if children
+        .atoms
+        .get(index + 1)
+        .is_some_and(|atom| atom.kind == b"free")
+    {
+        let free_atom = children.atoms.remove(index + 1);
+        let available = children.atoms[index].raw.len() + free_atom.raw.len();
+        free = match available.checked_sub(ilst.len()) {
+            Some(0) => Some(vec![]),
+            Some(length) if length >= ATOM_HEADER_LENGTH => {
+                let mut atom = vec![];
+                write_atom(&mut atom, b"free", &vec![0; length - ATOM_HEADER_LENGTH])?;
+                Some(atom)
+            }
+            _ => Some(free_atom.raw.to_vec()),
+        };
+    }
+
+    for (i, atom) in children.atoms.iter().enumerate() {
+        if i == index {
+            content.extend_from_slice(&ilst);
+            if let Some(free) = &free {
+                content.extend_from_slice(free);
+            }
+        } else {
+            content.extend_from_slice(atom.raw);
+        }
+    }
+    content.extend_from_slice(children.trailing);
+
+    Ok(content)
+}
+
+/// Content of the `hdlr` atom that is added to newly created `meta` atoms.
+const HDLR_CONTENT: &[u8] = &[
+    0x00, 0x00, 0x00, 0x00, // Version and flags
+    0x00, 0x00, 0x00, 0x00, // Pre-defined
+    b'm', b'd', b'i', b'r', // Handler type
+    b'a', b'p', b'p', b'l', // Reserved (Manufacturer)
+    0x00, 0x00, 0x00, 0x00, // Reserved
+    0x00, 0x00, 0x00, 0x00, // Reserved
+    0x00, // Name
+];
+
+/// Returns the content of the container atom with the child atom of the given kind replaced.
+///
+/// If the container does not contain such an atom, it is created from the `empty` content.
+fn rebuild_child(
+    container: &[u8],
+    kind: &[u8],
+    empty: &[u8],
+    rebuild: impl FnOnce(&[u8]) -> Result<Vec<u8>, Error>,
+) -> Result<Vec<u8>, Error> {
+    let children = parse_atoms(container);
+    let mut content = vec![];
+    let mut rebuild = Some(rebuild);
+    for atom in &children.atoms {
+        match rebuild.take_if(|_| atom.kind == kind) {
+            Some(rebuild) => write_atom(&mut content, kind, &rebuild(atom.data)?)?,
+            None => content.extend_from_slice(atom.raw),
+        }
+    }
+    if let Some(rebuild) = rebuild {
+        write_atom(&mut content, kind, &rebuild(empty)?)?;
+    }
+    content.extend_from_slice(children.trailing);
+
+    Ok(content)
+}
+
+/// Collects the positions of the chunk offset tables in the given `moov` content.
+///
+/// Returns the position of the table content (after the version and flags) and whether it's a
+/// `co64` table.
+fn find_chunk_offset_tables(input: &[u8], position: usize, tables: &mut Vec<(usize, bool)>) {
+    for atom in parse_atoms(input).atoms {
+        let data_position = position + atom.offset + atom.header_length;
+        match atom.kind {
+            b"trak" | b"mdia" | b"minf" | b"stbl" => {
+                find_chunk_offset_tables(atom.data, data_position, tables);
+            }
+            b"stco" => tables.push((data_position + 4, false)),
+            b"co64" => tables.push((data_position + 4, true)),
+            _ => (),
+        }
+    }
+}
+
+/// Moves all chunk offsets greater or equal to `threshold` by `delta`.
+fn adjust_chunk_offsets(moov: &mut [u8], threshold: u64, delta: i64) -> Result<(), Error> {
+    let mut tables = vec![];
+    find_chunk_offset_tables(&moov[ATOM_HEADER_LENGTH..], ATOM_HEADER_LENGTH, &mut tables);
+    for (position, is_co64) in tables {
+        let entry_size = if is_co64 { 8 } else { 4 };
+        let (_, count) = nom::number::complete::be_u32(&moov[position..])?;
+        for i in 0..count as usize {
+            let start = position + 4 + i * entry_size;
+            let entry = moov
+                .get_mut(start..start + entry_size)
+                .ok_or(Error::ParseError)?;
+            let offset = if is_co64 {
+                u64::from_be_bytes(entry.try_into().map_err(|_| Error::ParseError)?)
+            } else {
+                u64::from(u32::from_be_bytes(
+                    entry.try_into().map_err(|_| Error::ParseError)?,
+                ))
+            };
+            if offset < threshold {
+                continue;
+            }
+            let offset = offset.checked_add_signed(delta).ok_or(Error::ParseError)?;
+            if is_co64 {
+                entry.copy_from_slice(&offset.to_be_bytes());
+            } else {
+                let offset = u32::try_from(offset).map_err(|_| Error::ParseError)?;
+                entry.copy_from_slice(&offset.to_be_bytes());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl TagContainer {
+    /// Parse the data of a single freeform atom from an MP4 file.
+    ///
+    /// Atoms that don't belong to Serato or that contain tags that are not supported by the tag
+    /// container are ignored.
+    pub fn parse_mp4_freeform_atom(
+        &mut self,
+        mean: &str,
+        name: &str,
+        data: &[u8],
+    ) -> Result<(), Error> {
+        if mean != FREEFORM_MEAN {
+            return Ok(());
+        }
+
+        match name {
+            Analysis::MP4_ATOM_FREEFORM_NAME => self.parse_analysis(data, TagFormat::MP4),
+            Autotags::MP4_ATOM_FREEFORM_NAME => self.parse_autotags(data, TagFormat::MP4),
+            Beatgrid::MP4_ATOM_FREEFORM_NAME => self.parse_beatgrid(data, TagFormat::MP4),
+            Markers::MP4_ATOM_FREEFORM_NAME => self.parse_markers(data, TagFormat::MP4),
+            Markers2::MP4_ATOM_FREEFORM_NAME => self.parse_markers2(data, TagFormat::MP4),
+            Overview::MP4_ATOM_FREEFORM_NAME => self.parse_overview(data, TagFormat::MP4),
+            RelVolAd::MP4_ATOM_FREEFORM_NAME => self.parse_relvolad(data, TagFormat::MP4),
+            VidAssoc::MP4_ATOM_FREEFORM_NAME => self.parse_vidassoc(data, TagFormat::MP4),
+            _ => Ok(()),
+        }
+    }
+
+    /// Serialize all available Serato tags as name/data pairs for freeform atoms.
+    pub fn write_mp4_freeform_atoms(&self) -> Result<Vec<(&'static str, Vec<u8>)>, Error> {
+        type WriteFn = fn(&TagContainer, &mut io::Cursor<Vec<u8>>) -> Result<usize, Error>;
+        let writers: [(&'static str, WriteFn); 8] = [
+            (Analysis::MP4_ATOM_FREEFORM_NAME, |tags, w| {
+                tags.write_analysis(w, TagFormat::MP4)
+            }),
+            (Autotags::MP4_ATOM_FREEFORM_NAME, |tags, w| {
+                tags.write_autotags(w, TagFormat::MP4)
+            }),
+            (Beatgrid::MP4_ATOM_FREEFORM_NAME, |tags, w| {
+                tags.write_beatgrid(w, TagFormat::MP4)
+            }),
+            (Markers::MP4_ATOM_FREEFORM_NAME, |tags, w| {
+                tags.write_markers(w, TagFormat::MP4)
+            }),
+            (Markers2::MP4_ATOM_FREEFORM_NAME, |tags, w| {
+                tags.write_markers2(w, TagFormat::MP4)
+            }),
+            (Overview::MP4_ATOM_FREEFORM_NAME, |tags, w| {
+                tags.write_overview(w, TagFormat::MP4)
+            }),
+            (RelVolAd::MP4_ATOM_FREEFORM_NAME, |tags, w| {
+                tags.write_relvolad(w, TagFormat::MP4)
+            }),
+            (VidAssoc::MP4_ATOM_FREEFORM_NAME, |tags, w| {
+                tags.write_vidassoc(w, TagFormat::MP4)
+            }),
+        ];
+
+        let mut atoms = vec![];
+        for (name, write) in writers {
+            let mut buffer = io::Cursor::new(vec![]);
+            match write(self, &mut buffer) {
+                Ok(_) => atoms.push((name, buffer.into_inner())),
+                Err(Error::NoTagDataAvailable) => (),
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(atoms)
+    }
+
+    /// Read the Serato tags from the freeform atoms of the MP4 file at the given path.
+    ///
+    /// If the file does not contain an `ilst` atom, an empty container is returned.
+    pub fn read_from_mp4_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let data = fs::read(path)?;
+        let file = parse_atoms(&data);
+        let moov = file
+            .find(b"moov")
+            .ok_or(Error::MP4AtomNotFound { name: "moov" })?;
+
+        let mut container = Self::new();
+        let Some(meta) = parse_atoms(moov.data)
+            .find(b"udta")
+            .and_then(|udta| parse_atoms(udta.data).find(b"meta").map(|meta| meta.data))
+        else {
+            return Ok(container);
+        };
+        let (meta, _) = take_version_and_flags(meta)?;
+        let Some(ilst) = parse_atoms(meta).find(b"ilst").map(|ilst| ilst.data) else {
+            return Ok(container);
+        };
+        for atom in parse_atoms(ilst).atoms {
+            if let Some(freeform) = parse_freeform_atom(&atom) {
+                container.parse_mp4_freeform_atom(freeform.mean, freeform.name, freeform.data)?;
+            }
+        }
+
+        Ok(container)
+    }
+
+    /// Write all available Serato tags to freeform atoms of the MP4 file at the given path.
+    ///
+    /// Existing Serato atoms are replaced in place, new ones are appended to the `ilst` atom. If
+    /// the file does not contain the `udta`, `meta` or `ilst` atoms yet, they are created.
+    ///
+    /// The file is replaced atomically, i.e. it's either fully updated or left unchanged. Writing
+    /// to fragmented MP4 files fails with [`Error::MP4FragmentedFile`].
+    pub fn write_to_mp4_path(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let data = fs::read(path)?;
+        let file = parse_atoms(&data);
+        let moov = file
+            .find(b"moov")
+            .ok_or(Error::MP4AtomNotFound { name: "moov" })?;
+        if file.find(b"moof").is_some() || parse_atoms(moov.data).find(b"mvex").is_some() {
+            return Err(Error::MP4FragmentedFile);
+        }
+        let serato_atoms = self.write_mp4_freeform_atoms()?;
+
+        let moov_content = rebuild_child(moov.data, b"udta", &[], |udta| {
+            rebuild_child(udta, b"meta", &[0; 4], |meta| {
+                rebuild_meta(meta, &serato_atoms)
+            })
+        })?;
+        let mut new_moov = vec![];
+        write_atom(&mut new_moov, b"moov", &moov_content)?;
+
+        let moov_start = moov.offset;
+        let moov_end = moov_start + moov.raw.len();
+        let delta = new_moov.len() as i64 - moov.raw.len() as i64;
+        if delta != 0 {
+            adjust_chunk_offsets(&mut new_moov, moov_end as u64, delta)?;
+        }
+        write_atomically(path, &[&data[..moov_start], &new_moov, &data[moov_end..]])?;
+
+        Ok(())
+    }
+}
@@ -13,7 +13,7 @@ use crate::error::Error;
 use std::io;
 
 pub trait OggTag: Tag {
-    /// Name of the `MP4_ATOM` that this data is stored in.
+    /// Name of the Vorbis comment that this data is stored in.
     const OGG_COMMENT: &'static str;
 
     fn parse_ogg(input: &[u8]) -> Result<Self, Error>;
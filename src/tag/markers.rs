@@ -14,7 +14,7 @@
 
 use super::color::Color;
 use super::format::{Tag, enveloped, id3, mp4};
-use super::generic::{Position, Version};
+use super::generic::{Cue, Loop, Position, Version};
 use super::serato32;
 use super::util::{take_color, take_version, write_color, write_version};
 use crate::error::Error;
@@ -78,6 +78,34 @@ pub struct Markers {
 }
 
 impl Markers {
+    /// The tag version written by Serato DJ.
+    pub const VERSION: Version = Version { major: 2, minor: 5 };
+
+    /// The number of cue entries in the tag.
+    pub const NUM_CUES: usize = 5;
+
+    /// The number of loop entries in the tag.
+    pub const NUM_LOOPS: usize = 9;
+
+    /// Create a new `Serato Markers_` tag without any cues or loops.
+    ///
+    /// This uses the same layout as Serato DJ, i.e. 5 unset cue entries followed by 9 unset loop
+    /// entries, and a white track color (i.e. no track color).
+    #[must_use]
+    pub fn new() -> Self {
+        let cues = std::iter::repeat_n(MarkerType::Invalid, Self::NUM_CUES);
+        let loops = std::iter::repeat_n(MarkerType::Loop, Self::NUM_LOOPS);
+        Self {
+            version: Self::VERSION,
+            entries: cues.chain(loops).map(Marker::unset).collect(),
+            track_color: Color {
+                red: 0xFF,
+                green: 0xFF,
+                blue: 0xFF,
+            },
+        }
+    }
+
     #[must_use]
     pub fn cues(&self) -> Vec<(u8, &Marker)> {
         let mut index: u8 = 0;
@@ -112,6 +140,113 @@ impl Markers {
     pub fn track_color(&self) -> Color {
         self.track_color
     }
+
+    /// Returns a mutable reference to the marker entry for the cue with the given index.
+    fn cue_entry_mut(&mut self, index: u8) -> Option<&mut Marker> {
+        self.entries
+            .iter_mut()
+            .filter(|marker| {
+                marker.marker_type == MarkerType::Invalid || marker.marker_type == MarkerType::Cue
+            })
+            .nth(index.into())
+    }
+
+    /// Returns a mutable reference to the marker entry for the loop with the given index.
+    fn loop_entry_mut(&mut self, index: u8) -> Option<&mut Marker> {
+        self.entries
+            .iter_mut()
+            .filter(|marker| marker.marker_type == MarkerType::Loop)
+            .nth(index.into())
+    }
+
+    /// Sets the cue at the index of the given [`Cue`](Cue).
+    ///
+    /// The label is ignored, because the `Serato Markers_` tag doesn't store labels. Returns
+    /// `false` if this tag has no entry for that index (i.e. if the index is greater than 4).
+    pub fn set_cue(&mut self, cue: &Cue) -> bool {
+        if let Some(marker) = self.cue_entry_mut(cue.index) {
+            *marker = Marker {
+                start_position: Some(cue.position),
+                end_position: None,
+                color: cue.color,
+                marker_type: MarkerType::Cue,
+                is_locked: false,
+            };
+            return true;
+        }
+
+        false
+    }
+
+    /// Unsets the cue with the given index.
+    ///
+    /// Returns `false` if this tag has no entry for that index.
+    pub fn remove_cue(&mut self, index: u8) -> bool {
+        if let Some(marker) = self.cue_entry_mut(index) {
+            *marker = Marker::unset(MarkerType::Invalid);
+            return true;
+        }
+
+        false
+    }
+
+    /// Sets the loop at the index of the given [`Loop`](Loop).
+    ///
+    /// The label is ignored, because the `Serato Markers_` tag doesn't store labels. Returns
+    /// `false` if this tag has no entry for that index (i.e. if the index is greater than 8).
+    pub fn set_loop(&mut self, saved_loop: &Loop) -> bool {
+        if let Some(marker) = self.loop_entry_mut(saved_loop.index) {
+            *marker = Marker {
+                start_position: Some(saved_loop.start_position),
+                end_position: Some(saved_loop.end_position),
+                color: saved_loop.color,
+                marker_type: MarkerType::Loop,
+                is_locked: saved_loop.is_locked,
+            };
+            return true;
+        }
+
+        false
+    }
+
+    /// Unsets the loop with the given index.
+    ///
+    /// Returns `false` if this tag has no entry for that index.
+    pub fn remove_loop(&mut self, index: u8) -> bool {
+        if let Some(marker) = self.loop_entry_mut(index) {
+            *marker = Marker::unset(MarkerType::Loop);
+            return true;
+        }
+
+        false
+    }
+
+    pub fn set_track_color(&mut self, color: Color) {
+        self.track_color = color;
+    }
+}
+
+impl Default for Markers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Marker {
+    /// Returns an unset marker of the given type, the way Serato writes it.
+    const fn unset(marker_type: MarkerType) -> Self {
+        Self {
+            start_position: None,
+            end_position: None,
+            color: Color {
+                red: 0,
+                green: 0,
+                blue: 0,
+            },
+            marker_type,
+            is_locked: false,
+        }
+    }
 }
 
 impl Tag for Markers {
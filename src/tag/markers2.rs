@@ -12,9 +12,11 @@
 //!
 //! Note that some of this information is also stored in `Serato Markers_`, and Serato will prefer that data over the information stored in `Serato Markers2` if it is present.
 //!
-//! The minimum length of this tag seems to be 470 bytes, and shorter contents are padded with null bytes.
+//! The minimum length of this tag seems to be 470 bytes (514 bytes for FLAC and MP4), and shorter
+//! contents are padded with null bytes.
 
 use super::color::Color;
+use super::container::TagFormat;
 use super::format::{Tag, enveloped, flac, id3, mp4, ogg};
 use super::generic::{
     CensorFlipAction, Cue, Flip, FlipAction, JumpFlipAction, Loop, Position, UnknownFlipAction,
@@ -98,6 +100,47 @@ pub struct Markers2 {
 }
 
 impl Markers2 {
+    /// The tag version written by Serato DJ.
+    pub const VERSION: Version = Version { major: 1, minor: 1 };
+
+    /// The minimum size of the tag data in ID3 tags.
+    pub const MIN_SIZE_ID3: usize = 470;
+
+    /// The minimum size of the tag data in FLAC and MP4 tags (before base64 encoding).
+    pub const MIN_SIZE_ENVELOPED: usize = 514;
+
+    /// Create a new `Serato Markers2` tag without any cues, loops or flips.
+    ///
+    /// The tag contains a white track color (i.e. no track color) and an unlocked beatgrid, just
+    /// like the tags written by Serato DJ. Since the amount of null byte padding depends on the tag
+    /// format, the format that the tag will be written to needs to be specified.
+    #[must_use]
+    pub fn new(tag_format: TagFormat) -> Self {
+        let (version, size) = match tag_format {
+            TagFormat::ID3 => (Some(Self::VERSION), Self::MIN_SIZE_ID3),
+            TagFormat::FLAC | TagFormat::MP4 => (Some(Self::VERSION), Self::MIN_SIZE_ENVELOPED),
+            TagFormat::Ogg => (None, 0),
+        };
+        let markers = vec![
+            Marker::Color(TrackColorMarker {
+                color: Color {
+                    red: 0xFF,
+                    green: 0xFF,
+                    blue: 0xFF,
+                },
+            }),
+            Marker::BPMLock(BPMLockMarker { is_locked: false }),
+        ];
+        Self {
+            version,
+            size,
+            content: Markers2Content {
+                version: Self::VERSION,
+                markers,
+            },
+        }
+    }
+
     #[must_use]
     pub fn bpm_locked(&self) -> Option<bool> {
         for marker in &self.content.markers {
@@ -148,6 +191,115 @@ impl Markers2 {
             }
         })
     }
+
+    /// Inserts the marker at the position where Serato would put it.
+    ///
+    /// Serato writes the `COLOR` marker first, followed by the `CUE` markers, the `LOOP` markers,
+    /// the `FLIP` markers and finally the `BPMLOCK` marker. Markers of the same type are sorted by
+    /// index.
+    fn insert_marker(&mut self, marker: Marker) {
+        let key = marker.sort_key();
+        let position = self
+            .content
+            .markers
+            .iter()
+            .position(|m| match (m.sort_key(), key) {
+                (Some(k), Some(key)) => k > key,
+                _ => false,
+            })
+            .unwrap_or(self.content.markers.len());
+        self.content.markers.insert(position, marker);
+    }
+
+    /// Sets the given cue, replacing an existing cue with the same index.
+    pub fn set_cue(&mut self, cue: Cue) {
+        let existing = self
+            .content
+            .markers
+            .iter_mut()
+            .find(|m| matches!(m, Marker::Cue(c) if c.index == cue.index));
+        match existing {
+            Some(marker) => *marker = Marker::Cue(cue),
+            None => self.insert_marker(Marker::Cue(cue)),
+        }
+    }
+
+    /// Removes the cue with the given index and returns it (if any).
+    pub fn remove_cue(&mut self, index: u8) -> Option<Cue> {
+        let position = self
+            .content
+            .markers
+            .iter()
+            .position(|m| matches!(m, Marker::Cue(c) if c.index == index))?;
+        match self.content.markers.remove(position) {
+            Marker::Cue(cue) => Some(cue),
+            _ => None,
+        }
+    }
+
+    /// Sets the given loop, replacing an existing loop with the same index.
+    pub fn set_loop(&mut self, saved_loop: Loop) {
+        let existing = self
+            .content
+            .markers
+            .iter_mut()
+            .find(|m| matches!(m, Marker::Loop(l) if l.index == saved_loop.index));
+        match existing {
+            Some(marker) => *marker = Marker::Loop(saved_loop),
+            None => self.insert_marker(Marker::Loop(saved_loop)),
+        }
+    }
+
+    /// Removes the loop with the given index and returns it (if any).
+    pub fn remove_loop(&mut self, index: u8) -> Option<Loop> {
+        let position = self
+            .content
+            .markers
+            .iter()
+            .position(|m| matches!(m, Marker::Loop(l) if l.index == index))?;
+        match self.content.markers.remove(position) {
+            Marker::Loop(saved_loop) => Some(saved_loop),
+            _ => None,
+        }
+    }
+
+    /// Sets the track color, adding a `COLOR` marker if necessary.
+    pub fn set_track_color(&mut self, color: Color) {
+        for marker in &mut self.content.markers {
+            if let Marker::Color(m) = marker {
+                m.color = color;
+                return;
+            }
+        }
+        self.insert_marker(Marker::Color(TrackColorMarker { color }));
+    }
+
+    /// Sets the BPM lock status, adding a `BPMLOCK` marker if necessary.
+    pub fn set_bpm_locked(&mut self, is_locked: bool) {
+        for marker in &mut self.content.markers {
+            if let Marker::BPMLock(m) = marker {
+                m.is_locked = is_locked;
+                return;
+            }
+        }
+        self.insert_marker(Marker::BPMLock(BPMLockMarker { is_locked }));
+    }
+}
+
+impl Marker {
+    /// Returns the key that determines the marker order in the tag.
+    ///
+    /// Unknown markers have no defined position and return `None`.
+    fn sort_key(&self) -> Option<(u8, u8)> {
+        match self {
+            Marker::Unknown(_) => None,
+            Marker::Color(_) => Some((0, 0)),
+            Marker::Cue(m) => Some((1, m.index)),
+            Marker::Loop(m) => Some((2, m.index)),
+            Marker::Flip(m) => Some((3, m.index)),
+            Marker::BPMLock(_) => Some((4, 0)),
+        }
+    }
 }
 
 impl Tag for Markers2 {
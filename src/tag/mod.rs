@@ -8,6 +8,7 @@
 
 //! Parsers for Serato's file tags
 pub mod color;
+pub mod file;
 pub mod format;
 pub mod generic;
 pub mod serato32;
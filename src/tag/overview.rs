@@ -41,6 +41,20 @@ pub struct Overview {
     pub data: Vec<Vec<u8>>,
 }
 
+impl Overview {
+    /// The tag version written by Serato DJ.
+    pub const VERSION: Version = Version { major: 1, minor: 5 };
+
+    /// Create a new `Serato Overview` tag from the given 16-byte chunks.
+    #[must_use]
+    pub const fn new(data: Vec<Vec<u8>>) -> Self {
+        Self {
+            version: Self::VERSION,
+            data,
+        }
+    }
+}
+
 impl Tag for Overview {
     const NAME: &'static str = "Serato Overview";
 
@@ -32,7 +32,7 @@ use std::io;
 ///     println!("{:?}", content);
 /// }
 /// ```
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RelVolAd {
     /// The `RelVolAd` version.
     pub version: Version,
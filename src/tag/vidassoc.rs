@@ -6,9 +6,7 @@
 //
 // SPDX-License-Identifier: MPL-2.0
 
-//! The `Serato VidAssoc` tag stores the analysis version.
-//!
-//! This is probably the Serato Version number that performed the analysis.
+//! The `Serato VidAssoc` tag stores the video file that is associated with the track.
 
 use super::format::{Tag, enveloped, flac, mp4};
 use super::generic::Version;
@@ -20,7 +18,9 @@ use std::io;
 /// Represents the  `Serato VidAssoc` tag.
 ///
 /// **Note:** This tag has not been reverse-engineered yet. Judging from the name it contains
-/// "Video Association" data.
+/// "Video Association" data, i.e. the video file that is linked to the track in Serato Video.
+/// None of the known samples has a video linked, so the layout of the data is unknown and it is
+/// kept as a byte vector.
 ///
 /// # Example
 ///
@@ -34,7 +34,7 @@ use std::io;
 ///     println!("{:?}", content);
 /// }
 /// ```
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct VidAssoc {
     /// The `VidAssoc` version.
     pub version: Version,
@@ -42,6 +42,17 @@ pub struct VidAssoc {
     pub data: Vec<u8>,
 }
 
+impl VidAssoc {
+    /// Returns the path of the associated video file.
+    ///
+    /// Since the data has not been reverse-engineered yet, the path can't be decoded and `None`
+    /// is returned for now.
+    #[must_use]
+    pub fn video_path(&self) -> Option<&str> {
+        None
+    }
+}
+
 impl Tag for VidAssoc {
     const NAME: &'static str = "Serato VidAssoc";
 
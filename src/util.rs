@@ -54,3 +54,56 @@ pub fn take_utf8(input: &[u8]) -> Res<&[u8], &str> {
 fn test_take_utf8() {
     assert_eq!(take_utf8(&[0x41, 0x42, 0x00]), Ok((&b""[..], "AB")));
 }
+
+/// Writes the data to a temporary file in the same directory and then replaces the file at the
+/// given path with it, so that the file is never left half-written.
+///
+/// If the file already exists, its permissions are copied to the new file.
+pub(crate) fn write_atomically(path: &std::path::Path, data: &[&[u8]]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut file_name = std::ffi::OsString::from(".");
+    file_name.push(path.file_name().unwrap_or_default());
+    file_name.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = path.with_file_name(file_name);
+    let result = (|| {
+        let mut file = std::fs::File::create(&temp_path)?;
+        for chunk in data {
+            file.write_all(chunk)?;
+        }
+        match std::fs::metadata(path) {
+            Ok(metadata) => file.set_permissions(metadata.permissions())?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => (),
+            Err(err) => return Err(err),
+        }
+        file.sync_all()?;
+        std::fs::rename(&temp_path, path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+#[test]
+fn test_write_atomically() {
+    let dir = std::env::temp_dir().join(format!("triseratops-atomic-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("file.bin");
+    std::fs::write(&path, b"old").unwrap();
+    write_atomically(&path, &[b"new ", b"data"]).unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), b"new data");
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let permissions = std::fs::Permissions::from_mode(0o640);
+        std::fs::set_permissions(&path, permissions).unwrap();
+        write_atomically(&path, &[b"newer data"]).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+}
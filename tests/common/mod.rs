@@ -0,0 +1,99 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+//! Helpers that are shared by the conversion tests.
+
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use triseratops::library::Library;
+use triseratops::tag::beatgrid::{NonTerminalMarker, TerminalMarker};
+use triseratops::tag::{Beatgrid, TagContainer, TagFormat};
+
+/// Returns a tag container with the hotcues and loops from the test data and a beatgrid with two
+/// markers.
+pub fn container_with_cues_and_loops() -> TagContainer {
+    let mut tags = TagContainer::new();
+    tags.parse_markers(
+        include_bytes!("../data/tags/markers/hotcues_and_loops.mp4.bin"),
+        TagFormat::MP4,
+    )
+    .unwrap();
+    tags.parse_markers2(
+        include_bytes!("../data/tags/markers2/hotcues_and_loops.mp4.bin"),
+        TagFormat::MP4,
+    )
+    .unwrap();
+    tags.set_beatgrid(Beatgrid::new(
+        vec![NonTerminalMarker {
+            position: 0.5,
+            beats_till_next_marker: 6,
+        }],
+        TerminalMarker {
+            position: 3.5,
+            bpm: 128.0,
+        },
+    ));
+    tags
+}
+
+/// Returns the `usb_drive` library, with the library directory as volume root.
+pub fn usb_drive_library() -> Library {
+    let path = Path::new("tests/data/library/usb_drive");
+    let mut library = Library::read_from_path(path).unwrap();
+    library.set_volume_root(std::fs::canonicalize(path).unwrap());
+    library
+}
+
+/// A temporary directory that is removed when it is dropped (i.e. also if the test fails).
+#[derive(Debug)]
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Creates a new directory with a unique name, so that tests don't interfere with each other.
+    pub fn new(name: &str) -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let path = std::env::temp_dir().join(format!(
+            "triseratops-{name}-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Creates a temporary directory that contains a copy of the `database V2` file of the library
+/// with the given name (in `tests/data/library`).
+pub fn copy_library_database(name: &str, library: &str) -> TempDir {
+    let path = TempDir::new(name);
+    std::fs::create_dir_all(path.join("_Serato_")).unwrap();
+    std::fs::copy(
+        Path::new("tests/data/library")
+            .join(library)
+            .join("_Serato_/database V2"),
+        path.join("_Serato_/database V2"),
+    )
+    .unwrap();
+    path
+}
@@ -0,0 +1,64 @@
+-- Synthetic subset of the Engine DJ 2.x database schema (only the tables and columns used by the
+-- converter).
+CREATE TABLE Information (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  uuid TEXT,
+  schemaVersionMajor INTEGER,
+  schemaVersionMinor INTEGER,
+  schemaVersionPatch INTEGER,
+  currentPlayedIndiciator INTEGER,
+  lastRekordBoxLibraryImportReadCounter INTEGER
+);
+INSERT INTO Information VALUES (1, '8c3a1b0e-5e43-4f5b-9b2e-0d6c1f0a7e21', 2, 20, 3, 0, 0);
+CREATE TABLE Track (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  playOrder INTEGER,
+  length INTEGER,
+  bpm INTEGER,
+  year INTEGER,
+  path TEXT,
+  filename TEXT,
+  bitrate INTEGER,
+  bpmAnalyzed REAL,
+  albumArtId INTEGER,
+  fileBytes INTEGER,
+  title TEXT,
+  artist TEXT,
+  album TEXT,
+  genre TEXT,
+  comment TEXT,
+  label TEXT,
+  composer TEXT,
+  remixer TEXT,
+  key INTEGER,
+  rating INTEGER,
+  albumArt TEXT,
+  timeLastPlayed DATETIME,
+  isPlayed BOOLEAN,
+  fileType TEXT,
+  isAnalyzed BOOLEAN,
+  dateCreated DATETIME,
+  dateAdded DATETIME,
+  isAvailable BOOLEAN,
+  isMetadataOfPackedTrackChanged BOOLEAN,
+  isPerfomanceDataOfPackedTrackChanged BOOLEAN,
+  playedIndicator INTEGER,
+  isMetadataImported BOOLEAN,
+  pdbImportKey INTEGER,
+  streamingSource TEXT,
+  uri TEXT,
+  isBeatGridLocked BOOLEAN,
+  originDatabaseUuid TEXT,
+  originTrackId INTEGER,
+  trackData BLOB,
+  overviewWaveFormData BLOB,
+  beatData BLOB,
+  quickCues BLOB,
+  loops BLOB,
+  thirdPartySourceId INTEGER,
+  streamingFlags INTEGER,
+  explicitLyrics BOOLEAN,
+  activeOnLoadLoops INTEGER,
+  lastEditTime DATETIME,
+  CONSTRAINT C_path UNIQUE (path)
+);
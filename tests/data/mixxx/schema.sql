@@ -0,0 +1,75 @@
+-- Synthetic subset of the Mixxx 2.4 database schema (only the tables and columns used by the
+-- converter).
+CREATE TABLE track_locations (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  location varchar(512) UNIQUE,
+  filename varchar(512),
+  directory varchar(512),
+  filesize INTEGER,
+  fs_deleted INTEGER,
+  needs_verification INTEGER
+);
+CREATE TABLE library (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  artist varchar(64),
+  title varchar(64),
+  album varchar(64),
+  year varchar(16),
+  genre varchar(64),
+  tracknumber varchar(3),
+  location INTEGER REFERENCES track_locations(location),
+  comment varchar(256),
+  duration INTEGER,
+  bitrate INTEGER,
+  samplerate INTEGER,
+  bpm FLOAT,
+  datetime_added DEFAULT CURRENT_TIMESTAMP,
+  mixxx_deleted INTEGER,
+  header_parsed INTEGER DEFAULT 0,
+  filetype varchar(8) DEFAULT "?",
+  key varchar(8) DEFAULT "",
+  beats BLOB,
+  beats_version TEXT,
+  composer varchar(64) DEFAULT "",
+  beats_sub_version TEXT DEFAULT "",
+  grouping TEXT DEFAULT ""
+);
+CREATE TABLE cues (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  track_id INTEGER NOT NULL REFERENCES library(id),
+  type INTEGER DEFAULT 0 NOT NULL,
+  position INTEGER DEFAULT -1 NOT NULL,
+  length INTEGER DEFAULT 0 NOT NULL,
+  hotcue INTEGER DEFAULT -1 NOT NULL,
+  label TEXT DEFAULT '' NOT NULL,
+  color INTEGER DEFAULT 4294901760 NOT NULL
+);
+CREATE TABLE crates (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  name varchar(48) UNIQUE NOT NULL,
+  count INTEGER DEFAULT 0,
+  show INTEGER DEFAULT 1,
+  locked INTEGER DEFAULT 0,
+  autodj_source INTEGER DEFAULT 0
+);
+CREATE TABLE crate_tracks (
+  crate_id INTEGER NOT NULL REFERENCES crates(id),
+  track_id INTEGER NOT NULL REFERENCES library(id),
+  UNIQUE (crate_id, track_id)
+);
+CREATE TABLE Playlists (
+  id INTEGER PRIMARY KEY,
+  name varchar(48),
+  position INTEGER,
+  hidden INTEGER DEFAULT 0 NOT NULL,
+  date_created datetime,
+  date_modified datetime,
+  locked INTEGER DEFAULT 0
+);
+CREATE TABLE PlaylistTracks (
+  id INTEGER PRIMARY KEY,
+  playlist_id INTEGER REFERENCES Playlists(id),
+  track_id INTEGER REFERENCES library(id),
+  position INTEGER,
+  pl_datetime_added
+);
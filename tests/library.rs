@@ -8,7 +8,11 @@
 
 extern crate triseratops;
 
-use triseratops::library::Library;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use triseratops::error::Error;
+use triseratops::library::{Library, MergedLibrary, database};
 
 #[test]
 fn test_library() {
@@ -23,3 +27,459 @@ fn test_library() {
     let subcrate_tracks = library.subcrate("French House").unwrap();
     assert_eq!(2, subcrate_tracks.count());
 }
+
+#[test]
+fn test_crate_roundtrip() {
+    for name in ["80s Mashup", "French House"] {
+        let path = format!("tests/data/library/usb_drive/_Serato_/Subcrates/{name}.crate");
+        let input = std::fs::read(path).unwrap();
+        let fields = database::parse(&input).unwrap();
+        let mut writer = Cursor::new(vec![]);
+        database::write(&mut writer, &fields).unwrap();
+        assert_eq!(writer.get_ref(), &input);
+    }
+}
+
+/// Copies the library in the given directory to a temporary directory.
+fn copy_library(name: &str) -> PathBuf {
+    let source = Path::new("tests/data/library/usb_drive/_Serato_");
+    let path = std::env::temp_dir().join(format!("triseratops-library-{name}"));
+    let _ = std::fs::remove_dir_all(&path);
+    std::fs::create_dir_all(path.join("_Serato_/Subcrates")).unwrap();
+    std::fs::copy(
+        source.join("database V2"),
+        path.join("_Serato_/database V2"),
+    )
+    .unwrap();
+    for entry in source.join("Subcrates").read_dir().unwrap() {
+        let entry = entry.unwrap();
+        std::fs::copy(
+            entry.path(),
+            path.join("_Serato_/Subcrates").join(entry.file_name()),
+        )
+        .unwrap();
+    }
+    path
+}
+
+#[test]
+fn test_create_and_edit_subcrates() {
+    let path = copy_library("edit_subcrates");
+    let library = Library::read_from_path(&path).unwrap();
+
+    let mut subcrate = library.create_subcrate("New Crate").unwrap();
+    assert!(matches!(
+        library.create_subcrate("New Crate"),
+        Err(Error::CrateAlreadyExists { .. })
+    ));
+    assert!(matches!(
+        library.create_subcrate("Invalid/Name"),
+        Err(Error::InvalidCrateName { .. })
+    ));
+    let track_path = PathBuf::from("CASSIUS_-_99_Keller 2016 RE-EDIT -.mp3");
+    assert!(library.track(&track_path).is_some());
+    subcrate.add_track(track_path.clone());
+    subcrate.set_sorting("bpm", true);
+    library.write_subcrate("New Crate", &subcrate).unwrap();
+
+    assert_eq!(3, library.subcrates().count());
+    assert_eq!(1, library.subcrate("New Crate").unwrap().count());
+
+    assert!(library.subcrate_order().unwrap().is_empty());
+    library
+        .set_subcrate_order(&[
+            "New Crate".to_string(),
+            "French House".to_string(),
+            "80s Mashup".to_string(),
+        ])
+        .unwrap();
+    library.rename_subcrate("New Crate", "Renamed").unwrap();
+    assert!(matches!(
+        library.rename_subcrate("Renamed", "French House"),
+        Err(Error::CrateAlreadyExists { .. })
+    ));
+    let subcrate = library.read_subcrate("Renamed").unwrap();
+    assert!(subcrate.contains_track(&track_path));
+    assert_eq!(subcrate.sorting(), Some(("bpm", true)));
+    assert_eq!(
+        library.subcrate_order().unwrap(),
+        ["Renamed", "French House", "80s Mashup"]
+    );
+
+    library.delete_subcrate("80s Mashup").unwrap();
+    assert_eq!(2, library.subcrates().count());
+    assert_eq!(
+        library.subcrate_order().unwrap(),
+        ["Renamed", "French House"]
+    );
+
+    std::fs::remove_dir_all(path).unwrap();
+}
+
+#[test]
+fn test_subcrate_tree() {
+    let path = copy_library("subcrate_tree");
+    let library = Library::read_from_path(&path).unwrap();
+
+    library
+        .create_subcrate_by_path(&["French House", "Filter", "Disco"])
+        .unwrap();
+    assert!(
+        library
+            .create_subcrate_by_path(&["French House", "Filter"])
+            .is_err()
+    );
+    library.read_subcrate("French House%%Filter").unwrap();
+    library
+        .read_subcrate_by_path(&["French House", "Filter", "Disco"])
+        .unwrap();
+
+    let tree = library.subcrate_tree();
+    let names: Vec<_> = tree.iter().map(|node| node.name.as_str()).collect();
+    assert_eq!(names, ["80s Mashup", "French House"]);
+    let node = tree[1].find(&["Filter", "Disco"]).unwrap();
+    assert_eq!(node.full_name, "French House%%Filter%%Disco");
+    assert!(node.exists);
+    assert!(node.children.is_empty());
+
+    library.rename_subcrate("French House", "Nu Disco").unwrap();
+    let tree = library.subcrate_tree();
+    let names: Vec<_> = tree.iter().map(|node| node.name.as_str()).collect();
+    assert_eq!(names, ["80s Mashup", "Nu Disco"]);
+    assert!(tree[1].find(&["Filter", "Disco"]).unwrap().exists);
+
+    library.delete_subcrate("Nu Disco%%Filter").unwrap();
+    let names: Vec<_> = library.subcrates().collect();
+    assert_eq!(names.len(), 2);
+    assert!(names.iter().all(|name| !name.contains("Filter")));
+    assert!(matches!(
+        library.delete_subcrate("Nu Disco%%Filter"),
+        Err(Error::IOError(_))
+    ));
+
+    std::fs::remove_dir_all(path).unwrap();
+}
+
+#[test]
+fn test_smart_crate() {
+    let library = Library::read_from_path("tests/data/library/usb_drive").unwrap();
+    assert_eq!(library.smart_crates().collect::<Vec<_>>(), ["synthetic"]);
+
+    let smart_crate = library.read_smart_crate("synthetic").unwrap();
+    assert_eq!(smart_crate.fields().len(), 6);
+    let mut writer = Cursor::new(vec![]);
+    smart_crate.write(&mut writer).unwrap();
+    assert_eq!(
+        writer.get_ref().as_slice(),
+        std::fs::read("tests/data/library/usb_drive/_Serato_/SmartCrates/synthetic.scrate")
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_history_sessions() {
+    let library = Library::read_from_path("tests/data/library/usb_drive").unwrap();
+    let sessions = library.history_sessions().unwrap();
+    assert_eq!(sessions.len(), 1);
+
+    let entries = sessions[0].entries();
+    assert_eq!(entries.len(), 3);
+    assert!(
+        entries[1]
+            .fields()
+            .iter()
+            .any(|field| field.id == 31 && field.content == [0x00, 0x00, 0x00, 0x02])
+    );
+}
+
+#[test]
+fn test_track_fields() {
+    let library = Library::read_from_path("tests/data/library/usb_drive").unwrap();
+    assert_eq!(
+        library.database_version(),
+        Some("2.0/Serato Scratch LIVE Database")
+    );
+
+    let track = library
+        .track(Path::new("CASSIUS_-_99_Keller 2016 RE-EDIT -.mp3"))
+        .unwrap();
+    assert_eq!(track.bpm, Some(126.0));
+    assert_eq!(track.file_size_bytes, Some(15_319_300));
+    assert_eq!(track.color, Some(0xFFDD99));
+    assert!(track.played);
+    assert!(!track.corrupt);
+    assert_eq!(track.bitrate, Some(320.0));
+    assert_eq!(track.sample_rate, Some(44100));
+    assert_eq!(track.length, Some(Duration::from_millis(382_930)));
+    assert_eq!(track.file_size.as_deref(), Some("14.6MB"));
+    assert_eq!(
+        track.date_added,
+        Some(UNIX_EPOCH + Duration::from_secs(1_580_992_490))
+    );
+    assert_eq!(
+        track.file_time,
+        Some(UNIX_EPOCH + Duration::from_secs(1_580_989_282))
+    );
+    assert!(!track.unknown_fields.is_empty());
+    assert!(
+        track
+            .unknown_fields
+            .iter()
+            .all(|field| !matches!(field, database::Field::BPM(_)))
+    );
+
+    let track = library
+        .track(Path::new(
+            "Pete Heller - Big Love (Vaudafunk 2019 Reinterpretation).mp3",
+        ))
+        .unwrap();
+    assert_eq!(track.year.as_deref(), Some("2019"));
+}
+
+#[test]
+fn test_database_field_codes() {
+    let input = std::fs::read("tests/data/library/synthetic/_Serato_/database V2").unwrap();
+    let fields = database::parse(&input).unwrap();
+    let database::Field::Track(track_fields) = &fields[1] else {
+        panic!("Expected track field");
+    };
+    for field in [
+        database::Field::Color(0xCC0000),
+        database::Field::FileSizeBytes(1_234_567),
+        database::Field::TrackNumber(3),
+        database::Field::DiscNumber(1),
+        database::Field::Played(true),
+        database::Field::ITunesImport(true),
+        database::Field::Corrupt(true),
+    ] {
+        assert!(track_fields.contains(&field), "{field:?} not found");
+    }
+    // The meaning of these fields hasn't been confirmed yet.
+    for field in [
+        database::Field::UnknownU16Field {
+            name: b"bav".to_vec(),
+            value: 513,
+        },
+        database::Field::UnknownBoolean {
+            name: b"hrt".to_vec(),
+            value: false,
+        },
+        database::Field::UnknownBoolean {
+            name: b"ovc".to_vec(),
+            value: false,
+        },
+        database::Field::UnknownBoolean {
+            name: b"iro".to_vec(),
+            value: true,
+        },
+        database::Field::UnknownBoolean {
+            name: b"wlb".to_vec(),
+            value: true,
+        },
+        database::Field::UnknownBoolean {
+            name: b"wll".to_vec(),
+            value: true,
+        },
+        database::Field::UnknownBoolean {
+            name: b"uns".to_vec(),
+            value: true,
+        },
+        database::Field::UnknownBoolean {
+            name: b"krk".to_vec(),
+            value: true,
+        },
+    ] {
+        assert!(track_fields.contains(&field), "{field:?} not found");
+    }
+
+    let library = Library::read_from_path("tests/data/library/synthetic").unwrap();
+    let track = library
+        .track(Path::new("Music/Synthetic Artist - Synthetic Title.mp3"))
+        .unwrap();
+    assert_eq!(track.color, Some(0xCC0000));
+    assert_eq!(track.file_size_bytes, Some(1_234_567));
+    assert_eq!(track.track_number, Some(3));
+    assert_eq!(track.disc_number, Some(1));
+    assert!(track.played);
+    assert!(track.corrupt);
+    assert!(track.missing);
+    assert!(
+        track
+            .unknown_fields
+            .iter()
+            .any(|field| matches!(field, database::Field::UnknownContainerRField { .. }))
+    );
+}
+
+#[test]
+fn test_volume_root() {
+    let mut library = Library::read_from_path("tests/data/library/usb_drive").unwrap();
+    let library_path = std::fs::canonicalize("tests/data/library/usb_drive").unwrap();
+    assert!(library_path.starts_with(library.volume_root()));
+
+    // Pretend that the library is on a USB drive.
+    library.set_volume_root(&library_path);
+    let file_path = Path::new("CASSIUS_-_99_Keller 2016 RE-EDIT -.mp3");
+    let absolute_path = library.absolute_path(file_path);
+    assert_eq!(absolute_path, library_path.join(file_path));
+    assert_eq!(
+        library.relative_path(&absolute_path).as_deref(),
+        Some(file_path)
+    );
+    assert!(library.track(&absolute_path).is_some());
+    assert!(library.track(file_path).is_some());
+    assert!(
+        library
+            .relative_path(Path::new("/some/other/volume/track.mp3"))
+            .is_none()
+    );
+}
+
+#[test]
+fn test_merged_library() {
+    let copy_path = copy_library("merged");
+    let mut library = MergedLibrary::new();
+    for path in [
+        Path::new("tests/data/library/usb_drive"),
+        copy_path.as_path(),
+    ] {
+        // Both libraries are on the same file system, so pretend that they are on separate drives.
+        let mut drive = Library::read_from_path(path).unwrap();
+        drive.set_volume_root(std::fs::canonicalize(path).unwrap());
+        library.add_library(drive);
+    }
+    assert_eq!(library.libraries().len(), 2);
+    assert_eq!(library.tracks().count(), 8);
+    assert_eq!(library.subcrates(), ["80s Mashup", "French House"]);
+    assert_eq!(library.subcrate("French House").unwrap().len(), 4);
+
+    // The tracks of the copied library are duplicates of the original ones.
+    let duplicates = library.duplicates();
+    assert_eq!(duplicates.len(), 4);
+    for tracks in &duplicates {
+        assert_eq!(tracks.len(), 2);
+        assert_eq!(tracks[0].path, tracks[1].path);
+        assert_ne!(tracks[0].volume_root(), tracks[1].volume_root());
+    }
+
+    let mut synthetic = Library::read_from_path("tests/data/library/synthetic").unwrap();
+    let synthetic_root = std::fs::canonicalize("tests/data/library/synthetic").unwrap();
+    synthetic.set_volume_root(&synthetic_root);
+    library.add_library(synthetic);
+    assert_eq!(library.tracks().count(), 9);
+    assert_eq!(library.duplicates().len(), 4);
+
+    let file_path = synthetic_root.join("Music/Synthetic Artist - Synthetic Title.mp3");
+    let track = library.track(&file_path).unwrap();
+    assert_eq!(track.volume_root(), synthetic_root);
+    assert_eq!(track.absolute_path(), file_path);
+    assert_eq!(track.track.title.as_deref(), Some("Synthetic Title"));
+
+    std::fs::remove_dir_all(copy_path).unwrap();
+}
+
+#[test]
+fn test_relocate_missing_tracks() {
+    let path = std::fs::canonicalize(copy_library("relocate")).unwrap();
+    let mut library = Library::read_from_path(&path).unwrap();
+    library.set_volume_root(&path);
+    assert_eq!(library.missing_tracks().len(), 4);
+
+    // Create empty files with the original sizes in place of the moved tracks.
+    let create_file = |name: &str, size: u64| {
+        let file_path = path.join(name);
+        std::fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+        std::fs::File::create(&file_path)
+            .unwrap()
+            .set_len(size)
+            .unwrap();
+        file_path
+    };
+    let moved_path = create_file(
+        "Music/Moved/CASSIUS_-_99_Keller 2016 RE-EDIT -.mp3",
+        15_319_300,
+    );
+    let renamed_path = create_file(
+        "Music/pete heller - big love (vaudafunk 2019 reinterpretation).mp3",
+        19_391_415,
+    );
+    // Ambiguous candidates with a different size are not relocated automatically.
+    create_file("Music/A/ALAN BRAXE - INTRO ( Max Padovani Remix).mp3", 1024);
+    create_file("Music/B/ALAN BRAXE - INTRO ( Max Padovani Remix).mp3", 1024);
+
+    let cassius_path = Path::new("CASSIUS_-_99_Keller 2016 RE-EDIT -.mp3");
+    let candidates = library
+        .find_relocation_candidates(cassius_path, path.join("Music"))
+        .unwrap();
+    assert_eq!(candidates.len(), 1);
+    assert_eq!(candidates[0].path, moved_path);
+    assert!(candidates[0].file_name_matches);
+    assert!(candidates[0].file_size_matches);
+    assert_eq!(candidates[0].score(), 3);
+
+    let relocations = library.relocate_missing_tracks(path.join("Music")).unwrap();
+    assert_eq!(
+        relocations,
+        [
+            (
+                PathBuf::from(cassius_path),
+                PathBuf::from("Music/Moved/CASSIUS_-_99_Keller 2016 RE-EDIT -.mp3")
+            ),
+            (
+                PathBuf::from("Pete Heller - Big Love (Vaudafunk 2019 Reinterpretation).mp3"),
+                PathBuf::from("Music/pete heller - big love (vaudafunk 2019 reinterpretation).mp3")
+            ),
+        ]
+    );
+    assert_eq!(library.missing_tracks().len(), 2);
+    assert!(library.track(cassius_path).is_none());
+    assert!(library.track(&renamed_path).is_some());
+
+    // The new paths are written to the database and the crates.
+    let library = Library::read_from_path(&path).unwrap();
+    let track = library.track(&relocations[0].1).unwrap();
+    assert_eq!(
+        track.title.as_deref(),
+        Some("CASSIUS_-_99_Keller 2016 RE-EDIT -")
+    );
+    let subcrate = library.read_subcrate("French House").unwrap();
+    assert!(subcrate.contains_track(&relocations[0].1));
+    assert!(!subcrate.contains_track(cassius_path));
+
+    let mut library = library;
+    library.set_volume_root(&path);
+    let alan_braxe_path = Path::new("ALAN BRAXE - INTRO ( Max Padovani Remix).mp3");
+    assert_eq!(
+        library
+            .find_relocation_candidates(alan_braxe_path, &path)
+            .unwrap()
+            .len(),
+        2
+    );
+    assert!(matches!(
+        library.relocate_track(alan_braxe_path, Path::new("/some/other/volume/track.mp3")),
+        Err(Error::PathNotOnVolume { .. })
+    ));
+    assert!(matches!(
+        library.relocate_track(alan_braxe_path, &relocations[0].1),
+        Err(Error::TrackAlreadyExists { path }) if path == relocations[0].1
+    ));
+    let new_path = Path::new("Music/A/ALAN BRAXE - INTRO ( Max Padovani Remix).mp3");
+    library
+        .relocate_track(&path.join(alan_braxe_path), &path.join(new_path))
+        .unwrap();
+    assert!(library.track(new_path).is_some());
+    assert!(matches!(
+        library.relocate_track(alan_braxe_path, new_path),
+        Err(Error::TrackNotFound { path }) if path == alan_braxe_path
+    ));
+    assert!(matches!(
+        library.find_relocation_candidates(alan_braxe_path, &path),
+        Err(Error::TrackNotFound { .. })
+    ));
+    let subcrate = library.read_subcrate("French House").unwrap();
+    let track_paths: Vec<_> = subcrate.track_paths().collect();
+    assert_eq!(track_paths, [new_path, relocations[0].1.as_path()]);
+
+    std::fs::remove_dir_all(path).unwrap();
+}
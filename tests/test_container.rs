@@ -0,0 +1,235 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use std::io::Cursor;
+use triseratops::tag::color::Color;
+use triseratops::tag::generic::{Cue, Loop, Position};
+use triseratops::tag::{TagContainer, TagFormat};
+
+const RED: Color = Color {
+    red: 0xCC,
+    green: 0x00,
+    blue: 0x00,
+};
+const LOOP_COLOR: Color = Color {
+    red: 0x27,
+    green: 0xAA,
+    blue: 0xE1,
+};
+
+fn container_with_names() -> TagContainer {
+    let mut tags = TagContainer::new();
+    tags.parse_markers(
+        include_bytes!("data/tags/markers/hotcues_with_names.id3.bin"),
+        TagFormat::ID3,
+    )
+    .unwrap();
+    tags.parse_markers2(
+        include_bytes!("data/tags/markers2/hotcues_with_names.id3.bin"),
+        TagFormat::ID3,
+    )
+    .unwrap();
+    tags
+}
+
+/// Writes both marker tags and parses them into a new container.
+fn roundtrip(tags: &TagContainer) -> TagContainer {
+    let mut markers = Cursor::new(vec![]);
+    tags.write_markers(&mut markers, TagFormat::ID3).unwrap();
+    let mut markers2 = Cursor::new(vec![]);
+    tags.write_markers2(&mut markers2, TagFormat::ID3).unwrap();
+
+    let mut output = TagContainer::new();
+    output
+        .parse_markers(markers.get_ref(), TagFormat::ID3)
+        .unwrap();
+    output
+        .parse_markers2(markers2.get_ref(), TagFormat::ID3)
+        .unwrap();
+    output
+}
+
+#[test]
+fn test_set_and_remove_cue() {
+    let mut tags = container_with_names();
+    assert_eq!(tags.cues().len(), 3);
+
+    tags.set_cue(Cue {
+        index: 1,
+        position: Position { millis: 1234 },
+        color: RED,
+        label: "Drop".to_string(),
+    })
+    .unwrap();
+    tags.set_cue(Cue {
+        index: 6,
+        position: Position { millis: 5678 },
+        color: RED,
+        label: "Outro".to_string(),
+    })
+    .unwrap();
+    tags.remove_cue(2);
+
+    let tags = roundtrip(&tags);
+    let cues = tags.cues();
+    assert_eq!(cues.len(), 3);
+    assert_eq!(cues[0].index, 0);
+    assert_eq!(cues[0].label, "Hello, World!");
+    assert_eq!(cues[1].index, 1);
+    assert_eq!(cues[1].position, Position { millis: 1234 });
+    assert_eq!(cues[1].color, RED);
+    assert_eq!(cues[1].label, "Drop");
+    assert_eq!(cues[2].index, 6);
+    assert_eq!(cues[2].position, Position { millis: 5678 });
+    assert_eq!(cues[2].label, "Outro");
+}
+
+#[test]
+fn test_set_and_remove_loop() {
+    let mut tags = container_with_names();
+    assert!(tags.loops().is_empty());
+
+    tags.set_loop(Loop {
+        index: 0,
+        start_position: Position { millis: 1000 },
+        end_position: Position { millis: 3000 },
+        color: LOOP_COLOR,
+        is_locked: true,
+        label: "Intro".to_string(),
+    })
+    .unwrap();
+    tags.set_loop(Loop {
+        index: 3,
+        start_position: Position { millis: 4000 },
+        end_position: Position { millis: 5000 },
+        color: LOOP_COLOR,
+        is_locked: false,
+        label: String::new(),
+    })
+    .unwrap();
+    tags.remove_loop(3);
+
+    let tags = roundtrip(&tags);
+    let loops = tags.loops();
+    assert_eq!(loops.len(), 1);
+    assert_eq!(loops[0].index, 0);
+    assert_eq!(loops[0].start_position, Position { millis: 1000 });
+    assert_eq!(loops[0].end_position, Position { millis: 3000 });
+    assert!(loops[0].is_locked);
+    assert_eq!(loops[0].label, "Intro");
+}
+
+#[test]
+fn test_set_track_color_and_bpm_lock() {
+    let mut tags = container_with_names();
+    let color = Color {
+        red: 0x99,
+        green: 0x99,
+        blue: 0xFF,
+    };
+    tags.set_track_color(color).unwrap();
+    tags.set_bpm_locked(true).unwrap();
+
+    let tags = roundtrip(&tags);
+    assert_eq!(tags.track_color(), Some(color));
+    assert_eq!(tags.bpm_locked(), Some(true));
+}
+
+#[test]
+fn test_edit_without_tags() {
+    let mut tags = TagContainer::new();
+    assert!(tags.set_track_color(RED).is_err());
+    assert!(tags.set_bpm_locked(true).is_err());
+    assert!(
+        tags.set_cue(Cue {
+            index: 0,
+            position: Position::default(),
+            color: RED,
+            label: String::new(),
+        })
+        .is_err()
+    );
+}
+
+#[test]
+fn test_markers2_ogg_roundtrip() {
+    let data = include_bytes!("data/tags/markers2/hotcues_and_loops.ogg.bin");
+    let mut tags = TagContainer::new();
+    tags.parse_markers2(data, TagFormat::Ogg).unwrap();
+    let mut writer = Cursor::new(vec![]);
+    tags.write_markers2(&mut writer, TagFormat::Ogg).unwrap();
+    assert_eq!(writer.get_ref().as_slice(), &data[..]);
+
+    let mut tags = TagContainer::with_empty_tags(TagFormat::Ogg);
+    tags.set_cue(Cue {
+        index: 0,
+        position: Position { millis: 1234 },
+        color: RED,
+        label: "Drop".to_string(),
+    })
+    .unwrap();
+    let mut writer = Cursor::new(vec![]);
+    tags.write_markers2(&mut writer, TagFormat::Ogg).unwrap();
+
+    let mut output = TagContainer::new();
+    output
+        .parse_markers2(writer.get_ref(), TagFormat::Ogg)
+        .unwrap();
+    let cues = output.cues();
+    assert_eq!(cues.len(), 1);
+    assert_eq!(cues[0].position, Position { millis: 1234 });
+    assert_eq!(cues[0].label, "Drop");
+}
+
+#[test]
+fn test_relvolad_roundtrip() {
+    let mut tags = TagContainer::new();
+    let mut writer = Cursor::new(vec![]);
+    assert!(tags.write_relvolad(&mut writer, TagFormat::MP4).is_err());
+
+    let data = include_bytes!("data/tags/relvolad/analyzed.mp4.bin");
+    tags.parse_relvolad(data, TagFormat::MP4).unwrap();
+    tags.write_relvolad(&mut writer, TagFormat::MP4).unwrap();
+    assert_eq!(writer.get_ref().as_slice(), &data[..]);
+
+    assert!(tags.parse_relvolad(data, TagFormat::ID3).is_err());
+}
+
+#[test]
+fn test_video_path() {
+    let mut tags = TagContainer::new();
+    assert_eq!(tags.video_path(), None);
+
+    // None of the samples has a video associated.
+    let samples: [(&[u8], TagFormat); 5] = [
+        (
+            include_bytes!("data/tags/vidassoc/analyzed_enc_AA.flac.bin"),
+            TagFormat::FLAC,
+        ),
+        (
+            include_bytes!("data/tags/vidassoc/analyzed_enc_AR.flac.bin"),
+            TagFormat::FLAC,
+        ),
+        (
+            include_bytes!("data/tags/vidassoc/analyzed_enc_BQ.mp4.bin"),
+            TagFormat::MP4,
+        ),
+        (
+            include_bytes!("data/tags/vidassoc/analyzed_enc_CH.mp4.bin"),
+            TagFormat::MP4,
+        ),
+        (
+            include_bytes!("data/tags/vidassoc/analyzed_enc_CJ.mp4.bin"),
+            TagFormat::MP4,
+        ),
+    ];
+    for (data, tag_format) in samples {
+        tags.parse_vidassoc(data, tag_format).unwrap();
+        assert_eq!(tags.video_path(), None);
+    }
+}
@@ -0,0 +1,230 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+#![cfg(feature = "enginedj")]
+
+mod common;
+
+use common::{TempDir, container_with_cues_and_loops, copy_library_database, usb_drive_library};
+use flate2::read::ZlibDecoder;
+use rusqlite::Connection;
+use rusqlite::types::FromSql;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use triseratops::convert::enginedj::EngineDatabase;
+use triseratops::library::Library;
+use triseratops::tag::{TagContainer, TagFormat};
+
+const TRACK_PATH: &str = "CASSIUS_-_99_Keller 2016 RE-EDIT -.mp3";
+
+fn container_with_performance_data() -> TagContainer {
+    let mut tags = container_with_cues_and_loops();
+    tags.parse_overview(
+        include_bytes!("data/tags/overview/analyzed.mp4.bin"),
+        TagFormat::MP4,
+    )
+    .unwrap();
+    tags
+}
+
+/// Creates a drive with the Serato database of the `usb_drive` library and an empty Engine DJ
+/// database, and returns the path of the drive.
+fn create_drive(name: &str) -> TempDir {
+    let path = copy_library_database(name, "usb_drive");
+    std::fs::create_dir_all(path.join("Engine Library/Database2")).unwrap();
+    let connection = Connection::open(path.join("Engine Library/Database2/m.db")).unwrap();
+    connection
+        .execute_batch(include_str!("data/enginedj/schema.sql"))
+        .unwrap();
+    path
+}
+
+fn write_library(path: &Path) -> Connection {
+    write_library_with_tags(path, container_with_performance_data())
+}
+
+fn write_library_with_tags(path: &Path, tags: TagContainer) -> Connection {
+    let mut library = Library::read_from_path(path).unwrap();
+    library.set_volume_root(path);
+    let tags = HashMap::from([(PathBuf::from(TRACK_PATH), tags)]);
+    let mut database = EngineDatabase::open(path.join("Engine Library")).unwrap();
+    database.write_library(&library, &tags).unwrap();
+    Connection::open(path.join("Engine Library/Database2/m.db")).unwrap()
+}
+
+/// Returns the value of a column of the track with the given path.
+fn column<T: FromSql>(connection: &Connection, path: &str, column: &str) -> T {
+    connection
+        .query_row(
+            &format!("SELECT {column} FROM Track WHERE path = ?1"),
+            [path],
+            |row| row.get(0),
+        )
+        .unwrap()
+}
+
+fn decompress(blob: &[u8]) -> Vec<u8> {
+    let (length, data) = blob.split_at(4);
+    let mut output = vec![];
+    ZlibDecoder::new(data).read_to_end(&mut output).unwrap();
+    assert_eq!(
+        output.len(),
+        u32::from_be_bytes(length.try_into().unwrap()) as usize
+    );
+    output
+}
+
+/// Returns the labels and sample positions of the hot cues in the `quickCues` blob.
+fn quick_cues(data: &[u8]) -> Vec<(String, f64)> {
+    let count = i64::from_be_bytes(data[..8].try_into().unwrap());
+    let mut data = &data[8..];
+    let mut cues = vec![];
+    for _ in 0..count {
+        let length = usize::from(data[0]);
+        let label = String::from_utf8(data[1..=length].to_vec()).unwrap();
+        data = &data[1 + length..];
+        let position = f64::from_be_bytes(data[..8].try_into().unwrap());
+        data = &data[12..];
+        cues.push((label, position));
+    }
+    // The main cue.
+    assert_eq!(data.len(), 17);
+    cues
+}
+
+#[test]
+fn test_write_library() {
+    let path = create_drive("enginedj-export");
+    let connection = write_library(&path);
+
+    let count: i64 = connection
+        .query_row("SELECT COUNT(*) FROM Track", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(count, 4);
+    let path_of = |filename: &str| format!("../{filename}");
+    let other_path = path_of("Pete Heller - Big Love (Vaudafunk 2019 Reinterpretation).mp3");
+    assert_eq!(
+        column::<String>(&connection, &other_path, "filename"),
+        "Pete Heller - Big Love (Vaudafunk 2019 Reinterpretation).mp3"
+    );
+    assert_eq!(
+        column::<String>(&connection, &other_path, "title"),
+        "Big Love (Vaudafunk 2019 Reinterpretation)"
+    );
+    assert_eq!(column::<i64>(&connection, &other_path, "length"), 439);
+    assert_eq!(column::<i64>(&connection, &other_path, "bpm"), 123);
+    assert_eq!(
+        column::<String>(&connection, &other_path, "originDatabaseUuid"),
+        "8c3a1b0e-5e43-4f5b-9b2e-0d6c1f0a7e21"
+    );
+    assert_eq!(
+        column::<i64>(&connection, &other_path, "originTrackId"),
+        column::<i64>(&connection, &other_path, "id")
+    );
+
+    // Tracks without tags have no performance data.
+    assert_eq!(
+        column::<Option<Vec<u8>>>(&connection, &other_path, "quickCues"),
+        None
+    );
+
+    let track_path = path_of(TRACK_PATH);
+    assert!(column::<bool>(&connection, &track_path, "isAnalyzed"));
+    assert!((column::<f64>(&connection, &track_path, "bpmAnalyzed") - 120.0).abs() < 1e-6);
+    let track_data = column::<Vec<u8>>(&connection, &track_path, "trackData");
+    let quick_cue_data = column::<Vec<u8>>(&connection, &track_path, "quickCues");
+    let loops = column::<Vec<u8>>(&connection, &track_path, "loops");
+    let beat_data = column::<Vec<u8>>(&connection, &track_path, "beatData");
+    let overview = column::<Vec<u8>>(&connection, &track_path, "overviewWaveFormData");
+
+    let track_data = decompress(&track_data);
+    assert_eq!(track_data.len(), 28);
+    let sample_rate = f64::from_be_bytes(track_data[..8].try_into().unwrap());
+    assert!(sample_rate > 0.0);
+
+    let cues = quick_cues(&decompress(&quick_cue_data));
+    assert_eq!(cues.len(), 8);
+    assert_eq!(cues[1].0, "After Intro");
+    assert_eq!(cues[1].1, (21.124 * sample_rate).round());
+    assert_eq!(
+        cues.iter().filter(|(_, position)| *position >= 0.0).count(),
+        6
+    );
+
+    // The loops are not compressed.
+    assert_eq!(loops[..8], 8i64.to_le_bytes());
+    // The first saved loop has no label.
+    assert_eq!(loops[8], 0);
+    assert_eq!(loops[9..17], (0.254 * sample_rate).round().to_le_bytes());
+    assert_eq!(loops[17..25], (2.341 * sample_rate).round().to_le_bytes());
+    assert_eq!(loops[25..28], [1, 1, 0xFF]);
+
+    let beat_data = decompress(&beat_data);
+    assert_eq!(beat_data[..8], sample_rate.to_be_bytes());
+    assert_eq!(beat_data[16], 1);
+    assert_eq!(beat_data[17..25], 3i64.to_be_bytes());
+    assert_eq!(beat_data.len(), 17 + 2 * (8 + 3 * 24));
+
+    let overview = decompress(&overview);
+    assert_eq!(overview[..8], 1024i64.to_be_bytes());
+    assert_eq!(overview.len(), 24 + 3 * 1024 + 3);
+    assert!(overview[24..].iter().any(|value| *value > 0));
+}
+
+#[test]
+fn test_write_existing_tracks() {
+    let path = create_drive("enginedj-existing");
+    let connection = Connection::open(path.join("Engine Library/Database2/m.db")).unwrap();
+    connection
+        .execute(
+            &format!(
+                "INSERT INTO Track (id, path, title, trackData) \
+                 VALUES (1, '../{TRACK_PATH}', 'Existing Title', X'00')"
+            ),
+            [],
+        )
+        .unwrap();
+    drop(connection);
+
+    write_library(&path);
+    // Writing the library again doesn't duplicate anything.
+    let connection = write_library(&path);
+
+    let count: i64 = connection
+        .query_row("SELECT COUNT(*) FROM Track", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(count as usize, usb_drive_library().tracks().count());
+    // Existing tracks keep their metadata and analysis data.
+    let track_path = format!("../{TRACK_PATH}");
+    assert_eq!(column::<i64>(&connection, &track_path, "id"), 1);
+    assert_eq!(
+        column::<String>(&connection, &track_path, "title"),
+        "Existing Title"
+    );
+    assert_eq!(
+        column::<Vec<u8>>(&connection, &track_path, "trackData"),
+        [0]
+    );
+    assert!(column::<Option<Vec<u8>>>(&connection, &track_path, "quickCues").is_some());
+}
+
+#[test]
+fn test_write_library_without_overview() {
+    let path = create_drive("enginedj-without-overview");
+    let connection = write_library_with_tags(&path, container_with_cues_and_loops());
+
+    // The beats are written, but Engine DJ still has to analyze the waveform.
+    let track_path = format!("../{TRACK_PATH}");
+    assert!(column::<Option<Vec<u8>>>(&connection, &track_path, "beatData").is_some());
+    assert_eq!(
+        column::<Option<Vec<u8>>>(&connection, &track_path, "overviewWaveFormData"),
+        None
+    );
+    assert!(!column::<bool>(&connection, &track_path, "isAnalyzed"));
+}
@@ -0,0 +1,331 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+#![cfg(feature = "mixxx")]
+
+mod common;
+
+use common::{container_with_cues_and_loops, copy_library_database, usb_drive_library};
+use rusqlite::Connection;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use triseratops::convert::mixxx::{Beats, CueType, MixxxDatabase};
+use triseratops::library::Library;
+use triseratops::tag::TagFormat;
+use triseratops::tag::color::Color;
+use triseratops::tag::generic::{Cue, Loop};
+
+const TRACK_PATH: &str = "CASSIUS_-_99_Keller 2016 RE-EDIT -.mp3";
+
+fn create_connection() -> Connection {
+    let connection = Connection::open_in_memory().unwrap();
+    connection
+        .pragma_update(None, "foreign_keys", false)
+        .unwrap();
+    connection
+        .execute_batch(include_str!("data/mixxx/schema.sql"))
+        .unwrap();
+    connection
+}
+
+fn write_library(connection: Connection, library: &Library) -> MixxxDatabase {
+    let tags = HashMap::from([(PathBuf::from(TRACK_PATH), container_with_cues_and_loops())]);
+    let mut database = MixxxDatabase::from_connection(connection);
+    database.write_library(library, &tags).unwrap();
+    database
+}
+
+#[test]
+fn test_write_library() {
+    let library = usb_drive_library();
+    let location = library.absolute_path(Path::new(TRACK_PATH));
+
+    // An existing track with a main cue and an outdated hot cue.
+    let connection = create_connection();
+    connection
+        .execute_batch(&format!(
+            "INSERT INTO track_locations (id, location, filename) VALUES (1, '{}', '{TRACK_PATH}');
+             INSERT INTO library (id, title, location, samplerate, header_parsed)
+             VALUES (1, 'Existing Title', 1, 44100, 1);
+             INSERT INTO cues (track_id, type, position, hotcue) VALUES (1, 2, 1000, -1);
+             INSERT INTO cues (track_id, type, position, hotcue) VALUES (1, 1, 2000, 3);",
+            location.display()
+        ))
+        .unwrap();
+    let database = write_library(connection, &library);
+
+    let tracks = database.tracks().unwrap();
+    assert_eq!(tracks.len(), 4);
+    let track = tracks
+        .iter()
+        .find(|track| track.location == location)
+        .unwrap();
+    assert_eq!(track.id, 1);
+    // Existing tracks keep their metadata.
+    assert_eq!(track.title.as_deref(), Some("Existing Title"));
+
+    // The main cue is kept, the hot cues and saved loops are replaced.
+    let cue_types: Vec<_> = track.cues.iter().map(|cue| cue.cue_type).collect();
+    assert_eq!(
+        cue_types.iter().filter(|t| **t == CueType::MainCue).count(),
+        1
+    );
+    assert_eq!(
+        cue_types.iter().filter(|t| **t == CueType::HotCue).count(),
+        6
+    );
+    assert_eq!(cue_types.iter().filter(|t| **t == CueType::Loop).count(), 3);
+    assert!(
+        !track
+            .cues
+            .iter()
+            .any(|cue| cue.cue_type == CueType::HotCue && cue.position == 2000.0)
+    );
+    let sample_rate = f64::from(track.sample_rate.unwrap());
+    let after_intro = track
+        .cues
+        .iter()
+        .find(|cue| cue.label == "After Intro")
+        .unwrap();
+    assert_eq!(after_intro.cue_type, CueType::HotCue);
+    assert_eq!(after_intro.hotcue, 1);
+    assert_eq!(after_intro.position, (21.124 * sample_rate).round() * 2.0);
+    // Cues use the Serato DJ Pro colors.
+    assert_eq!(after_intro.color, Some(Color::from(0xF8821A)));
+    let electric_guitar = track
+        .cues
+        .iter()
+        .find(|cue| cue.label == "Electric Guitar")
+        .unwrap();
+    assert_eq!(electric_guitar.cue_type, CueType::Loop);
+    assert!(electric_guitar.hotcue >= 8);
+    assert_eq!(
+        electric_guitar.length,
+        ((108.776 * sample_rate).round() - (100.428 * sample_rate).round()) * 2.0
+    );
+
+    // Beatgrids with multiple markers are exported as a list of beats (in frames).
+    let Some(Beats::Map(positions)) = &track.beats else {
+        panic!("Expected a beat map, got {:?}", track.beats);
+    };
+    assert!(positions.contains(&((0.5 * sample_rate).round() as i32)));
+    assert!(positions.contains(&((3.5 * sample_rate).round() as i32)));
+
+    // New tracks are added without cues.
+    let track = tracks
+        .iter()
+        .find(|track| track.title.as_deref() == Some("Big Love (Vaudafunk 2019 Reinterpretation)"))
+        .unwrap();
+    assert!(track.cues.is_empty());
+    assert!(track.beats.is_none());
+
+    let crates = database.crates().unwrap();
+    let names: Vec<_> = crates.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["80s Mashup", "French House"]);
+    assert_eq!(crates[1].track_ids.len(), 2);
+    assert!(crates[1].track_ids.contains(&1));
+
+    // Writing the library again doesn't duplicate anything.
+    let mut database = database;
+    let tags = HashMap::from([(PathBuf::from(TRACK_PATH), container_with_cues_and_loops())]);
+    database.write_library(&library, &tags).unwrap();
+    let tracks = database.tracks().unwrap();
+    assert_eq!(tracks.len(), 4);
+    let track = tracks.iter().find(|track| track.id == 1).unwrap();
+    assert_eq!(track.cues.len(), 10);
+    assert_eq!(database.crates().unwrap(), crates);
+}
+
+#[test]
+fn test_read_tracks() {
+    let connection = create_connection();
+    let beats = Beats::Grid {
+        bpm: 124.0,
+        first_beat: 2205,
+    };
+    connection
+        .execute_batch(
+            "INSERT INTO track_locations (id, location) VALUES
+                (1, '/music/Synthetic Artist - Synthetic Title.mp3'),
+                (2, '/music/Other Title.mp3'),
+                (3, '/music/Deleted Title.mp3');
+             INSERT INTO library (id, artist, title, location, samplerate, mixxx_deleted) VALUES
+                (1, 'Synthetic Artist', 'Synthetic Title', 1, 44100, 0),
+                (2, NULL, 'Other Title', 2, 48000, 0),
+                (3, NULL, 'Deleted Title', 3, 44100, 1);
+             INSERT INTO cues (track_id, type, position, length, hotcue, label, color) VALUES
+                (1, 2, 0, 0, -1, '', 16711680),
+                (1, 1, 8820, 0, 0, 'Intro', 16711680),
+                (1, 1, 441000, 0, 12, 'Drop', 65280),
+                (1, 4, 1411200, 705600, 9, 'Hot Loop', 255);",
+        )
+        .unwrap();
+    connection
+        .execute(
+            "UPDATE library SET beats = ?1, beats_version = ?2 WHERE id = 1",
+            (beats.to_bytes(), beats.version()),
+        )
+        .unwrap();
+    let database = MixxxDatabase::from_connection(connection);
+
+    let tracks = database.tracks().unwrap();
+    assert_eq!(tracks.len(), 2);
+    let track = &tracks[0];
+    assert_eq!(
+        track.location,
+        Path::new("/music/Synthetic Artist - Synthetic Title.mp3")
+    );
+    assert_eq!(track.artist.as_deref(), Some("Synthetic Artist"));
+    assert_eq!(track.beats, Some(beats));
+    assert_eq!(track.cues.len(), 4);
+
+    // The main cue is ignored, the hot cue 13 is moved to the first free slot.
+    let cues: Vec<_> = track
+        .cues()
+        .into_iter()
+        .map(|cue| (cue.index, cue.position.millis, cue.label))
+        .collect();
+    assert_eq!(
+        cues,
+        [(0, 100, "Intro".to_string()), (1, 5000, "Drop".to_string())]
+    );
+    assert_eq!(
+        track.cues()[0].color,
+        Color::from(0xFF0000).into_nearest_hotcue_color()
+    );
+
+    let loops: Vec<_> = track
+        .loops()
+        .into_iter()
+        .map(|saved_loop| {
+            (
+                saved_loop.index,
+                saved_loop.start_position.millis,
+                saved_loop.end_position.millis,
+                saved_loop.label,
+            )
+        })
+        .collect();
+    assert_eq!(loops, [(0, 16000, 24000, "Hot Loop".to_string())]);
+
+    let beatgrid = track.beatgrid().unwrap();
+    assert!(beatgrid.non_terminal_markers.is_empty());
+    assert!((beatgrid.terminal_marker.position - 0.05).abs() < 1e-6);
+    assert!((beatgrid.terminal_marker.bpm - 124.0).abs() < 1e-6);
+
+    let tags = track.to_tag_container(TagFormat::ID3).unwrap();
+    assert_eq!(tags.cues().len(), 2);
+    assert_eq!(tags.loops().len(), 1);
+    assert!(tags.beatgrid().is_some());
+
+    // Tracks without beats have no beatgrid.
+    assert!(tracks[1].beatgrid().is_none());
+    assert!(tracks[1].cues().is_empty());
+}
+
+#[test]
+fn test_write_subcrates() {
+    let path = copy_library_database("mixxx-import", "synthetic");
+    let mut library = Library::read_from_path(&*path).unwrap();
+    library.set_volume_root("/Volumes/USB Drive");
+
+    let connection = create_connection();
+    connection
+        .execute_batch(
+            "INSERT INTO track_locations (id, location) VALUES
+                (1, '/Volumes/USB Drive/Music/Synthetic Artist - Synthetic Title.mp3'),
+                (2, '/Volumes/USB Drive/Music/Other Title.mp3'),
+                (3, '/Music/Track.mp3');
+             INSERT INTO library (id, title, location) VALUES
+                (1, 'Synthetic Title', 1), (2, 'Other Title', 2), (3, 'Track', 3);
+             INSERT INTO crates (id, name) VALUES (1, 'Gigs / Club'), (2, 'Warmup');
+             INSERT INTO crate_tracks (crate_id, track_id) VALUES (1, 1), (2, 1), (2, 3);
+             INSERT INTO Playlists (id, name, position, hidden) VALUES
+                (1, 'Auto DJ', 1, 1), (2, 'Set', 2, 0);
+             INSERT INTO PlaylistTracks (playlist_id, track_id, position) VALUES
+                (1, 1, 1), (2, 1, 2), (2, 2, 1);",
+        )
+        .unwrap();
+    let database = MixxxDatabase::from_connection(connection);
+
+    let crates = database.crates().unwrap();
+    assert_eq!(crates.len(), 2);
+    assert_eq!(crates[1].track_ids, [1, 3]);
+    let playlists = database.playlists().unwrap();
+    assert_eq!(playlists.len(), 1);
+    assert_eq!(playlists[0].name, "Set");
+    assert_eq!(playlists[0].track_ids, [2, 1]);
+
+    let names = database.write_subcrates(&library).unwrap();
+    assert_eq!(names, ["Gigs%%Club", "Warmup", "Set"]);
+
+    let mut subcrates: Vec<_> = library.subcrates().collect();
+    subcrates.sort();
+    assert_eq!(subcrates, ["Gigs", "Gigs%%Club", "Set", "Warmup"]);
+    // Tracks on other volumes are skipped.
+    let subcrate = library.read_subcrate("Warmup").unwrap();
+    let track_paths: Vec<_> = subcrate.track_paths().collect();
+    assert_eq!(
+        track_paths,
+        [Path::new("Music/Synthetic Artist - Synthetic Title.mp3")]
+    );
+    let subcrate = library.read_subcrate("Set").unwrap();
+    let track_paths: Vec<_> = subcrate.track_paths().collect();
+    assert_eq!(
+        track_paths,
+        [
+            Path::new("Music/Other Title.mp3"),
+            Path::new("Music/Synthetic Artist - Synthetic Title.mp3")
+        ]
+    );
+}
+
+#[test]
+fn test_roundtrip() {
+    let library = usb_drive_library();
+    let tags = container_with_cues_and_loops();
+    let database = write_library(create_connection(), &library);
+
+    let location = library.absolute_path(Path::new(TRACK_PATH));
+    let tracks = database.tracks().unwrap();
+    let track = tracks
+        .iter()
+        .find(|track| track.location == location)
+        .unwrap();
+
+    let cues = |cues: Vec<Cue>| -> Vec<_> {
+        cues.into_iter()
+            .map(|cue| (cue.index, cue.position.millis, cue.label, cue.color))
+            .collect()
+    };
+    assert_eq!(cues(track.cues()), cues(tags.cues()));
+
+    // Saved loops keep their order, but are moved to consecutive slots.
+    let loops = |loops: Vec<Loop>| -> Vec<_> {
+        loops
+            .into_iter()
+            .map(|saved_loop| {
+                (
+                    saved_loop.start_position.millis,
+                    saved_loop.end_position.millis,
+                    saved_loop.label,
+                )
+            })
+            .collect()
+    };
+    assert_eq!(loops(track.loops()), loops(tags.loops()));
+
+    // The exported beats start before the first marker, so the first marker is moved to the
+    // first beat of the track.
+    let beatgrid = track.beatgrid().unwrap();
+    assert_eq!(beatgrid.non_terminal_markers.len(), 1);
+    assert!(beatgrid.non_terminal_markers[0].position.abs() < 1e-3);
+    assert_eq!(beatgrid.non_terminal_markers[0].beats_till_next_marker, 7);
+    assert!((beatgrid.terminal_marker.position - 3.5).abs() < 1e-3);
+    assert!((beatgrid.terminal_marker.bpm - 128.0).abs() < 1e-2);
+}
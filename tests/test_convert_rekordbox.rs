@@ -0,0 +1,324 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+#![cfg(feature = "rekordbox")]
+
+mod common;
+
+use common::{container_with_cues_and_loops, copy_library_database, usb_drive_library};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use triseratops::convert::rekordbox::{self, Playlist, PositionMarkType, RekordboxXml};
+use triseratops::library::Library;
+use triseratops::tag::TagFormat;
+use triseratops::tag::generic::{Cue, Loop};
+
+#[test]
+fn test_write_xml() {
+    let library = usb_drive_library();
+    let tags = HashMap::from([(
+        PathBuf::from("CASSIUS_-_99_Keller 2016 RE-EDIT -.mp3"),
+        container_with_cues_and_loops(),
+    )]);
+    let mut output = vec![];
+    rekordbox::write_xml(&mut output, &library, &tags).unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    assert!(output.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<DJ_PLAYLISTS"));
+    assert!(output.contains("<COLLECTION Entries=\"4\">"));
+    assert!(output.contains(
+        "<TRACK TrackID=\"4\" Name=\"Big Love (Vaudafunk 2019 Reinterpretation)\" \
+         Artist=\"Pete Heller\" Comments=\"vaudafunk@gmail.com\" Tonality=\"Am\" Year=\"2019\" \
+         Kind=\"MP3 File\" Size=\"19391415\" TotalTime=\"439\" AverageBpm=\"123.00\" \
+         DateAdded=\"2020-02-06\" BitRate=\"320\" SampleRate=\"44100\" \
+         Location=\"file://localhost/"
+    ));
+
+    // Beatgrid
+    assert!(
+        output.contains("<TEMPO Inizio=\"0.500\" Bpm=\"120.00\" Metro=\"4/4\" Battito=\"1\"/>")
+    );
+    assert!(
+        output.contains("<TEMPO Inizio=\"3.500\" Bpm=\"128.00\" Metro=\"4/4\" Battito=\"3\"/>")
+    );
+
+    // Cues use the Serato DJ Pro colors.
+    assert_eq!(output.matches("Type=\"0\" Start=").count(), 6);
+    assert!(output.contains(
+        "<POSITION_MARK Name=\"After Intro\" Type=\"0\" Start=\"21.124\" Num=\"1\" Red=\"248\" \
+         Green=\"130\" Blue=\"26\"/>"
+    ));
+
+    // Saved loops are exported as memory loops.
+    assert_eq!(output.matches("Type=\"4\"").count(), 3);
+    assert!(output.contains(
+        "<POSITION_MARK Name=\"Electric Guitar\" Type=\"4\" Start=\"100.428\" End=\"108.776\" \
+         Num=\"-1\"/>"
+    ));
+
+    // Subcrates
+    assert!(output.contains("<NODE Type=\"0\" Name=\"ROOT\" Count=\"2\">"));
+    assert!(output.contains(
+        "<NODE Name=\"French House\" Type=\"1\" KeyType=\"0\" Entries=\"2\">\n        \
+         <TRACK Key=\"1\"/>\n        <TRACK Key=\"2\"/>"
+    ));
+}
+
+#[test]
+fn test_write_xml_nested_subcrates() {
+    let path = copy_library_database("rekordbox-nested", "usb_drive");
+    let library = Library::read_from_path(&*path).unwrap();
+    let mut subcrate = library.create_subcrate_by_path(&["House", "Deep"]).unwrap();
+    subcrate.add_track(PathBuf::from("CASSIUS_-_99_Keller 2016 RE-EDIT -.mp3"));
+    library.write_subcrate("House%%Deep", &subcrate).unwrap();
+
+    let mut output = vec![];
+    rekordbox::write_xml(&mut output, &library, &HashMap::new()).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains(
+        "<NODE Type=\"0\" Name=\"House\" Count=\"2\">\n        \
+         <NODE Name=\"House\" Type=\"1\" KeyType=\"0\" Entries=\"0\">\n        \
+         </NODE>\n        \
+         <NODE Name=\"Deep\" Type=\"1\" KeyType=\"0\" Entries=\"1\">\n          \
+         <TRACK Key=\"2\"/>"
+    ));
+}
+
+#[test]
+fn test_parse_xml_high_hotcue_numbers() {
+    let xml = RekordboxXml::parse(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<DJ_PLAYLISTS Version="1.0.0">
+  <COLLECTION Entries="1">
+    <TRACK TrackID="1" Location="file://localhost/Music/Synthetic.mp3">
+      <POSITION_MARK Name="First" Type="0" Start="1.000" Num="0"/>
+      <POSITION_MARK Name="Ninth" Type="0" Start="3.000" Num="8"/>
+      <POSITION_MARK Name="Tenth" Type="0" Start="2.000" Num="9"/>
+    </TRACK>
+  </COLLECTION>
+</DJ_PLAYLISTS>
+"#
+        .as_bytes(),
+    )
+    .unwrap();
+    let cues: Vec<_> = xml.tracks[0]
+        .cues()
+        .into_iter()
+        .map(|cue| (cue.index, cue.position.millis, cue.label))
+        .collect();
+    // Hot cues in slots that Serato DJ doesn't have are moved to the free slots.
+    assert_eq!(
+        cues,
+        [
+            (0, 1000, "First".to_string()),
+            (1, 2000, "Tenth".to_string()),
+            (2, 3000, "Ninth".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_xml() {
+    let xml = RekordboxXml::read_from_path("tests/data/rekordbox/synthetic.xml").unwrap();
+    assert_eq!(xml.tracks.len(), 2);
+    assert!(xml.track("999").is_none());
+    // The lookup also works if the tracks have been changed after parsing.
+    let mut reversed = xml.clone();
+    reversed.tracks.reverse();
+    assert_eq!(reversed.track("101").unwrap().track_id, "101");
+
+    let track = xml.track("101").unwrap();
+    assert_eq!(
+        track.location,
+        Path::new("/Music/Synthetic Artist - Synthetic Title.mp3")
+    );
+    assert_eq!(track.title.as_deref(), Some("Synthetic Title"));
+    assert_eq!(track.tempos.len(), 2);
+    assert_eq!(track.position_marks.len(), 9);
+    assert_eq!(track.position_marks[5].mark_type, PositionMarkType::Loop);
+    assert_eq!(track.position_marks[5].num, 1);
+
+    let cues: Vec<_> = track
+        .cues()
+        .into_iter()
+        .map(|cue| {
+            (
+                cue.index,
+                cue.position.millis,
+                cue.label,
+                u32::from(cue.color),
+            )
+        })
+        .collect();
+    assert_eq!(
+        cues,
+        [
+            (0, 100, "Intro".to_string(), 0xCC0088),
+            // The memory cue is moved to the first free slot and gets the default color.
+            (1, 90250, "Break".to_string(), 0xCC8800),
+            (2, 62500, "Drop".to_string(), 0x0088CC),
+        ]
+    );
+
+    let loops: Vec<_> = track
+        .loops()
+        .into_iter()
+        .map(|saved_loop| {
+            (
+                saved_loop.index,
+                saved_loop.start_position.millis,
+                saved_loop.end_position.millis,
+                saved_loop.label,
+            )
+        })
+        .collect();
+    assert_eq!(
+        loops,
+        [
+            (0, 16000, 23742, "Hot Loop".to_string()),
+            (1, 180_000, 187_619, "Outro Loop".to_string()),
+        ]
+    );
+
+    let beatgrid = track.beatgrid().unwrap();
+    assert_eq!(beatgrid.non_terminal_markers.len(), 1);
+    assert_eq!(beatgrid.non_terminal_markers[0].beats_till_next_marker, 64);
+    assert!((beatgrid.terminal_marker.position - 31.068).abs() < 1e-6);
+    assert!((beatgrid.terminal_marker.bpm - 126.0).abs() < 1e-6);
+
+    let tags = track.to_tag_container(TagFormat::ID3).unwrap();
+    assert_eq!(tags.cues().len(), 3);
+    assert_eq!(tags.loops().len(), 2);
+    assert!(tags.beatgrid().is_some());
+
+    assert_eq!(
+        xml.playlists,
+        [
+            Playlist {
+                path: vec!["Warmup".to_string()],
+                track_ids: vec!["102".to_string(), "101".to_string()],
+            },
+            Playlist {
+                path: vec!["Gigs".to_string(), "Club / Late".to_string()],
+                track_ids: vec!["101".to_string()],
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_write_subcrates() {
+    let path = copy_library_database("rekordbox-import", "synthetic");
+    let mut library = Library::read_from_path(&*path).unwrap();
+    library.set_volume_root("/");
+
+    let xml = RekordboxXml::read_from_path("tests/data/rekordbox/synthetic.xml").unwrap();
+    let names = xml.write_subcrates(&library).unwrap();
+    assert_eq!(names, ["Warmup", "Gigs%%Club - Late"]);
+
+    let mut subcrates: Vec<_> = library.subcrates().collect();
+    subcrates.sort();
+    assert_eq!(subcrates, ["Gigs", "Gigs%%Club - Late", "Warmup"]);
+    let subcrate = library.read_subcrate("Warmup").unwrap();
+    let track_paths: Vec<_> = subcrate.track_paths().collect();
+    assert_eq!(
+        track_paths,
+        [
+            Path::new("Music/Other Title.mp3"),
+            Path::new("Music/Synthetic Artist - Synthetic Title.mp3")
+        ]
+    );
+    let tracks: Vec<_> = library.subcrate("Gigs%%Club - Late").unwrap().collect();
+    assert_eq!(tracks.len(), 1);
+    assert_eq!(tracks[0].title.as_deref(), Some("Synthetic Title"));
+
+    // Existing crates are not replaced.
+    let mut xml = xml;
+    xml.playlists[0].track_ids.clear();
+    let names = xml.write_subcrates(&library).unwrap();
+    assert_eq!(names, ["Warmup (2)", "Gigs%%Club - Late (2)"]);
+    assert_eq!(
+        library
+            .read_subcrate("Warmup")
+            .unwrap()
+            .track_paths()
+            .count(),
+        2
+    );
+
+    // Playlists with the same name don't replace each other.
+    xml.playlists[1].path = xml.playlists[0].path.clone();
+    let names = xml.write_subcrates(&library).unwrap();
+    assert_eq!(names, ["Warmup (3)", "Warmup (4)"]);
+    assert_eq!(
+        library
+            .read_subcrate("Warmup (4)")
+            .unwrap()
+            .track_paths()
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn test_roundtrip() {
+    let library = usb_drive_library();
+    let tags = container_with_cues_and_loops();
+    let track_path = PathBuf::from("CASSIUS_-_99_Keller 2016 RE-EDIT -.mp3");
+    let tags_by_path = HashMap::from([(track_path.clone(), tags.clone())]);
+    let mut output = vec![];
+    rekordbox::write_xml(&mut output, &library, &tags_by_path).unwrap();
+
+    let xml = RekordboxXml::parse(output.as_slice()).unwrap();
+    assert_eq!(xml.tracks.len(), 4);
+    let track = xml
+        .tracks
+        .iter()
+        .find(|track| track.location == library.absolute_path(&track_path))
+        .unwrap();
+
+    let cues = |cues: Vec<Cue>| -> Vec<_> {
+        cues.into_iter()
+            .map(|cue| (cue.index, cue.position.millis, cue.label, cue.color))
+            .collect()
+    };
+    assert_eq!(cues(track.cues()), cues(tags.cues()));
+
+    let loops = |loops: Vec<Loop>| -> Vec<_> {
+        let mut loops: Vec<_> = loops
+            .into_iter()
+            .map(|saved_loop| {
+                (
+                    saved_loop.start_position.millis,
+                    saved_loop.end_position.millis,
+                    saved_loop.label,
+                )
+            })
+            .collect();
+        loops.sort();
+        loops
+    };
+    assert_eq!(loops(track.loops()), loops(tags.loops()));
+
+    let beatgrid = track.beatgrid().unwrap();
+    let (non_terminal_markers, terminal_marker) = tags.beatgrid().unwrap();
+    assert_eq!(
+        beatgrid.non_terminal_markers.len(),
+        non_terminal_markers.len()
+    );
+    assert_eq!(
+        beatgrid.non_terminal_markers[0].beats_till_next_marker,
+        non_terminal_markers[0].beats_till_next_marker
+    );
+    assert!((beatgrid.terminal_marker.position - terminal_marker.position).abs() < 1e-3);
+    assert!((beatgrid.terminal_marker.bpm - terminal_marker.bpm).abs() < 1e-3);
+
+    assert_eq!(xml.playlists.len(), 2);
+    assert_eq!(xml.playlists[1].path, ["French House"]);
+    assert_eq!(xml.playlists[1].track_ids.len(), 2);
+}
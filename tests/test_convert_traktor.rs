@@ -0,0 +1,288 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+#![cfg(feature = "traktor")]
+
+mod common;
+
+use common::{container_with_cues_and_loops, copy_library_database, usb_drive_library};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use triseratops::convert::traktor::{self, CueType, GridStrategy, Playlist, TraktorNml};
+use triseratops::library::Library;
+use triseratops::tag::color::Color;
+use triseratops::tag::generic::{Cue, Loop};
+use triseratops::tag::{TagContainer, TagFormat};
+
+fn write_nml(library: &Library, tags: &TagContainer, strategy: GridStrategy) -> String {
+    let tags = HashMap::from([(
+        PathBuf::from("CASSIUS_-_99_Keller 2016 RE-EDIT -.mp3"),
+        tags.clone(),
+    )]);
+    let mut output = vec![];
+    traktor::write_nml(&mut output, library, &tags, strategy).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn test_write_nml() {
+    let library = usb_drive_library();
+    let output = write_nml(
+        &library,
+        &container_with_cues_and_loops(),
+        GridStrategy::SingleMarker,
+    );
+
+    assert!(output.starts_with(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n<NML VERSION=\"19\">"
+    ));
+    assert!(output.contains("<COLLECTION ENTRIES=\"4\">"));
+    assert!(output.contains(
+        "<ENTRY TITLE=\"Big Love (Vaudafunk 2019 Reinterpretation)\" ARTIST=\"Pete Heller\">\n      \
+         <LOCATION DIR=\"/:\" FILE=\"Pete Heller - Big Love (Vaudafunk 2019 Reinterpretation).mp3\" \
+         VOLUME=\"usb_drive\" VOLUMEID=\"usb_drive\"/>\n      \
+         <INFO BITRATE=\"320000\" COMMENT=\"vaudafunk@gmail.com\" KEY=\"Am\" PLAYTIME=\"439\" \
+         IMPORT_DATE=\"2020/2/6\" FILESIZE=\"18936\"/>\n      \
+         <TEMPO BPM=\"123.000000\" BPM_QUALITY=\"100.000000\"/>"
+    ));
+
+    // The beatgrid is exported as a single grid marker with the average tempo.
+    assert!(output.contains("<TEMPO BPM=\"120.000000\" BPM_QUALITY=\"100.000000\"/>"));
+    assert_eq!(output.matches("TYPE=\"4\"").count(), 1);
+    assert!(output.contains(
+        "<CUE_V2 NAME=\"Beat Marker\" DISPL_ORDER=\"0\" TYPE=\"4\" START=\"500.000000\" \
+         LEN=\"0.000000\" REPEATS=\"-1\" HOTCUE=\"-1\"/>"
+    ));
+
+    // Cues keep their slot, saved loops are exported without a hotcue slot.
+    assert_eq!(output.matches("TYPE=\"0\"").count(), 6);
+    assert!(output.contains(
+        "<CUE_V2 NAME=\"After Intro\" DISPL_ORDER=\"0\" TYPE=\"0\" START=\"21124.000000\" \
+         LEN=\"0.000000\" REPEATS=\"-1\" HOTCUE=\"1\"/>"
+    ));
+    assert_eq!(output.matches("TYPE=\"5\"").count(), 3);
+    assert!(output.contains(
+        "<CUE_V2 NAME=\"Electric Guitar\" DISPL_ORDER=\"0\" TYPE=\"5\" START=\"100428.000000\" \
+         LEN=\"8348.000000\" REPEATS=\"-1\" HOTCUE=\"-1\"/>"
+    ));
+
+    // Subcrates
+    assert!(output.contains("<NODE TYPE=\"FOLDER\" NAME=\"$ROOT\">\n      <SUBNODES COUNT=\"2\">"));
+    assert!(output.contains(
+        "<NODE TYPE=\"PLAYLIST\" NAME=\"French House\">\n          \
+         <PLAYLIST ENTRIES=\"2\" TYPE=\"LIST\" UUID=\""
+    ));
+    assert!(output.contains(
+        "<PRIMARYKEY TYPE=\"TRACK\" KEY=\"usb_drive/:CASSIUS_-_99_Keller 2016 RE-EDIT -.mp3\"/>"
+    ));
+}
+
+#[test]
+fn test_write_nml_all_markers() {
+    let library = usb_drive_library();
+    let output = write_nml(
+        &library,
+        &container_with_cues_and_loops(),
+        GridStrategy::AllMarkers,
+    );
+
+    assert!(output.contains("<TEMPO BPM=\"120.000000\" BPM_QUALITY=\"100.000000\"/>"));
+    assert_eq!(output.matches("TYPE=\"4\"").count(), 2);
+    assert!(output.contains("TYPE=\"4\" START=\"500.000000\""));
+    assert!(output.contains("TYPE=\"4\" START=\"3500.000000\""));
+}
+
+#[test]
+fn test_parse_nml() {
+    let nml = TraktorNml::read_from_path("tests/data/traktor/synthetic.nml").unwrap();
+    assert_eq!(nml.entries.len(), 2);
+    let entry = nml
+        .entry("Macintosh HD/:Music/:Synthetic Artist - Synthetic Title.mp3")
+        .unwrap();
+    assert_eq!(entry.volume, "Macintosh HD");
+    assert_eq!(
+        entry.path,
+        Path::new("Music/Synthetic Artist - Synthetic Title.mp3")
+    );
+    assert_eq!(entry.title.as_deref(), Some("Synthetic Title"));
+    assert_eq!(entry.bpm, Some(124.0));
+    assert_eq!(entry.cues.len(), 11);
+    assert_eq!(entry.cues[7].cue_type, CueType::Loop);
+    assert_eq!(entry.cues[7].hotcue, 1);
+
+    let cues: Vec<_> = entry
+        .cues()
+        .into_iter()
+        .map(|cue| (cue.index, cue.position.millis, cue.label, cue.color))
+        .collect();
+    assert_eq!(
+        cues,
+        [
+            (0, 100, "Intro".to_string(), Color::default_hotcue_color(0)),
+            // The cue without hotcue slot is moved to the first free slot.
+            (
+                1,
+                90250,
+                "Break".to_string(),
+                Color::default_hotcue_color(1)
+            ),
+            (2, 62500, "Drop".to_string(), Color::default_hotcue_color(2)),
+        ]
+    );
+
+    let loops: Vec<_> = entry
+        .loops()
+        .into_iter()
+        .map(|saved_loop| {
+            (
+                saved_loop.index,
+                saved_loop.start_position.millis,
+                saved_loop.end_position.millis,
+                saved_loop.label,
+            )
+        })
+        .collect();
+    assert_eq!(
+        loops,
+        [
+            (0, 16000, 23742, "Hot Loop".to_string()),
+            (1, 180_000, 187_619, "Outro Loop".to_string()),
+        ]
+    );
+
+    let beatgrid = entry.beatgrid().unwrap();
+    assert_eq!(beatgrid.non_terminal_markers.len(), 1);
+    assert!((beatgrid.non_terminal_markers[0].position - 0.1).abs() < 1e-6);
+    assert_eq!(beatgrid.non_terminal_markers[0].beats_till_next_marker, 64);
+    assert!((beatgrid.terminal_marker.position - 31.068).abs() < 1e-6);
+    assert!((beatgrid.terminal_marker.bpm - 124.0).abs() < 1e-6);
+
+    let tags = entry.to_tag_container(TagFormat::ID3).unwrap();
+    assert_eq!(tags.cues().len(), 3);
+    assert_eq!(tags.loops().len(), 2);
+    assert!(tags.beatgrid().is_some());
+
+    // Entries without grid markers have no beatgrid.
+    let entry = nml.entry("Macintosh HD/:Music/:Other Title.mp3").unwrap();
+    assert!(entry.beatgrid().is_none());
+    assert!(nml.entry("Macintosh HD/:Music/:Missing.mp3").is_none());
+
+    // The lookup also works if the entries have been changed after parsing.
+    let mut reversed = nml.clone();
+    reversed.entries.reverse();
+    let entry = reversed
+        .entry("Macintosh HD/:Music/:Other Title.mp3")
+        .unwrap();
+    assert_eq!(entry.key, "Macintosh HD/:Music/:Other Title.mp3");
+
+    // Smart playlists are ignored.
+    assert_eq!(
+        nml.playlists,
+        [
+            Playlist {
+                path: vec!["Warmup".to_string()],
+                keys: vec![
+                    "Macintosh HD/:Music/:Other Title.mp3".to_string(),
+                    "Macintosh HD/:Music/:Synthetic Artist - Synthetic Title.mp3".to_string(),
+                ],
+            },
+            Playlist {
+                path: vec!["Gigs".to_string(), "Club / Late".to_string()],
+                keys: vec![
+                    "Macintosh HD/:Music/:Synthetic Artist - Synthetic Title.mp3".to_string()
+                ],
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_write_subcrates() {
+    let path = copy_library_database("traktor-import", "synthetic");
+    let mut library = Library::read_from_path(&*path).unwrap();
+    library.set_volume_root("/");
+
+    let nml = TraktorNml::read_from_path("tests/data/traktor/synthetic.nml").unwrap();
+    let names = nml.write_subcrates(&library).unwrap();
+    assert_eq!(names, ["Warmup", "Gigs%%Club - Late"]);
+
+    let mut subcrates: Vec<_> = library.subcrates().collect();
+    subcrates.sort();
+    assert_eq!(subcrates, ["Gigs", "Gigs%%Club - Late", "Warmup"]);
+    let subcrate = library.read_subcrate("Warmup").unwrap();
+    let track_paths: Vec<_> = subcrate.track_paths().collect();
+    assert_eq!(
+        track_paths,
+        [
+            Path::new("Music/Other Title.mp3"),
+            Path::new("Music/Synthetic Artist - Synthetic Title.mp3")
+        ]
+    );
+    let tracks: Vec<_> = library.subcrate("Gigs%%Club - Late").unwrap().collect();
+    assert_eq!(tracks.len(), 1);
+    assert_eq!(tracks[0].title.as_deref(), Some("Synthetic Title"));
+
+    // Tracks on other volumes are skipped.
+    library.set_volume_root(&*path);
+    let names = nml.write_subcrates(&library).unwrap();
+    assert_eq!(names, ["Warmup (2)", "Gigs%%Club - Late (2)"]);
+    let subcrate = library.read_subcrate("Warmup (2)").unwrap();
+    assert_eq!(subcrate.track_paths().count(), 0);
+}
+
+#[test]
+fn test_roundtrip() {
+    let library = usb_drive_library();
+    let tags = container_with_cues_and_loops();
+    let output = write_nml(&library, &tags, GridStrategy::AllMarkers);
+
+    let nml = TraktorNml::parse(output.as_bytes()).unwrap();
+    assert_eq!(nml.entries.len(), 4);
+    let entry = nml
+        .entry("usb_drive/:CASSIUS_-_99_Keller 2016 RE-EDIT -.mp3")
+        .unwrap();
+    assert_eq!(
+        entry.path,
+        Path::new("CASSIUS_-_99_Keller 2016 RE-EDIT -.mp3")
+    );
+
+    // Traktor doesn't store cue colors, so only positions and labels are preserved.
+    let cues = |cues: Vec<Cue>| -> Vec<_> {
+        cues.into_iter()
+            .map(|cue| (cue.index, cue.position.millis, cue.label))
+            .collect()
+    };
+    assert_eq!(cues(entry.cues()), cues(tags.cues()));
+
+    let loops = |loops: Vec<Loop>| -> Vec<_> {
+        let mut loops: Vec<_> = loops
+            .into_iter()
+            .map(|saved_loop| {
+                (
+                    saved_loop.start_position.millis,
+                    saved_loop.end_position.millis,
+                    saved_loop.label,
+                )
+            })
+            .collect();
+        loops.sort();
+        loops
+    };
+    assert_eq!(loops(entry.loops()), loops(tags.loops()));
+
+    // The marker positions are preserved, but all markers get the average tempo.
+    let beatgrid = entry.beatgrid().unwrap();
+    assert_eq!(beatgrid.non_terminal_markers.len(), 1);
+    assert_eq!(beatgrid.non_terminal_markers[0].beats_till_next_marker, 6);
+    assert!((beatgrid.terminal_marker.position - 3.5).abs() < 1e-3);
+    assert!((beatgrid.terminal_marker.bpm - 120.0).abs() < 1e-3);
+
+    assert_eq!(nml.playlists.len(), 2);
+    assert_eq!(nml.playlists[1].path, ["French House"]);
+    assert_eq!(nml.playlists[1].keys.len(), 2);
+}
@@ -0,0 +1,185 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+#![cfg(feature = "flac")]
+
+use std::path::PathBuf;
+use triseratops::tag::color::Color;
+use triseratops::tag::generic::{Cue, Position};
+use triseratops::tag::{TagContainer, TagFormat};
+
+const AUDIO_DATA: &[u8] = &[0xFF, 0xF8, 0x69, 0x08, 0x00, 0x00, 0x00, 0x00];
+
+fn metadata_block(block_type: u8, is_last: bool, data: &[u8]) -> Vec<u8> {
+    let header = if is_last {
+        block_type | 0x80
+    } else {
+        block_type
+    };
+    let mut block = vec![header];
+    block.extend_from_slice(&(data.len() as u32).to_be_bytes()[1..]);
+    block.extend_from_slice(data);
+    block
+}
+
+fn vorbis_comment(fields: &[&[u8]]) -> Vec<u8> {
+    let mut data = vec![];
+    data.extend_from_slice(&4u32.to_le_bytes());
+    data.extend_from_slice(b"test");
+    data.extend_from_slice(&(fields.len() as u32).to_le_bytes());
+    for field in fields {
+        data.extend_from_slice(&(field.len() as u32).to_le_bytes());
+        data.extend_from_slice(field);
+    }
+    data
+}
+
+/// Creates a file with a `STREAMINFO` block, the given `VORBIS_COMMENT` fields and `PADDING` block,
+/// followed by some fake audio data.
+fn create_file(name: &str, fields: Option<&[&[u8]]>, padding: Option<usize>) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("triseratops-{name}.flac"));
+    let mut data = b"fLaC".to_vec();
+    let streaminfo_is_last = fields.is_none() && padding.is_none();
+    data.extend(metadata_block(0, streaminfo_is_last, &[0; 34]));
+    if let Some(fields) = fields {
+        data.extend(metadata_block(
+            4,
+            padding.is_none(),
+            &vorbis_comment(fields),
+        ));
+    }
+    if let Some(length) = padding {
+        data.extend(metadata_block(1, true, &vec![0; length]));
+    }
+    data.extend_from_slice(AUDIO_DATA);
+    std::fs::write(&path, data).unwrap();
+    path
+}
+
+fn new_cue() -> Cue {
+    Cue {
+        index: 4,
+        position: Position { millis: 4000 },
+        color: Color {
+            red: 0xCC,
+            green: 0x00,
+            blue: 0x00,
+        },
+        label: "New".to_string(),
+    }
+}
+
+#[test]
+fn test_flac_file_roundtrip_with_padding() {
+    let mut markers2 = b"serato_markers_v2=".to_vec();
+    markers2.extend_from_slice(include_bytes!(
+        "data/tags/markers2/hotcues_and_loops.flac.bin"
+    ));
+    let fields: &[&[u8]] = &[b"TITLE=Some Title", &markers2, b"ARTIST=Some Artist"];
+    let path = create_file("roundtrip_with_padding", Some(fields), Some(4096));
+    let file_length = std::fs::metadata(&path).unwrap().len();
+
+    let mut tags = TagContainer::read_from_flac_path(&path).unwrap();
+    let num_cues = tags.cues().len();
+    assert!(num_cues > 0);
+    tags.set_cue(new_cue()).unwrap();
+    tags.write_to_flac_path(&path).unwrap();
+
+    // The padding has been reused, so the audio data has not been moved.
+    let data = std::fs::read(&path).unwrap();
+    assert_eq!(data.len() as u64, file_length);
+    assert!(data.ends_with(AUDIO_DATA));
+
+    let tags = TagContainer::read_from_flac_path(&path).unwrap();
+    assert_eq!(tags.cues().len(), num_cues + 1);
+    assert!(tags.cues().iter().any(|cue| cue.label == "New"));
+
+    // Other comments are kept in their original order.
+    let title = data
+        .windows(16)
+        .position(|window| window == b"TITLE=Some Title")
+        .unwrap();
+    let markers2 = data
+        .windows(18)
+        .position(|window| window == b"SERATO_MARKERS_V2=")
+        .unwrap();
+    let artist = data
+        .windows(18)
+        .position(|window| window == b"ARTIST=Some Artist")
+        .unwrap();
+    assert!(title < markers2 && markers2 < artist);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_flac_file_without_vorbis_comment() {
+    let path = create_file("without_vorbis_comment", None, None);
+
+    let tags = TagContainer::read_from_flac_path(&path).unwrap();
+    assert!(tags.cues().is_empty());
+
+    let mut tags = TagContainer::with_empty_tags(TagFormat::FLAC);
+    tags.set_cue(new_cue()).unwrap();
+    tags.write_to_flac_path(&path).unwrap();
+
+    let data = std::fs::read(&path).unwrap();
+    assert!(data.ends_with(AUDIO_DATA));
+
+    let tags = TagContainer::read_from_flac_path(&path).unwrap();
+    assert_eq!(tags.cues().len(), 1);
+    assert_eq!(tags.bpm_locked(), Some(false));
+    assert!(tags.analysis_version().is_some());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_flac_file_invalid() {
+    let path = std::env::temp_dir().join("triseratops-invalid.flac");
+    std::fs::write(&path, AUDIO_DATA).unwrap();
+    assert!(TagContainer::read_from_flac_path(&path).is_err());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_flac_file_multiple_vorbis_comments() {
+    let mut markers2 = b"SERATO_MARKERS_V2=".to_vec();
+    markers2.extend_from_slice(include_bytes!(
+        "data/tags/markers2/hotcues_and_loops.flac.bin"
+    ));
+    let path = std::env::temp_dir().join("triseratops-multiple_vorbis_comments.flac");
+    let mut data = b"fLaC".to_vec();
+    data.extend(metadata_block(0, false, &[0; 34]));
+    data.extend(metadata_block(
+        4,
+        false,
+        &vorbis_comment(&[b"TITLE=Some Title"]),
+    ));
+    data.extend(metadata_block(4, true, &vorbis_comment(&[&markers2])));
+    data.extend_from_slice(AUDIO_DATA);
+    std::fs::write(&path, data).unwrap();
+
+    let mut tags = TagContainer::read_from_flac_path(&path).unwrap();
+    let num_cues = tags.cues().len();
+    tags.set_cue(new_cue()).unwrap();
+    tags.write_to_flac_path(&path).unwrap();
+
+    // The Serato comments are only stored in the first block.
+    let data = std::fs::read(&path).unwrap();
+    assert_eq!(
+        data.windows(18)
+            .filter(|window| *window == b"SERATO_MARKERS_V2=")
+            .count(),
+        1
+    );
+    let tags = TagContainer::read_from_flac_path(&path).unwrap();
+    assert_eq!(tags.cues().len(), num_cues + 1);
+
+    std::fs::remove_file(path).unwrap();
+}
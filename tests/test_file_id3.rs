@@ -0,0 +1,108 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+#![cfg(feature = "id3")]
+
+use id3::TagLike;
+use id3::frame::EncapsulatedObject;
+use std::path::PathBuf;
+use triseratops::tag::color::Color;
+use triseratops::tag::generic::{Cue, Position};
+use triseratops::tag::{TagContainer, TagFormat};
+
+/// Creates a file with some fake audio data and the given ID3 tag in the temp directory.
+fn create_file(name: &str, tag: Option<&id3::Tag>) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("triseratops-{name}.mp3"));
+    std::fs::write(&path, [0xFF, 0xFB, 0x90, 0x00].repeat(256)).unwrap();
+    if let Some(tag) = tag {
+        tag.write_to_path(&path, id3::Version::Id3v23).unwrap();
+    }
+    path
+}
+
+#[test]
+fn test_id3_file_roundtrip() {
+    let mut tag = id3::Tag::new();
+    tag.set_title("Some Title");
+    tag.add_frame(EncapsulatedObject {
+        mime_type: "application/octet-stream".to_string(),
+        filename: String::new(),
+        description: "Serato Markers2".to_string(),
+        data: include_bytes!("data/tags/markers2/hotcues_with_names.id3.bin").to_vec(),
+    });
+    tag.add_frame(EncapsulatedObject {
+        mime_type: "application/octet-stream".to_string(),
+        filename: String::new(),
+        description: "Serato Markers_".to_string(),
+        data: include_bytes!("data/tags/markers/hotcues_with_names.id3.bin").to_vec(),
+    });
+    tag.add_frame(EncapsulatedObject {
+        mime_type: "application/octet-stream".to_string(),
+        filename: String::new(),
+        description: "Serato Offsets_".to_string(),
+        data: b"unknown".to_vec(),
+    });
+    let path = create_file("roundtrip", Some(&tag));
+
+    let mut tags = TagContainer::read_from_id3_path(&path).unwrap();
+    assert_eq!(tags.cues().len(), 3);
+    tags.set_cue(Cue {
+        index: 4,
+        position: Position { millis: 4000 },
+        color: Color {
+            red: 0xCC,
+            green: 0x00,
+            blue: 0x00,
+        },
+        label: "New".to_string(),
+    })
+    .unwrap();
+    tags.write_to_id3_path(&path).unwrap();
+
+    let tags = TagContainer::read_from_id3_path(&path).unwrap();
+    let cues = tags.cues();
+    assert_eq!(cues.len(), 4);
+    assert_eq!(cues[3].index, 4);
+    assert_eq!(cues[3].label, "New");
+
+    // Other frames must not be touched
+    let tag = id3::Tag::read_from_path(&path).unwrap();
+    assert_eq!(tag.version(), id3::Version::Id3v23);
+    assert_eq!(tag.title(), Some("Some Title"));
+    assert_eq!(tag.encapsulated_objects().count(), 3);
+    assert!(
+        tag.encapsulated_objects()
+            .any(|geob| geob.description == "Serato Offsets_" && geob.data == b"unknown")
+    );
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_id3_file_without_tag() {
+    let path = create_file("without_tag", None);
+
+    let tags = TagContainer::read_from_id3_path(&path).unwrap();
+    assert!(tags.cues().is_empty());
+    assert!(tags.bpm_locked().is_none());
+
+    let mut tags = TagContainer::with_empty_tags(TagFormat::ID3);
+    tags.set_bpm_locked(true).unwrap();
+    tags.write_to_id3_path(&path).unwrap();
+
+    let tags = TagContainer::read_from_id3_path(&path).unwrap();
+    assert_eq!(tags.bpm_locked(), Some(true));
+    assert!(tags.analysis_version().is_some());
+    assert!(
+        std::fs::read(&path)
+            .unwrap()
+            .ends_with(&[0xFF, 0xFB, 0x90, 0x00])
+    );
+
+    std::fs::remove_file(path).unwrap();
+}
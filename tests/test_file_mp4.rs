@@ -0,0 +1,192 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+#![cfg(feature = "mp4")]
+
+use std::path::PathBuf;
+use triseratops::tag::color::Color;
+use triseratops::tag::generic::{Cue, Position};
+use triseratops::tag::{TagContainer, TagFormat};
+
+const AUDIO_DATA: &[u8] = &[0x21, 0x10, 0x05, 0x20, 0xA4, 0x1B, 0xFF, 0xC0];
+
+fn atom(kind: &[u8], content: &[u8]) -> Vec<u8> {
+    let mut atom = ((content.len() + 8) as u32).to_be_bytes().to_vec();
+    atom.extend_from_slice(kind);
+    atom.extend_from_slice(content);
+    atom
+}
+
+fn full_atom(kind: &[u8], content: &[u8]) -> Vec<u8> {
+    let mut full_content = vec![0; 4];
+    full_content.extend_from_slice(content);
+    atom(kind, &full_content)
+}
+
+fn freeform_atom(mean: &str, name: &str, data: &[u8]) -> Vec<u8> {
+    let mut content = full_atom(b"mean", mean.as_bytes());
+    content.extend(full_atom(b"name", name.as_bytes()));
+    let mut data_content = vec![0, 0, 0, 1, 0, 0, 0, 0];
+    data_content.extend_from_slice(data);
+    content.extend(atom(b"data", &data_content));
+    atom(b"----", &content)
+}
+
+/// Creates a file with a `moov` atom (containing the given `udta` content) followed by an `mdat`
+/// atom with some fake audio data.
+fn create_file(name: &str, udta: Option<&[u8]>) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("triseratops-{name}.m4a"));
+    let ftyp = atom(b"ftyp", b"M4A \x00\x00\x00\x00M4A isom");
+    let mvhd = full_atom(b"mvhd", &[0; 96]);
+    let mut udta = udta
+        .map(|content| atom(b"udta", content))
+        .unwrap_or_default();
+    let stco_length = 8 + 4 + 8;
+    let trak_length = 4 * 8 + stco_length;
+    let moov_length = 8 + mvhd.len() + trak_length + udta.len();
+    let audio_offset = ftyp.len() + moov_length + 8;
+    let mut stco_content = 1u32.to_be_bytes().to_vec();
+    stco_content.extend_from_slice(&(audio_offset as u32).to_be_bytes());
+    let stco = full_atom(b"stco", &stco_content);
+    let trak = atom(
+        b"trak",
+        &atom(b"mdia", &atom(b"minf", &atom(b"stbl", &stco))),
+    );
+    let mut moov_content = mvhd;
+    moov_content.extend(trak);
+    moov_content.append(&mut udta);
+
+    let mut data = ftyp;
+    data.extend(atom(b"moov", &moov_content));
+    data.extend(atom(b"mdat", AUDIO_DATA));
+    std::fs::write(&path, data).unwrap();
+    path
+}
+
+/// Asserts that the chunk offset of the track points to the audio data.
+fn assert_chunk_offset(data: &[u8]) {
+    let position = data.windows(4).position(|kind| kind == b"stco").unwrap();
+    let offset = u32::from_be_bytes(data[position + 12..position + 16].try_into().unwrap());
+    assert!(data[offset as usize..].starts_with(AUDIO_DATA));
+}
+
+fn new_cue() -> Cue {
+    Cue {
+        index: 6,
+        position: Position { millis: 4000 },
+        color: Color {
+            red: 0xCC,
+            green: 0x00,
+            blue: 0x00,
+        },
+        label: "New".to_string(),
+    }
+}
+
+#[test]
+fn test_mp4_file_roundtrip_with_free_atom() {
+    let title = atom(
+        b"\xA9nam",
+        &atom(b"data", b"\x00\x00\x00\x01\x00\x00\x00\x00Title"),
+    );
+    let mut ilst = title.clone();
+    ilst.extend(freeform_atom(
+        "com.serato.dj",
+        "markers",
+        include_bytes!("data/tags/markers/hotcues_and_loops.mp4.bin"),
+    ));
+    ilst.extend(freeform_atom(
+        "com.serato.dj",
+        "markersv2",
+        include_bytes!("data/tags/markers2/hotcues_and_loops.mp4.bin"),
+    ));
+    ilst.extend(freeform_atom("com.apple.iTunes", "markersv2", b"unrelated"));
+    let mut meta = atom(b"hdlr", &[0; 25]);
+    meta.extend(atom(b"ilst", &ilst));
+    meta.extend(atom(b"free", &[0; 4096]));
+    let udta = full_atom(b"meta", &meta);
+    let path = create_file("roundtrip_with_free_atom", Some(&udta));
+    let file_length = std::fs::metadata(&path).unwrap().len();
+
+    let mut tags = TagContainer::read_from_mp4_path(&path).unwrap();
+    let num_cues = tags.cues().len();
+    assert!(num_cues > 0);
+    tags.set_cue(new_cue()).unwrap();
+    tags.write_to_mp4_path(&path).unwrap();
+
+    // The free atom has been reused, so the audio data has not been moved.
+    let data = std::fs::read(&path).unwrap();
+    assert_eq!(data.len() as u64, file_length);
+    assert_chunk_offset(&data);
+
+    let tags = TagContainer::read_from_mp4_path(&path).unwrap();
+    assert_eq!(tags.cues().len(), num_cues + 1);
+    assert!(tags.cues().iter().any(|cue| cue.label == "New"));
+
+    // Other atoms are kept as they are.
+    assert!(data.windows(title.len()).any(|window| window == title));
+    assert!(data.windows(9).any(|window| window == b"unrelated"));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_mp4_file_without_udta() {
+    let path = create_file("without_udta", None);
+
+    let tags = TagContainer::read_from_mp4_path(&path).unwrap();
+    assert!(tags.cues().is_empty());
+
+    let mut tags = TagContainer::with_empty_tags(TagFormat::MP4);
+    tags.set_cue(new_cue()).unwrap();
+    tags.write_to_mp4_path(&path).unwrap();
+
+    // The `moov` atom grew, so the chunk offsets need to be adjusted.
+    let data = std::fs::read(&path).unwrap();
+    assert!(data.ends_with(AUDIO_DATA));
+    assert_chunk_offset(&data);
+
+    let tags = TagContainer::read_from_mp4_path(&path).unwrap();
+    assert_eq!(tags.cues().len(), 1);
+    assert_eq!(tags.bpm_locked(), Some(false));
+    assert!(tags.analysis_version().is_some());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_mp4_file_invalid() {
+    let path = std::env::temp_dir().join("triseratops-invalid.m4a");
+    std::fs::write(&path, atom(b"mdat", AUDIO_DATA)).unwrap();
+    assert!(TagContainer::read_from_mp4_path(&path).is_err());
+    assert!(TagContainer::new().write_to_mp4_path(&path).is_err());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_mp4_file_fragmented() {
+    let path = std::env::temp_dir().join("triseratops-fragmented.m4a");
+    let mut moov_content = full_atom(b"mvhd", &[0; 96]);
+    moov_content.extend(atom(b"mvex", &full_atom(b"trex", &[0; 20])));
+    let mut data = atom(b"ftyp", b"M4A \x00\x00\x00\x00M4A isom");
+    data.extend(atom(b"moov", &moov_content));
+    data.extend(atom(b"moof", &full_atom(b"mfhd", &[0, 0, 0, 1])));
+    data.extend(atom(b"mdat", AUDIO_DATA));
+    std::fs::write(&path, &data).unwrap();
+
+    let mut tags = TagContainer::with_empty_tags(TagFormat::MP4);
+    tags.set_cue(new_cue()).unwrap();
+    assert!(matches!(
+        tags.write_to_mp4_path(&path),
+        Err(triseratops::error::Error::MP4FragmentedFile)
+    ));
+    // The file is left unchanged.
+    assert_eq!(std::fs::read(&path).unwrap(), data);
+
+    std::fs::remove_file(path).unwrap();
+}
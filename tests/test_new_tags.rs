@@ -0,0 +1,188 @@
+// Copyright (c) 2025 Jan Holthuis <jan.holthuis@rub.de>
+//
+// This Source Code Form is subject to the terms of the Mozilla Public License, v. 2.0. If a copy
+// of the MPL was not distributed with this file, You can obtain one at
+// http://mozilla.org/MPL/2.0/.
+//
+// SPDX-License-Identifier: MPL-2.0
+
+use std::io::Cursor;
+use triseratops::tag::format::{flac::FLACTag, id3::ID3Tag, mp4::MP4Tag, ogg::OggTag};
+use triseratops::tag::{
+    Analysis, Autotags, Beatgrid, Markers, Markers2, Overview, TagContainer, TagFormat,
+};
+
+/// Compares the written data with the data written by Serato.
+///
+/// The last two bytes of the base64 encoding in FLAC/MP4 tags is random, so they are ignored.
+fn assert_written(output: &[u8], expected: &[u8], tag_format: TagFormat) {
+    match tag_format {
+        TagFormat::FLAC | TagFormat::MP4 => {
+            assert_eq!(output.len(), expected.len());
+            assert_eq!(output[..output.len() - 2], expected[..expected.len() - 2]);
+        }
+        TagFormat::ID3 | TagFormat::Ogg => assert_eq!(output, expected),
+    }
+}
+
+#[test]
+fn test_new_analysis() {
+    let mut writer = Cursor::new(vec![]);
+    Analysis::new().write_id3(&mut writer).unwrap();
+    assert_written(
+        writer.get_ref(),
+        include_bytes!("data/tags/analysis/analyzed.id3.bin"),
+        TagFormat::ID3,
+    );
+
+    let mut writer = Cursor::new(vec![]);
+    Analysis::new().write_ogg(&mut writer).unwrap();
+    assert_written(
+        writer.get_ref(),
+        include_bytes!("data/tags/analysis/version_2_1.ogg.bin"),
+        TagFormat::Ogg,
+    );
+}
+
+#[test]
+fn test_new_autotags() {
+    let mut writer = Cursor::new(vec![]);
+    Autotags::new(115.0, -3.257, 0.0)
+        .write_id3(&mut writer)
+        .unwrap();
+    assert_written(
+        writer.get_ref(),
+        include_bytes!("data/tags/autotags/analyzed.id3.bin"),
+        TagFormat::ID3,
+    );
+}
+
+#[test]
+fn test_new_beatgrid() {
+    let beatgrid = Beatgrid::with_constant_bpm(f32::from_bits(0x3E9C2838), 115.0);
+    let mut writer = Cursor::new(vec![]);
+    beatgrid.write_id3(&mut writer).unwrap();
+    assert_written(
+        writer.get_ref(),
+        include_bytes!("data/tags/beatgrid/terminal_only_with_footer_0x00.id3.bin"),
+        TagFormat::ID3,
+    );
+}
+
+#[test]
+fn test_new_markers() {
+    let mut writer = Cursor::new(vec![]);
+    Markers::new().write_id3(&mut writer).unwrap();
+    assert_written(
+        writer.get_ref(),
+        include_bytes!("data/tags/markers/analyzed.id3.bin"),
+        TagFormat::ID3,
+    );
+
+    let mut writer = Cursor::new(vec![]);
+    Markers::new().write_mp4(&mut writer).unwrap();
+    assert_written(
+        writer.get_ref(),
+        include_bytes!("data/tags/markers/analyzed.mp4.bin"),
+        TagFormat::MP4,
+    );
+}
+
+#[test]
+fn test_new_markers2() {
+    let mut writer = Cursor::new(vec![]);
+    Markers2::new(TagFormat::ID3)
+        .write_id3(&mut writer)
+        .unwrap();
+    assert_written(
+        writer.get_ref(),
+        include_bytes!("data/tags/markers2/analyzed.id3.bin"),
+        TagFormat::ID3,
+    );
+
+    let mut writer = Cursor::new(vec![]);
+    Markers2::new(TagFormat::FLAC)
+        .write_flac(&mut writer)
+        .unwrap();
+    assert_written(
+        writer.get_ref(),
+        include_bytes!("data/tags/markers2/analyzed.flac.bin"),
+        TagFormat::FLAC,
+    );
+
+    let mut writer = Cursor::new(vec![]);
+    Markers2::new(TagFormat::MP4)
+        .write_mp4(&mut writer)
+        .unwrap();
+    assert_written(
+        writer.get_ref(),
+        include_bytes!("data/tags/markers2/analyzed.mp4.bin"),
+        TagFormat::MP4,
+    );
+
+    let mut writer = Cursor::new(vec![]);
+    Markers2::new(TagFormat::Ogg)
+        .write_ogg(&mut writer)
+        .unwrap();
+    assert_written(
+        writer.get_ref(),
+        include_bytes!("data/tags/markers2/analyzed.ogg.bin"),
+        TagFormat::Ogg,
+    );
+}
+
+#[test]
+fn test_new_overview() {
+    let input = include_bytes!("data/tags/overview/analyzed.id3.bin");
+    let data = Overview::parse_id3(input).unwrap().data;
+    let mut writer = Cursor::new(vec![]);
+    Overview::new(data).write_id3(&mut writer).unwrap();
+    assert_written(writer.get_ref(), input, TagFormat::ID3);
+}
+
+#[test]
+fn test_container_with_empty_tags() {
+    let mut tags = TagContainer::with_empty_tags(TagFormat::ID3);
+    assert!(tags.cues().is_empty());
+    assert!(tags.loops().is_empty());
+    assert_eq!(tags.bpm_locked(), Some(false));
+
+    tags.set_autotags(Autotags::new(115.0, -3.257, 0.0));
+    tags.set_beatgrid(Beatgrid::with_constant_bpm(0.0, 115.0));
+
+    let mut writer = Cursor::new(vec![]);
+    tags.write_analysis(&mut writer, TagFormat::ID3).unwrap();
+    assert_written(
+        writer.get_ref(),
+        include_bytes!("data/tags/analysis/analyzed.id3.bin"),
+        TagFormat::ID3,
+    );
+
+    let mut writer = Cursor::new(vec![]);
+    tags.write_markers(&mut writer, TagFormat::ID3).unwrap();
+    assert_written(
+        writer.get_ref(),
+        include_bytes!("data/tags/markers/analyzed.id3.bin"),
+        TagFormat::ID3,
+    );
+
+    let mut writer = Cursor::new(vec![]);
+    tags.write_markers2(&mut writer, TagFormat::ID3).unwrap();
+    assert_written(
+        writer.get_ref(),
+        include_bytes!("data/tags/markers2/analyzed.id3.bin"),
+        TagFormat::ID3,
+    );
+
+    let mut writer = Cursor::new(vec![]);
+    tags.write_autotags(&mut writer, TagFormat::ID3).unwrap();
+    assert_written(
+        writer.get_ref(),
+        include_bytes!("data/tags/autotags/analyzed.id3.bin"),
+        TagFormat::ID3,
+    );
+
+    let tags = TagContainer::with_empty_tags(TagFormat::FLAC);
+    let mut writer = Cursor::new(vec![]);
+    assert!(tags.write_markers(&mut writer, TagFormat::FLAC).is_err());
+}